@@ -0,0 +1,347 @@
+//! Batched, parallel embedding
+//!
+//! `EmbeddingsBuilder` embeds all documents in one call on one thread, which is slow
+//! once every adapter source chunk and every bug is indexed. Here the texts are split
+//! into batches of `embedding.batch_size` that a rayon pool of `embedding.threads`
+//! workers embeds concurrently. Texts are produced one wave of batches at a time, one
+//! batch per worker, so `embedding.memory_cap_mb` bounds the text held at once: each
+//! worker's batch closes early when its share is full. A progress callback is told
+//! about every finished batch.
+//!
+//! ONNX Runtime runs every batch on all cores, so workers overlap tokenization and
+//! session calls rather than add CPU; `models bench` measures what they gain.
+
+use anyhow::{anyhow, bail, Result};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use rig::{
+    embeddings::{to_texts, Embed, Embedding, EmbeddingModel as _},
+    OneOrMany,
+};
+use rig_fastembed::EmbeddingModel;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::debug;
+
+use crate::config::Config;
+
+/// How texts are split and embedded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchOptions {
+    pub batch_size: usize,
+    /// Batches embedded at once; 0 uses rayon's global pool, one worker per core
+    pub threads: usize,
+    /// Text held by all batches in flight, in bytes
+    pub memory_cap: usize,
+}
+
+impl BatchOptions {
+    /// Options from the `[embedding]` settings
+    pub fn from_settings() -> Self {
+        let settings = &Config::settings().embedding;
+        Self {
+            batch_size: settings.batch_size,
+            threads: settings.threads,
+            memory_cap: settings.memory_cap_mb.saturating_mul(1024 * 1024),
+        }
+    }
+
+    /// Threads the pool runs with
+    pub fn worker_threads(&self) -> usize {
+        if self.threads > 0 {
+            self.threads
+        } else {
+            rayon::current_num_threads()
+        }
+    }
+
+    /// Bytes of text one batch may hold, an equal share of the memory cap
+    pub fn batch_bytes(&self) -> usize {
+        (self.memory_cap / self.worker_threads()).max(1)
+    }
+}
+
+/// Embedding progress after a batch finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Progress {
+    pub embedded: usize,
+    pub total: usize,
+    pub batches_done: usize,
+    pub batches: usize,
+}
+
+/// Split texts of the given byte lengths into consecutive batches of at most
+/// `batch_size` texts and `batch_bytes` bytes; a longer text gets a batch of its own
+pub fn plan_batches(lengths: &[usize], batch_size: usize, batch_bytes: usize) -> Vec<Range<usize>> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut bytes = 0;
+    for (i, &len) in lengths.iter().enumerate() {
+        let full = i - start >= batch_size.max(1) || (i > start && bytes + len > batch_bytes);
+        if full {
+            batches.push(start..i);
+            start = i;
+            bytes = 0;
+        }
+        bytes += len;
+    }
+    if start < lengths.len() {
+        batches.push(start..lengths.len());
+    }
+    batches
+}
+
+/// Pools built for `threads` settings other than 0, kept for the life of the process
+static POOLS: Mutex<BTreeMap<usize, Arc<ThreadPool>>> = Mutex::new(BTreeMap::new());
+
+/// Run `work` on rayon's global pool when `threads` is 0, otherwise on a pool of that size
+fn install<R: Send>(threads: usize, work: impl FnOnce() -> R + Send) -> Result<R> {
+    if threads == 0 {
+        return Ok(work());
+    }
+    let pool = {
+        let mut pools = POOLS.lock().unwrap_or_else(|e| e.into_inner());
+        match pools.get(&threads) {
+            Some(pool) => pool.clone(),
+            None => {
+                let pool = Arc::new(ThreadPoolBuilder::new().num_threads(threads).build()?);
+                pools.insert(threads, pool.clone());
+                pool
+            }
+        }
+    };
+    Ok(pool.install(work))
+}
+
+/// Embed `texts` in parallel batches, returning one embedding per text in order
+///
+/// Blocks the calling thread; call it from `spawn_blocking` in async code.
+pub fn embed_texts(
+    model: &EmbeddingModel,
+    texts: &[String],
+    options: &BatchOptions,
+    progress: &(dyn Fn(Progress) + Sync),
+) -> Result<Vec<Embedding>> {
+    let lengths: Vec<usize> = texts.iter().map(String::len).collect();
+    embed_stream(
+        model,
+        &lengths,
+        texts.iter().cloned().map(Ok),
+        options,
+        progress,
+    )
+}
+
+/// Embed the texts `texts` yields, whose byte lengths are `lengths`, in parallel batches,
+/// returning one embedding per text in order
+///
+/// Only one wave of batches, one per worker, is taken from `texts` at a time.
+fn embed_stream(
+    model: &EmbeddingModel,
+    lengths: &[usize],
+    mut texts: impl Iterator<Item = Result<String>>,
+    options: &BatchOptions,
+    progress: &(dyn Fn(Progress) + Sync),
+) -> Result<Vec<Embedding>> {
+    let batches = plan_batches(lengths, options.batch_size, options.batch_bytes());
+    let workers = options.worker_threads();
+    debug!(
+        "Embedding {} texts in {} batches on {} threads",
+        lengths.len(),
+        batches.len(),
+        workers
+    );
+
+    let embedded = AtomicUsize::new(0);
+    let batches_done = AtomicUsize::new(0);
+    let mut embeddings = Vec::with_capacity(lengths.len());
+    for wave in batches.chunks(workers) {
+        let mut wave_texts = Vec::with_capacity(wave.len());
+        for range in wave {
+            let batch = texts
+                .by_ref()
+                .take(range.len())
+                .collect::<Result<Vec<String>>>()?;
+            if batch.len() != range.len() {
+                bail!("Fewer texts to embed than planned");
+            }
+            wave_texts.push(batch);
+        }
+
+        let results: Vec<Vec<Embedding>> = install(options.threads, || {
+            wave_texts
+                .into_par_iter()
+                .map(|batch| {
+                    let count = batch.len();
+                    let embeddings = futures::executor::block_on(model.embed_texts(batch))?;
+                    progress(Progress {
+                        embedded: embedded.fetch_add(count, Ordering::SeqCst) + count,
+                        total: lengths.len(),
+                        batches_done: batches_done.fetch_add(1, Ordering::SeqCst) + 1,
+                        batches: batches.len(),
+                    });
+                    Ok(embeddings)
+                })
+                .collect::<Result<Vec<_>>>()
+        })??;
+        embeddings.extend(results.into_iter().flatten());
+    }
+    Ok(embeddings)
+}
+
+/// Embed `documents` like `EmbeddingsBuilder`, in parallel batches, off the async runtime
+pub async fn embed_documents<T>(
+    model: &EmbeddingModel,
+    documents: Vec<T>,
+    options: BatchOptions,
+    progress: impl Fn(Progress) + Send + Sync + 'static,
+) -> Result<Vec<(T, OneOrMany<Embedding>)>>
+where
+    T: Embed + Send + 'static,
+{
+    if documents.is_empty() {
+        return Ok(Vec::new());
+    }
+    let model = model.clone();
+    tokio::task::spawn_blocking(move || -> Result<Vec<(T, OneOrMany<Embedding>)>> {
+        // Plan on text lengths first, then render the texts again wave by wave
+        let mut lengths = Vec::new();
+        let mut counts = Vec::with_capacity(documents.len());
+        for document in &documents {
+            let document_texts = to_texts(document)?;
+            if document_texts.is_empty() {
+                bail!("A document has no text to embed");
+            }
+            counts.push(document_texts.len());
+            lengths.extend(document_texts.iter().map(String::len));
+        }
+        let texts = documents
+            .iter()
+            .flat_map(|document| match to_texts(document) {
+                Ok(texts) => texts.into_iter().map(Ok).collect::<Vec<_>>(),
+                Err(e) => vec![Err(e.into())],
+            });
+
+        let mut embeddings =
+            embed_stream(&model, &lengths, texts, &options, &progress)?.into_iter();
+        documents
+            .into_iter()
+            .zip(counts)
+            .map(|(document, count)| {
+                let many = OneOrMany::many(embeddings.by_ref().take(count).collect::<Vec<_>>())
+                    .map_err(|_| anyhow!("Embedding model returned fewer embeddings than texts"))?;
+                Ok((document, many))
+            })
+            .collect()
+    })
+    .await?
+}
+
+/// Embed with the `[embedding]` batch settings, logging progress at debug level
+pub async fn embed_configured<T>(
+    model: &EmbeddingModel,
+    documents: Vec<T>,
+) -> Result<Vec<(T, OneOrMany<Embedding>)>>
+where
+    T: Embed + Send + 'static,
+{
+    embed_documents(
+        model,
+        documents,
+        BatchOptions::from_settings(),
+        |p: Progress| {
+            debug!(
+                "Embedded {}/{} texts ({}/{} batches)",
+                p.embedded, p.total, p.batches_done, p.batches
+            )
+        },
+    )
+    .await
+}
+
+/// Throughput of one benchmark run
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchResult {
+    pub documents: usize,
+    pub batch_size: usize,
+    /// Batches embedded at once, not the cores ONNX Runtime uses
+    pub workers: usize,
+    pub batches: usize,
+    pub seconds: f64,
+    pub docs_per_second: f64,
+}
+
+/// Embed `texts` with `options` and measure documents per second
+pub fn benchmark(
+    model: &EmbeddingModel,
+    texts: &[String],
+    options: &BatchOptions,
+) -> Result<BenchResult> {
+    let batches = AtomicUsize::new(0);
+    let started = Instant::now();
+    embed_texts(model, texts, options, &|p: Progress| {
+        batches.store(p.batches, Ordering::SeqCst)
+    })?;
+    let seconds = started.elapsed().as_secs_f64();
+    Ok(BenchResult {
+        documents: texts.len(),
+        batch_size: options.batch_size,
+        workers: options.worker_threads(),
+        batches: batches.load(Ordering::SeqCst),
+        seconds,
+        docs_per_second: if seconds > 0.0 {
+            texts.len() as f64 / seconds
+        } else {
+            0.0
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_batches() {
+        assert_eq!(plan_batches(&[10; 5], 2, 1000), vec![0..2, 2..4, 4..5]);
+        // The byte cap closes a batch early; an oversized text still gets embedded alone
+        assert_eq!(
+            plan_batches(&[40, 40, 40, 500, 10], 10, 100),
+            vec![0..2, 2..3, 3..4, 4..5]
+        );
+        assert!(plan_batches(&[], 8, 100).is_empty());
+    }
+
+    #[test]
+    fn test_batch_bytes_share_the_cap() {
+        let options = BatchOptions {
+            batch_size: 64,
+            threads: 4,
+            memory_cap: 1000,
+        };
+        assert_eq!(options.batch_bytes(), 250);
+        assert!(
+            BatchOptions {
+                threads: 0,
+                ..options
+            }
+            .worker_threads()
+                >= 1
+        );
+    }
+
+    #[test]
+    fn test_pools_are_reused() {
+        assert_eq!(install(3, rayon::current_num_threads).unwrap(), 3);
+        assert_eq!(install(3, rayon::current_num_threads).unwrap(), 3);
+        assert_eq!(
+            install(0, rayon::current_num_threads).unwrap(),
+            rayon::current_num_threads()
+        );
+        assert_eq!(POOLS.lock().unwrap().keys().filter(|&&n| n == 3).count(), 1);
+    }
+}
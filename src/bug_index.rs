@@ -0,0 +1,422 @@
+//! Semantic index over stored bugs
+//!
+//! Every bug record in the bugs directory is embedded (description, code sample
+//! and fix suggestion) into a collection separate from the seeded bug patterns.
+//! The index is reconciled with the directory before each search and after bugs
+//! are written, re-embedding only records whose text changed and dropping
+//! records whose files were deleted, so results never point at stale bugs.
+//! The same text is kept in a keyword index so searches for identifiers find
+//! the bugs that mention them; see `hybrid`.
+
+use anyhow::Result;
+use rig::{
+    embeddings::Embedding,
+    vector_store::{
+        in_memory_store::InMemoryVectorStore, request::VectorSearchRequest, VectorStoreIndex,
+    },
+    Embed, OneOrMany,
+};
+use rig_fastembed::EmbeddingModel;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tokio::sync::{OnceCell, RwLock};
+use tracing::{debug, info};
+
+use crate::batch_embed;
+use crate::bug_store::{self, BugFilter, StoredBug};
+use crate::config::Config;
+use crate::hybrid::{self, LexicalIndex, ScoreBreakdown, SearchMode};
+use crate::index_snapshot;
+
+/// Text of a bug that is embedded
+#[derive(Embed, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BugDocument {
+    pub bug_id: String,
+    #[embed]
+    pub text: String,
+}
+
+impl BugDocument {
+    pub fn from_bug(bug: &StoredBug) -> Self {
+        let mut text = bug.description.trim().to_string();
+        if let Some(code) = bug.code_sample.as_deref().filter(|c| !c.trim().is_empty()) {
+            text.push_str("\n\nCode:\n");
+            text.push_str(code.trim());
+        }
+        if let Some(fix) = bug
+            .fix_suggestion
+            .as_deref()
+            .filter(|f| !f.trim().is_empty())
+        {
+            text.push_str("\n\nFix:\n");
+            text.push_str(fix.trim());
+        }
+        Self {
+            bug_id: bug.bug_id.clone(),
+            text,
+        }
+    }
+}
+
+struct IndexedBug {
+    bug: StoredBug,
+    document: BugDocument,
+    embedding: OneOrMany<Embedding>,
+}
+
+/// What a sync changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SyncSummary {
+    pub embedded: usize,
+    pub removed: usize,
+    pub total: usize,
+}
+
+/// A stored bug matching a search
+#[derive(Debug, Clone, Serialize)]
+pub struct BugHit {
+    /// Cosine similarity to the query
+    pub score: f64,
+    /// Fused or re-rank score the order is based on
+    pub rank_score: f64,
+    pub scores: ScoreBreakdown,
+    pub bug_id: String,
+    pub severity: String,
+    pub adapter: String,
+    pub status: String,
+    pub file: Option<String>,
+    pub line: Option<u64>,
+    pub description: String,
+}
+
+/// Embeddings of every stored bug, keyed by bug ID
+pub struct BugIndex {
+    embedding_model: EmbeddingModel,
+    entries: BTreeMap<String, IndexedBug>,
+    lexical: LexicalIndex,
+}
+
+impl BugIndex {
+    pub fn new(embedding_model: EmbeddingModel) -> Result<Self> {
+        Ok(Self {
+            embedding_model,
+            entries: BTreeMap::new(),
+            lexical: LexicalIndex::new()?,
+        })
+    }
+
+    /// Bring the index in line with the bug records in `bugs_dir`
+    pub async fn sync(&mut self, bugs_dir: &Path) -> Result<SyncSummary> {
+        let mut current: BTreeMap<String, StoredBug> = BTreeMap::new();
+        for (_, bug) in bug_store::load_bugs(bugs_dir).await? {
+            current.insert(bug.bug_id.clone(), bug);
+        }
+
+        let deleted: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|bug_id| !current.contains_key(*bug_id))
+            .cloned()
+            .collect();
+        for bug_id in &deleted {
+            self.entries.remove(bug_id);
+            self.lexical.remove(bug_id)?;
+        }
+        let removed = deleted.len();
+
+        let mut changed = Vec::new();
+        for (bug_id, bug) in current {
+            let document = BugDocument::from_bug(&bug);
+            match self.entries.get_mut(&bug_id) {
+                // Metadata such as status changes without re-embedding
+                Some(entry) if entry.document == document => entry.bug = bug,
+                _ => changed.push((document, bug)),
+            }
+        }
+
+        let embedded = changed.len();
+        if !changed.is_empty() {
+            // Bugs imported with `index import` keep their embeddings when made by this model
+            let cache = index_snapshot::load_cache();
+            let mut missing = Vec::new();
+            for (document, bug) in changed {
+                match cache.bug_embedding(&self.embedding_model.model, &document) {
+                    Some(embedding) => self.insert(document, bug, embedding)?,
+                    None => missing.push((document, bug)),
+                }
+            }
+            if !missing.is_empty() {
+                let (documents, bugs): (Vec<BugDocument>, Vec<StoredBug>) =
+                    missing.into_iter().unzip();
+                let embeddings =
+                    batch_embed::embed_configured(&self.embedding_model, documents).await?;
+                for ((document, embedding), bug) in embeddings.into_iter().zip(bugs) {
+                    self.insert(document, bug, embedding)?;
+                }
+            }
+        }
+
+        if embedded > 0 || removed > 0 {
+            debug!(
+                "Bug index synced: {} embedded, {} removed, {} total",
+                embedded,
+                removed,
+                self.entries.len()
+            );
+        }
+        Ok(SyncSummary {
+            embedded,
+            removed,
+            total: self.entries.len(),
+        })
+    }
+
+    fn insert(
+        &mut self,
+        document: BugDocument,
+        bug: StoredBug,
+        embedding: OneOrMany<Embedding>,
+    ) -> Result<()> {
+        self.lexical.upsert(&document.bug_id, &document.text)?;
+        self.entries.insert(
+            document.bug_id.clone(),
+            IndexedBug {
+                bug,
+                document,
+                embedding,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stored bugs passing `filter` with their embedding vectors, in bug ID order
+    pub fn vectors(&self, filter: &BugFilter) -> Vec<(StoredBug, Vec<f64>)> {
+        self.entries
+            .values()
+            .filter(|entry| filter.matches(&entry.bug))
+            .map(|entry| (entry.bug.clone(), entry.embedding.first().vec))
+            .collect()
+    }
+
+    /// Bugs best matching `query` among those passing `filter`, by fused vector and keyword rank
+    pub async fn search(
+        &self,
+        query: &str,
+        filter: &BugFilter,
+        limit: usize,
+    ) -> Result<Vec<BugHit>> {
+        let hits = self
+            .ranked_search(query, filter, limit, SearchMode::Hybrid, true)
+            .await?
+            .into_iter()
+            .filter_map(|(bug_id, scores)| {
+                let bug = &self.entries.get(&bug_id)?.bug;
+                Some(BugHit {
+                    score: scores.vector_score.unwrap_or_default(),
+                    rank_score: scores.score(),
+                    scores,
+                    bug_id: bug.bug_id.clone(),
+                    severity: bug.severity.clone(),
+                    adapter: bug.adapter(),
+                    status: bug.status.to_string(),
+                    file: bug.source_path().map(str::to_string),
+                    line: bug.line_number(),
+                    description: bug.description.clone(),
+                })
+            })
+            .collect();
+        Ok(hits)
+    }
+
+    /// IDs of the bugs passing `filter` that best match `query` under `mode`, best first
+    ///
+    /// `rerank` applies the configured cross-encoder, if any, to the candidates.
+    pub async fn ranked_search(
+        &self,
+        query: &str,
+        filter: &BugFilter,
+        limit: usize,
+        mode: SearchMode,
+        rerank: bool,
+    ) -> Result<Vec<(String, ScoreBreakdown)>> {
+        let candidates: Vec<(BugDocument, OneOrMany<Embedding>)> = self
+            .entries
+            .values()
+            .filter(|entry| filter.matches(&entry.bug))
+            .map(|entry| (entry.document.clone(), entry.embedding.clone()))
+            .collect();
+        if candidates.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let pool = Config::settings().embedding.search_candidates.max(limit);
+        let index_size = candidates.len();
+
+        let index =
+            InMemoryVectorStore::from_documents_with_id_f(candidates, |doc| doc.bug_id.clone())
+                .index(self.embedding_model.clone());
+        // Score every candidate so keyword-only hits report their similarity too
+        let req = VectorSearchRequest::builder()
+            .query(query)
+            .samples(index_size as u64)
+            .build()?;
+        let mut vector: Vec<(String, f64)> = index
+            .top_n::<BugDocument>(req)
+            .await?
+            .into_iter()
+            .map(|(score, _id, doc)| (doc.bug_id, score))
+            .collect();
+        let similarity: HashMap<String, f64> = vector.iter().cloned().collect();
+        vector.truncate(pool);
+
+        // The keyword index covers every bug, so over-fetch before filtering
+        let lexical: Vec<(String, f64)> = self
+            .lexical
+            .search(query, self.entries.len())?
+            .into_iter()
+            .filter(|(bug_id, _)| {
+                self.entries
+                    .get(bug_id)
+                    .is_some_and(|entry| filter.matches(&entry.bug))
+            })
+            .take(pool)
+            .collect();
+
+        let mut fused = mode.fuse(&vector, &lexical);
+        hybrid::fill_similarity(&mut fused, &similarity);
+        fused.truncate(pool);
+        if rerank {
+            let texts = fused
+                .iter()
+                .map(|(bug_id, _)| {
+                    self.entries
+                        .get(bug_id)
+                        .map(|entry| entry.document.text.clone())
+                        .unwrap_or_default()
+                })
+                .collect();
+            hybrid::rerank(query, &mut fused, texts).await?;
+        }
+        fused.truncate(limit);
+        Ok(fused)
+    }
+}
+
+static SHARED: OnceCell<RwLock<BugIndex>> = OnceCell::const_new();
+
+/// Bug index shared by the CLI, the analysis pipeline and every MCP session, built on first use
+pub async fn shared() -> Result<&'static RwLock<BugIndex>> {
+    SHARED
+        .get_or_try_init(|| async {
+            let mut index = BugIndex::new(crate::models::shared_embedding_model().await?)?;
+            let summary = index.sync(&Config::bugs_directory_path()).await?;
+            info!("✅ Bug index built with {} stored bugs", summary.total);
+            Ok::<_, anyhow::Error>(RwLock::new(index))
+        })
+        .await
+}
+
+/// Sync the shared index after bugs were stored, updated or deleted
+///
+/// A no-op until something searched the index.
+pub async fn refresh() -> Result<()> {
+    if let Some(index) = SHARED.get() {
+        index
+            .write()
+            .await
+            .sync(&Config::bugs_directory_path())
+            .await?;
+    }
+    Ok(())
+}
+
+/// Search stored bugs, syncing the shared index with the bugs directory first
+pub async fn search(query: &str, filter: &BugFilter, limit: usize) -> Result<Vec<BugHit>> {
+    let index = shared().await?;
+    index
+        .write()
+        .await
+        .sync(&Config::bugs_directory_path())
+        .await?;
+    let hits = index.read().await.search(query, filter, limit).await?;
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bug_store::test_bug;
+    use serde_json::json;
+
+    #[test]
+    fn test_document_text_includes_code_and_fix() {
+        let mut stored = test_bug(
+            json!({"bug_id": "BUG_1", "severity": "HIGH", "description": "Price parsed through f64"}),
+        );
+        stored.code_sample = Some("let px = s.parse::<f64>()?;".to_string());
+        stored.fix_suggestion = Some("  ".to_string());
+        let document = BugDocument::from_bug(&stored);
+        assert!(document
+            .text
+            .starts_with("Price parsed through f64\n\nCode:\nlet px"));
+        assert!(!document.text.contains("Fix:"));
+    }
+
+    #[tokio::test]
+    async fn test_sync_tracks_store_update_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, bug: &StoredBug| {
+            std::fs::write(dir.path().join(name), serde_json::to_string(bug).unwrap()).unwrap()
+        };
+        write(
+            "AUTO_BUG_1.json",
+            &test_bug(
+                json!({"bug_id": "BUG_1", "severity": "HIGH", "description": "Price precision lost converting to f64"}),
+            ),
+        );
+        let websocket = json!({"bug_id": "BUG_2", "severity": "LOW", "description": "Websocket reconnect drops subscriptions"});
+        write("AUTO_BUG_2.json", &test_bug(websocket.clone()));
+
+        let mut index =
+            BugIndex::new(crate::models::shared_embedding_model().await.unwrap()).unwrap();
+        let summary = index.sync(dir.path()).await.unwrap();
+        assert_eq!(
+            (summary.embedded, summary.removed, summary.total),
+            (2, 0, 2)
+        );
+
+        // Status changes do not re-embed; text changes and deletions do
+        let mut fixed = test_bug(websocket);
+        fixed.status = bug_store::BugStatus::Fixed;
+        write("AUTO_BUG_2.json", &fixed);
+        assert_eq!(index.sync(dir.path()).await.unwrap().embedded, 0);
+        std::fs::remove_file(dir.path().join("AUTO_BUG_1.json")).unwrap();
+        let summary = index.sync(dir.path()).await.unwrap();
+        assert_eq!(
+            (summary.embedded, summary.removed, summary.total),
+            (0, 1, 1)
+        );
+
+        let active = BugFilter {
+            status: Some("active".to_string()),
+            ..Default::default()
+        };
+        assert!(index
+            .search("websocket", &active, 5)
+            .await
+            .unwrap()
+            .is_empty());
+        let hits = index
+            .search("websocket", &BugFilter::default(), 5)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].bug_id, "BUG_2");
+        assert_eq!(hits[0].status, "fixed");
+        assert!(hits[0].scores.vector_rank.is_some());
+        let hits = index
+            .search("subscriptions", &BugFilter::default(), 5)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].scores.lexical_rank, Some(1));
+    }
+}
@@ -0,0 +1,891 @@
+//! Bug store and lifecycle tracking for Nautilus Trader Rig
+//!
+//! This module reads and writes the bug records kept in the bugs directory and
+//! tracks their lifecycle between analysis runs. Each bug carries a fingerprint
+//! derived from its source location so that findings from a new run can be matched
+//! against previously stored bugs and moved between `open`, `fixed` and `regressed`.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::fs as async_fs;
+use tracing::{debug, info, warn};
+
+use crate::repo_paths::to_repo_relative;
+
+/// Lines between a finding and a stored bug in the same file for them to count as one
+pub const FALLBACK_LINE_DISTANCE: u64 = 10;
+
+/// Lifecycle status of a stored bug
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BugStatus {
+    #[default]
+    Open,
+    Fixed,
+    Regressed,
+}
+
+impl BugStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BugStatus::Open => "open",
+            BugStatus::Fixed => "fixed",
+            BugStatus::Regressed => "regressed",
+        }
+    }
+
+    /// Whether the bug still needs attention
+    pub fn is_active(&self) -> bool {
+        matches!(self, BugStatus::Open | BugStatus::Regressed)
+    }
+}
+
+impl std::fmt::Display for BugStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single status transition recorded on a bug
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatusChange {
+    pub status: BugStatus,
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Bug record as stored in `bugs/*.json`
+///
+/// Fields not modelled here are preserved in `extra` so that rewriting a record
+/// never drops data written by other tools.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StoredBug {
+    pub bug_id: String,
+    #[serde(default)]
+    pub severity: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub adapter_name: Option<String>,
+    #[serde(default)]
+    pub code_sample: Option<String>,
+    #[serde(default)]
+    pub fix_suggestion: Option<String>,
+    #[serde(default)]
+    pub timestamp: String,
+    #[serde(default)]
+    pub status: BugStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub affected_functions: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status_history: Vec<StatusChange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_location: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_info: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl StoredBug {
+    /// Source file path recorded for this bug, relative to the repository root when available
+    pub fn source_path(&self) -> Option<&str> {
+        self.location_detail("relative_path")
+            .or_else(|| self.location_detail("absolute_path"))
+            .and_then(|v| v.as_str())
+    }
+
+    /// SHA-256 of the source file at the time the bug was recorded
+    pub fn recorded_source_hash(&self) -> Option<&str> {
+        self.location_detail("source_sha256")
+            .and_then(|v| v.as_str())
+    }
+
+    /// Modification time of the source file at the time the bug was recorded
+    pub fn recorded_mtime(&self) -> Option<u64> {
+        self.location_detail("last_modified_timestamp")
+            .and_then(|v| v.as_u64())
+    }
+
+    fn location_detail(&self, key: &str) -> Option<&serde_json::Value> {
+        self.file_location.as_ref()?.get("details")?.get(key)
+    }
+
+    /// Approximate 1-based line number of the code sample, if it was located
+    pub fn line_number(&self) -> Option<u64> {
+        self.file_location
+            .as_ref()?
+            .get("source_location")?
+            .get("approximate_line_number")?
+            .as_u64()
+    }
+
+    /// Adapter the bug belongs to, taken from `crates/adapters/<adapter>/` when available
+    pub fn adapter(&self) -> String {
+        self.source_path()
+            .map(to_repo_relative)
+            .and_then(|path| adapter_from_path(&path))
+            .or_else(|| self.adapter_name.clone())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// Bug category, either stored explicitly or derived from the description
+    pub fn category(&self) -> &'static str {
+        if let Some(category) = self.extra.get("category").and_then(|c| c.as_str()) {
+            if let Some((known, _)) = CATEGORY_KEYWORDS
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(category))
+            {
+                return known;
+            }
+        }
+        classify_category(&self.description)
+    }
+
+    /// Whether the bug was confirmed by a reviewer or a confirmation pass
+    pub fn is_confirmed(&self) -> bool {
+        self.extra
+            .get("confirmed")
+            .and_then(|c| c.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Time the bug was first recorded, parsed from its `timestamp` or first status change
+    pub fn recorded_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let parse = |value: &str| {
+            chrono::DateTime::parse_from_rfc3339(value)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .ok()
+                .or_else(|| {
+                    chrono::NaiveDateTime::parse_from_str(value, "%Y%m%d_%H%M%S")
+                        .ok()
+                        .map(|t| t.and_utc())
+                })
+        };
+        parse(&self.timestamp).or_else(|| {
+            self.status_history
+                .first()
+                .and_then(|c| parse(&c.timestamp))
+        })
+    }
+
+    /// Numeric rank of the bug severity
+    pub fn severity_rank(&self) -> u8 {
+        severity_rank(&self.severity)
+    }
+
+    /// Fingerprint of this bug, computing it from the record when it was never stored
+    pub fn effective_fingerprint(&self) -> String {
+        if let Some(fingerprint) = &self.fingerprint {
+            return fingerprint.clone();
+        }
+        match self.source_path() {
+            Some(path) => {
+                compute_fingerprint(path, self.affected_functions.as_deref().unwrap_or(""))
+            }
+            None => compute_fingerprint(
+                &format!(
+                    "unknown/{}",
+                    self.adapter_name.as_deref().unwrap_or("unknown")
+                ),
+                &self.description,
+            ),
+        }
+    }
+
+    /// Location key shared by every bug in the same source file
+    pub fn location_key(&self) -> Option<String> {
+        self.source_path().map(to_repo_relative)
+    }
+
+    /// Whether a finding in the same file is plausibly this bug
+    fn is_near(&self, site: &FindingSite) -> bool {
+        let functions = normalize_functions(self.affected_functions.as_deref().unwrap_or(""));
+        let shares_function = normalize_functions(&site.affected_functions)
+            .iter()
+            .any(|function| functions.contains(function));
+        let close_lines = match (self.line_number(), site.line_number) {
+            (Some(a), Some(b)) => a.abs_diff(b) <= FALLBACK_LINE_DISTANCE,
+            _ => false,
+        };
+        shares_function || close_lines
+    }
+
+    /// Record the source hash, modification time and line seen by the latest run
+    pub fn refresh_location(&mut self, site: &FindingSite) {
+        let location = self
+            .file_location
+            .get_or_insert_with(|| serde_json::json!({ "details": {}, "source_location": {} }));
+        let Some(location) = location.as_object_mut() else {
+            return;
+        };
+        let details = location
+            .entry("details")
+            .or_insert_with(|| serde_json::json!({}));
+        if let Some(details) = details.as_object_mut() {
+            details.insert(
+                "source_sha256".to_string(),
+                site.content_hash.clone().into(),
+            );
+            match site.last_modified {
+                Some(mtime) => details.insert("last_modified_timestamp".to_string(), mtime.into()),
+                None => details.shift_remove("last_modified_timestamp"),
+            };
+        }
+        if let Some(line) = site.line_number {
+            let source_location = location
+                .entry("source_location")
+                .or_insert_with(|| serde_json::json!({}));
+            if let Some(source_location) = source_location.as_object_mut() {
+                source_location.insert("approximate_line_number".to_string(), line.into());
+            }
+        }
+    }
+
+    /// Mark the bug as confirmed by a reviewer or a confirmation pass
+    pub fn mark_confirmed(&mut self, source: &str) {
+        self.extra
+            .insert("confirmed".to_string(), serde_json::Value::Bool(true));
+        self.extra.insert(
+            "confirmed_at".to_string(),
+            chrono::Utc::now().to_rfc3339().into(),
+        );
+        self.extra.insert("confirmed_by".to_string(), source.into());
+    }
+
+    /// Record a status transition
+    pub fn transition(
+        &mut self,
+        status: BugStatus,
+        commit_hash: Option<String>,
+        note: Option<String>,
+    ) {
+        self.status = status;
+        self.status_history.push(StatusChange {
+            status,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            commit_hash,
+            note,
+        });
+    }
+}
+
+/// Bug record for tests, built from the fields given as JSON
+#[cfg(test)]
+pub(crate) fn test_bug(fields: serde_json::Value) -> StoredBug {
+    serde_json::from_value(fields).unwrap()
+}
+
+/// Bug categories and the description keywords that identify them, checked in order
+pub const CATEGORY_KEYWORDS: &[(&str, &[&str])] = &[
+    (
+        "MEMORY",
+        &[
+            "memory safety",
+            "c_char",
+            "null pointer",
+            "dangling",
+            "use after free",
+            "buffer",
+        ],
+    ),
+    (
+        "OVERFLOW",
+        &[
+            "overflow",
+            "underflow",
+            "integer conversion",
+            "truncat",
+            "wrap around",
+        ],
+    ),
+    (
+        "PRECISION",
+        &[
+            "precision",
+            "f64",
+            "floating-point",
+            "floating point",
+            "rounding",
+            "decimals",
+        ],
+    ),
+    (
+        "AUTHENTICATION",
+        &[
+            "api key",
+            "credential",
+            "secret",
+            "authentication",
+            "plain text",
+        ],
+    ),
+    (
+        "ERROR_HANDLING",
+        &["panic", "unwrap", "expect(", "error handling", "poisoned"],
+    ),
+    (
+        "CONCURRENCY",
+        &[
+            "race condition",
+            "deadlock",
+            "concurrent",
+            "lock contention",
+        ],
+    ),
+    (
+        "VALIDATION",
+        &[
+            "validation",
+            "validate",
+            "path traversal",
+            "unchecked",
+            "malformed",
+        ],
+    ),
+    (
+        "PERFORMANCE",
+        &["performance", "latency", "bottleneck", "denial of service"],
+    ),
+    (
+        "LOGIC",
+        &["logic error", "mapping", "incorrect", "inconsistent"],
+    ),
+];
+
+/// Category used when no keyword matches
+pub const DEFAULT_CATEGORY: &str = "GENERAL";
+
+/// Derive a bug category from its description
+pub fn classify_category(description: &str) -> &'static str {
+    let description = description.to_lowercase();
+    CATEGORY_KEYWORDS
+        .iter()
+        .find(|(_, keywords)| keywords.iter().any(|k| description.contains(k)))
+        .map(|(category, _)| *category)
+        .unwrap_or(DEFAULT_CATEGORY)
+}
+
+/// Numeric rank of a severity label: CRITICAL=4, HIGH=3, MEDIUM=2, LOW=1, unknown=0
+pub fn severity_rank(severity: &str) -> u8 {
+    match severity.trim().to_uppercase().as_str() {
+        "CRITICAL" => 4,
+        "HIGH" => 3,
+        "MEDIUM" => 2,
+        "LOW" => 1,
+        _ => 0,
+    }
+}
+
+/// Adapter name from a repository-relative `crates/adapters/<adapter>/...` path
+pub fn adapter_from_path(relative_path: &str) -> Option<String> {
+    let mut parts = relative_path.split('/');
+    while let Some(part) = parts.next() {
+        if part == "adapters" {
+            return parts.next().map(|adapter| adapter.to_string());
+        }
+    }
+    None
+}
+
+/// Normalize the LLM-reported list of affected functions
+fn normalize_functions(affected_functions: &str) -> Vec<String> {
+    let mut functions: Vec<String> = affected_functions
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .map(|f| {
+            f.trim_matches(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .trim_end_matches("()")
+                .to_lowercase()
+        })
+        .filter(|f| !f.is_empty() && f != "unknown" && f != "none" && f != "n/a")
+        .collect();
+    functions.sort();
+    functions.dedup();
+    functions
+}
+
+/// Compute a stable fingerprint from the source path and affected functions
+pub fn compute_fingerprint(file_path: &str, affected_functions: &str) -> String {
+    let key = format!(
+        "{}|{}",
+        to_repo_relative(file_path),
+        normalize_functions(affected_functions).join(",")
+    );
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// SHA-256 of file contents, used to detect changed source files
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Whether a file in the bugs directory is an individual bug record
+pub fn is_bug_file(path: &Path) -> bool {
+    let filename = path.file_name().unwrap_or_default().to_string_lossy();
+    path.extension().is_some_and(|ext| ext == "json")
+        && (filename.starts_with("AUTO_BUG_") || filename.contains("_BUG_"))
+}
+
+/// Load every bug record from the bugs directory
+pub async fn load_bugs(bugs_dir: &Path) -> Result<Vec<(PathBuf, StoredBug)>> {
+    let mut bugs = Vec::new();
+    if !bugs_dir.exists() {
+        return Ok(bugs);
+    }
+
+    let mut entries = async_fs::read_dir(bugs_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if !is_bug_file(&path) {
+            continue;
+        }
+        let content = match async_fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Failed to read bug file {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        match serde_json::from_str::<StoredBug>(&content) {
+            Ok(bug) => bugs.push((path, bug)),
+            Err(e) => warn!("Failed to parse bug file {}: {}", path.display(), e),
+        }
+    }
+
+    bugs.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(bugs)
+}
+
+/// Filters over stored bugs by severity, adapter and status
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BugFilter {
+    /// Severity label (case-insensitive), e.g. HIGH
+    pub severity: Option<String>,
+    /// Adapter name (case-insensitive substring), e.g. okx
+    pub adapter: Option<String>,
+    /// Lifecycle status: open, fixed, regressed, or active for open and regressed
+    pub status: Option<String>,
+}
+
+impl BugFilter {
+    /// Whether a bug passes every filter that is set
+    pub fn matches(&self, bug: &StoredBug) -> bool {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .filter(|v| !v.trim().is_empty())
+                .map(str::to_string)
+        };
+
+        if let Some(severity) = non_empty(&self.severity) {
+            if !bug.severity.eq_ignore_ascii_case(&severity) {
+                return false;
+            }
+        }
+        if let Some(adapter) = non_empty(&self.adapter) {
+            if !bug
+                .adapter()
+                .to_lowercase()
+                .contains(&adapter.to_lowercase())
+            {
+                return false;
+            }
+        }
+        if let Some(status) = non_empty(&self.status) {
+            let matches = if status.eq_ignore_ascii_case("active") {
+                bug.status.is_active()
+            } else {
+                bug.status.as_str().eq_ignore_ascii_case(&status)
+            };
+            if !matches {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Write a bug record back to disk
+pub async fn save_bug(path: &Path, bug: &StoredBug) -> Result<()> {
+    async_fs::write(path, serde_json::to_string_pretty(bug)?).await?;
+    Ok(())
+}
+
+/// Mark the stored bug `bug_id` as confirmed and return it
+pub async fn confirm_bug(bugs_dir: &Path, bug_id: &str, source: &str) -> Result<StoredBug> {
+    let (path, mut bug) = load_bugs(bugs_dir)
+        .await?
+        .into_iter()
+        .find(|(_, bug)| bug.bug_id == bug_id)
+        .ok_or_else(|| anyhow::anyhow!("Bug {} not found", bug_id))?;
+    bug.mark_confirmed(source);
+    save_bug(&path, &bug).await?;
+    Ok(bug)
+}
+
+/// New/fixed/regressed/persisting breakdown of a run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LifecycleSummary {
+    pub new: Vec<String>,
+    pub fixed: Vec<String>,
+    pub regressed: Vec<String>,
+    pub persisting: Vec<String>,
+}
+
+impl LifecycleSummary {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "new": self.new.len(),
+            "fixed": self.fixed.len(),
+            "regressed": self.regressed.len(),
+            "persisting": self.persisting.len(),
+            "new_bug_ids": self.new,
+            "fixed_bug_ids": self.fixed,
+            "regressed_bug_ids": self.regressed,
+            "persisting_bug_ids": self.persisting,
+        })
+    }
+}
+
+/// Result of matching a finding against stored bugs
+#[derive(Debug, Clone, PartialEq)]
+pub enum FindingMatch {
+    /// No stored bug matches; the finding is a new bug
+    New,
+    /// An open bug with the same fingerprint was found again
+    Persisting { bug_id: String, bug_file: PathBuf },
+    /// A fixed bug with the same fingerprint reappeared
+    Regressed { bug_id: String, bug_file: PathBuf },
+}
+
+/// Where a finding was reported, used to match it and to refresh a matched record
+#[derive(Debug, Clone, Default)]
+pub struct FindingSite {
+    pub file_path: String,
+    pub affected_functions: String,
+    /// Approximate 1-based line of the code sample, if it was located
+    pub line_number: Option<u64>,
+    /// SHA-256 of the analyzed source file
+    pub content_hash: String,
+    pub last_modified: Option<u64>,
+}
+
+/// Source file that was fully analyzed during a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyzedFile {
+    pub file_path: String,
+    pub content_hash: String,
+    pub last_modified: Option<u64>,
+}
+
+/// Matches findings of an analysis run against stored bugs and updates their status
+pub struct LifecycleTracker {
+    bugs: Vec<(PathBuf, StoredBug)>,
+    detected: HashSet<usize>,
+    commit_hash: Option<String>,
+    pub summary: LifecycleSummary,
+}
+
+impl LifecycleTracker {
+    /// Load stored bugs for matching
+    pub async fn load(bugs_dir: &Path, commit_hash: Option<String>) -> Result<Self> {
+        let bugs = load_bugs(bugs_dir).await?;
+        debug!("Lifecycle tracker loaded {} stored bugs", bugs.len());
+        Ok(Self {
+            bugs,
+            detected: HashSet::new(),
+            commit_hash,
+            summary: LifecycleSummary::default(),
+        })
+    }
+
+    /// Match a finding by fingerprint, falling back to an active bug at the same place
+    ///
+    /// The fallback only takes a bug in the same file that shares an affected function
+    /// or sits within [`FALLBACK_LINE_DISTANCE`] lines, so a distinct bug in a file that
+    /// already has one is still recorded as new.
+    pub async fn match_finding(
+        &mut self,
+        fingerprint: &str,
+        site: &FindingSite,
+    ) -> Result<FindingMatch> {
+        let location = to_repo_relative(&site.file_path);
+
+        let index = self
+            .bugs
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.detected.contains(i))
+            .find(|(_, (_, bug))| bug.effective_fingerprint() == fingerprint)
+            .or_else(|| {
+                self.bugs
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !self.detected.contains(i))
+                    .find(|(_, (_, bug))| {
+                        bug.status.is_active()
+                            && bug.location_key().as_deref() == Some(location.as_str())
+                            && bug.is_near(site)
+                    })
+            })
+            .map(|(i, _)| i);
+
+        let Some(index) = index else {
+            return Ok(FindingMatch::New);
+        };
+
+        self.detected.insert(index);
+        let now = chrono::Utc::now().to_rfc3339();
+        let commit_hash = self.commit_hash.clone();
+        let (bug_file, bug) = &mut self.bugs[index];
+        bug.last_seen = Some(now);
+        if bug.fingerprint.is_none() {
+            bug.fingerprint = Some(bug.effective_fingerprint());
+        }
+        bug.refresh_location(site);
+
+        let outcome = if bug.status == BugStatus::Fixed {
+            info!("🔁 Bug {} regressed", bug.bug_id);
+            bug.transition(
+                BugStatus::Regressed,
+                commit_hash,
+                Some("Detected again after being marked fixed".to_string()),
+            );
+            self.summary.regressed.push(bug.bug_id.clone());
+            FindingMatch::Regressed {
+                bug_id: bug.bug_id.clone(),
+                bug_file: bug_file.clone(),
+            }
+        } else {
+            debug!("Bug {} persists", bug.bug_id);
+            self.summary.persisting.push(bug.bug_id.clone());
+            FindingMatch::Persisting {
+                bug_id: bug.bug_id.clone(),
+                bug_file: bug_file.clone(),
+            }
+        };
+
+        save_bug(bug_file, bug).await?;
+        Ok(outcome)
+    }
+
+    /// Record a newly stored bug
+    pub fn record_new(&mut self, bug_id: &str) {
+        self.summary.new.push(bug_id.to_string());
+    }
+
+    /// Mark bugs as detected by ID, for findings matched by earlier trackers of the same run
+    pub fn mark_detected(&mut self, bug_ids: &HashSet<String>) {
+        for (index, (_, bug)) in self.bugs.iter().enumerate() {
+            if bug_ids.contains(&bug.bug_id) {
+                self.detected.insert(index);
+            }
+        }
+    }
+
+    /// Close active bugs in analyzed files that were not detected again
+    ///
+    /// Bugs are only moved to `fixed` when the source file changed since the bug was
+    /// recorded; undetected bugs in unchanged files are counted as persisting.
+    pub async fn close_undetected(&mut self, analyzed_files: &[AnalyzedFile]) -> Result<()> {
+        for (index, (bug_file, bug)) in self.bugs.iter_mut().enumerate() {
+            if self.detected.contains(&index) || !bug.status.is_active() {
+                continue;
+            }
+            let Some(location) = bug.location_key() else {
+                continue;
+            };
+            let Some(analyzed) = analyzed_files
+                .iter()
+                .find(|f| to_repo_relative(&f.file_path) == location)
+            else {
+                continue;
+            };
+
+            let changed = match bug.recorded_source_hash() {
+                Some(hash) => hash != analyzed.content_hash,
+                None => bug.recorded_mtime() != analyzed.last_modified,
+            };
+
+            if changed {
+                info!("✅ Bug {} no longer detected, marking fixed", bug.bug_id);
+                bug.transition(
+                    BugStatus::Fixed,
+                    self.commit_hash.clone(),
+                    Some("No longer detected in changed source".to_string()),
+                );
+                self.summary.fixed.push(bug.bug_id.clone());
+                save_bug(bug_file, bug).await?;
+            } else {
+                debug!("Bug {} not detected but source unchanged", bug.bug_id);
+                self.summary.persisting.push(bug.bug_id.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_is_machine_independent() {
+        let a = compute_fingerprint(
+            "/home/a/rig/../crates/adapters/tardis/src/enums.rs",
+            "from_venue_str",
+        );
+        let b = compute_fingerprint(
+            "/Users/b/nautilus_trader/crates/adapters/tardis/src/enums.rs",
+            "`from_venue_str()`",
+        );
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_function_order_and_unknown() {
+        let a = compute_fingerprint("crates/adapters/x.rs", "parse, decode");
+        let b = compute_fingerprint("crates/adapters/x.rs", "decode,parse, Unknown");
+        assert_eq!(a, b);
+        assert_ne!(a, compute_fingerprint("crates/adapters/x.rs", "parse"));
+    }
+
+    #[test]
+    fn test_classify_category() {
+        assert_eq!(
+            classify_category("Uses `c_char` for a flag field"),
+            "MEMORY"
+        );
+        assert_eq!(
+            classify_category("Precision loss when converting to f64"),
+            "PRECISION"
+        );
+        assert_eq!(
+            classify_category("Nothing recognisable here"),
+            DEFAULT_CATEGORY
+        );
+    }
+
+    #[test]
+    fn test_bug_filter() {
+        let bug: StoredBug = serde_json::from_value(serde_json::json!({
+            "bug_id": "B1",
+            "severity": "HIGH",
+            "status": "regressed",
+            "file_location": { "details": { "relative_path": "crates/adapters/okx/src/lib.rs" } }
+        }))
+        .unwrap();
+
+        let filter =
+            |severity: Option<&str>, adapter: Option<&str>, status: Option<&str>| BugFilter {
+                severity: severity.map(str::to_string),
+                adapter: adapter.map(str::to_string),
+                status: status.map(str::to_string),
+            };
+        assert!(filter(None, None, None).matches(&bug));
+        assert!(filter(Some("high"), Some("OKX"), Some("active")).matches(&bug));
+        assert!(filter(Some(""), None, Some("regressed")).matches(&bug));
+        assert!(!filter(Some("LOW"), None, None).matches(&bug));
+        assert!(!filter(None, Some("bitmex"), None).matches(&bug));
+        assert!(!filter(None, None, Some("fixed")).matches(&bug));
+    }
+
+    #[test]
+    fn test_adapter_from_path() {
+        assert_eq!(
+            adapter_from_path("crates/adapters/okx/src/lib.rs").as_deref(),
+            Some("okx")
+        );
+        assert_eq!(adapter_from_path("crates/model/src/lib.rs"), None);
+    }
+
+    #[test]
+    fn test_stored_bug_roundtrip_preserves_extra_fields() {
+        let json =
+            r#"{"bug_id":"B1","severity":"HIGH","description":"d","enhanced_metadata":{"k":1}}"#;
+        let bug: StoredBug = serde_json::from_str(json).unwrap();
+        assert_eq!(bug.status, BugStatus::Open);
+        let value = serde_json::to_value(&bug).unwrap();
+        assert_eq!(value["enhanced_metadata"]["k"], 1);
+        assert_eq!(value["status"], "open");
+    }
+
+    #[tokio::test]
+    async fn test_distinct_findings_in_one_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = "crates/adapters/okx/src/parse.rs";
+        let bug = StoredBug {
+            bug_id: "B1".to_string(),
+            affected_functions: Some("parse_price".to_string()),
+            fingerprint: Some(compute_fingerprint(path, "parse_price, parse_order")),
+            file_location: Some(serde_json::json!({
+                "details": { "relative_path": path, "source_sha256": "old" },
+                "source_location": { "approximate_line_number": 40 }
+            })),
+            ..Default::default()
+        };
+        save_bug(&dir.path().join("AUTO_BUG_B1.json"), &bug)
+            .await
+            .unwrap();
+
+        // A different function far from the stored bug is a new bug
+        let mut tracker = LifecycleTracker::load(dir.path(), None).await.unwrap();
+        let other = FindingSite {
+            file_path: path.to_string(),
+            affected_functions: "parse_trade".to_string(),
+            line_number: Some(200),
+            content_hash: "new".to_string(),
+            last_modified: None,
+        };
+        let fingerprint = compute_fingerprint(path, &other.affected_functions);
+        assert_eq!(
+            tracker.match_finding(&fingerprint, &other).await.unwrap(),
+            FindingMatch::New
+        );
+
+        // The stored bug reported with a slightly different function list still matches
+        let same = FindingSite {
+            affected_functions: "parse_price".to_string(),
+            line_number: Some(42),
+            ..other
+        };
+        let fingerprint = compute_fingerprint(path, &same.affected_functions);
+        assert!(matches!(
+            tracker.match_finding(&fingerprint, &same).await.unwrap(),
+            FindingMatch::Persisting { bug_id, .. } if bug_id == "B1"
+        ));
+
+        let (_, stored) = load_bugs(dir.path()).await.unwrap().remove(0);
+        assert_eq!(stored.recorded_source_hash(), Some("new"));
+        assert_eq!(stored.line_number(), Some(42));
+    }
+
+    #[tokio::test]
+    async fn test_confirm_bug_persists_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let bug = StoredBug {
+            bug_id: "B1".to_string(),
+            severity: "HIGH".to_string(),
+            ..Default::default()
+        };
+        let path = dir.path().join("AUTO_BUG_B1.json");
+        save_bug(&path, &bug).await.unwrap();
+        assert!(!bug.is_confirmed());
+
+        confirm_bug(dir.path(), "B1", "triage").await.unwrap();
+        let (_, stored) = load_bugs(dir.path()).await.unwrap().remove(0);
+        assert!(stored.is_confirmed());
+        assert_eq!(stored.extra["confirmed_by"], "triage");
+        assert!(confirm_bug(dir.path(), "B2", "triage").await.is_err());
+    }
+}
@@ -0,0 +1,1162 @@
+//! Command line subcommands for Nautilus Trader Rig
+//!
+//! Without a command (or with `run`) the binary starts everything: startup checks,
+//! the analysis worker, the scheduler and the MCP server. Every other command does
+//! one thing and exits with a status CI can gate on:
+//!
+//! - `0`: success, nothing at or above the `--fail-on` severity
+//! - `1`: findings at or above `--fail-on`, or failed checks
+//! - `2`: invalid command line or configuration
+//! - `3`: the command itself failed
+
+use anyhow::{anyhow, Result};
+use rig::embeddings::to_texts;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::error;
+
+use crate::batch_embed::{self, BatchOptions};
+use crate::bug_store::{self, BugFilter};
+use crate::code_index::{self, CodeChunk};
+use crate::config::Config;
+use crate::dashboard::{self, BugListEntry};
+use crate::deepseek::DeepSeekClient;
+use crate::doctor;
+use crate::index_snapshot::{self, ImportMode};
+use crate::job_queue::{self, JobQueue};
+use crate::models;
+use crate::pattern_catalog;
+use crate::pattern_learning;
+use crate::repo_paths;
+use crate::retrieval_eval::{self, EvalMode, ModelSpec, QuerySet};
+use crate::run_diff;
+use crate::sarif;
+use crate::themes::{self, ThemeOptions};
+use crate::vector_store;
+
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_GATE_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_FAILURE: i32 = 3;
+
+pub const USAGE: &str = "\
+Usage: nautilus_trader_rig [global flags] [command] [arguments]
+
+Commands:
+  run                      Startup checks, analysis worker, scheduler and MCP server (default)
+  serve                    MCP server, dashboard, health endpoints and queued jobs
+  analyze [--adapter <name>]... [--path <prefix>]... [--fail-on <severity>]
+                           Analyze the matching adapter files once, then exit
+  bugs list [--severity <s>] [--adapter <a>] [--status <s>] [--json] [--fail-on <severity>]
+  bugs show <bug_id> [--json]
+  bugs export [--format json|sarif] [--output <file>] [--include-fixed]
+  bugs confirm <bug_id>    Mark a bug confirmed so pattern learning uses it
+  search <query> [--limit <n>] [--category <c>] [--min-severity <n>] [--adapter <a>]
+         [--status <s>] [--since <date>] [--until <date>] [--min-score <x>] [--json]
+                           Search bug patterns by similarity
+  patterns [--category <c>] [--json]
+                           List the bug pattern catalog (paths.patterns_dir)
+  patterns learn [--min-cluster <n>]
+                           Propose patterns generalized from confirmed bugs (default clusters of 2+)
+  patterns proposals [--json]
+  patterns accept <id>     Add a proposed pattern to the catalog
+  patterns reject <id>
+  doctor [--json]          Check every prerequisite and print fixes
+  models list [--json]     List the supported FastEmbed models and their dimensions
+  models import <snapshot-dir> [--output <dir>]
+                           Copy a downloaded model into a checksummed bundle (default embedding.model_dir)
+  models verify [<dir>]    Check a bundle against its checksums and embedding.model
+  models bench [--docs <n>] [--batch-size <n>]... [--threads <n>]... [--json]
+                           Measure embedding throughput (docs/sec) on adapter source chunks;
+                           --threads sets concurrent batches, ONNX Runtime uses every core per batch
+  report html [run] [--output <file>] [--source-base-url <url>]
+  report risk [run] [--json]
+  report sarif [--output <file>] [--include-fixed]
+  report diff [<base> <head>] [--format json|markdown|text]
+  report themes [--adapter <a>] [--status <s>] [--threshold <t>] [--min-size <n>]
+                [--json] [--output <file>]
+                           Cluster bugs into systemic themes labelled by DeepSeek
+  index export <file>      Write the embedded patterns and bugs to a portable file
+  index import <file> [--replace]
+                           Load an exported index without re-embedding; --replace drops
+                           catalog patterns and bug records missing from the file
+  eval [--queries <file>] [--model <name>[=<bundle-dir>]]... [--mode <mode>]... [--k <n>] [--json]
+                           Score search quality on the labelled queries in eval/queries.json
+                           (modes: vector, lexical, hybrid, hybrid+rerank)
+  migrate-paths            Rewrite stored records to repository-relative paths
+  help                     Show this help
+
+Global flags:
+  --config <file>  --root <dir> (repeatable)  --bugs-dir <dir>  --model <name>  --bind <host:port>
+  --offline        Never download the embedding model
+
+Exit codes: 0 success, 1 findings at or above --fail-on or failed checks,
+            2 invalid command line or configuration, 3 command failed
+";
+
+/// Result of a successful command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    /// Findings at or above the requested severity, or failed checks
+    GateFailed,
+}
+
+/// Invalid command line, reported with exit code 2
+#[derive(Debug)]
+pub struct UsageError(pub String);
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+pub fn usage_error(message: impl Into<String>) -> anyhow::Error {
+    UsageError(message.into()).into()
+}
+
+/// Process exit code for a command result
+pub fn exit_code(result: &Result<Outcome>) -> i32 {
+    match result {
+        Ok(Outcome::Success) => EXIT_SUCCESS,
+        Ok(Outcome::GateFailed) => EXIT_GATE_FAILED,
+        Err(e) if e.downcast_ref::<UsageError>().is_some() => EXIT_USAGE,
+        Err(_) => EXIT_FAILURE,
+    }
+}
+
+/// Next argument as the value of `flag`
+fn flag_value<'a>(iter: &mut impl Iterator<Item = &'a String>, flag: &str) -> Result<String> {
+    iter.next()
+        .cloned()
+        .ok_or_else(|| usage_error(format!("{} requires a value", flag)))
+}
+
+/// Severity rank of a `--fail-on` value
+fn parse_severity(value: &str) -> Result<u8> {
+    match bug_store::severity_rank(value) {
+        0 => Err(usage_error(format!(
+            "Unknown severity '{}'; use critical, high, medium or low",
+            value
+        ))),
+        rank => Ok(rank),
+    }
+}
+
+/// Gate on the number of findings at or above the `--fail-on` threshold
+fn gate(gated: usize, threshold: Option<u8>) -> Outcome {
+    if threshold.is_some() && gated > 0 {
+        Outcome::GateFailed
+    } else {
+        Outcome::Success
+    }
+}
+
+/// Outcome of an `analyze` run: any failed analysis fails the command, otherwise
+/// the severity gate decides
+fn run_outcome(
+    run_id: &str,
+    failures: usize,
+    files: usize,
+    gated: usize,
+    threshold: Option<u8>,
+) -> Result<Outcome> {
+    if failures > 0 {
+        return Err(anyhow!(
+            "{} of {} analyses in run {} failed",
+            failures,
+            files,
+            run_id
+        ));
+    }
+    Ok(gate(gated, threshold))
+}
+
+fn at_or_above(severity: &str, threshold: Option<u8>) -> bool {
+    threshold.is_some_and(|threshold| bug_store::severity_rank(severity) >= threshold)
+}
+
+/// Whether a repository-relative file passes the adapter and path filters
+fn matches_filters(relative_path: &str, adapters: &[String], prefixes: &[String]) -> bool {
+    let adapter_ok = adapters.is_empty()
+        || bug_store::adapter_from_path(relative_path)
+            .is_some_and(|adapter| adapters.iter().any(|a| a.eq_ignore_ascii_case(&adapter)));
+    let path_ok = prefixes.is_empty()
+        || prefixes
+            .iter()
+            .any(|prefix| relative_path.starts_with(prefix.as_str()));
+    adapter_ok && path_ok
+}
+
+// Command to analyze matching adapter files once:
+// analyze [--adapter <name>]... [--path <prefix>]... [--fail-on <severity>]
+pub async fn analyze_command(args: &[String]) -> Result<Outcome> {
+    let mut adapters = Vec::new();
+    let mut prefixes = Vec::new();
+    let mut threshold = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--adapter" => adapters.push(flag_value(&mut iter, arg)?),
+            "--path" => prefixes.push(repo_paths::to_repo_relative(&flag_value(&mut iter, arg)?)),
+            "--fail-on" => threshold = Some(parse_severity(&flag_value(&mut iter, arg)?)?),
+            other => return Err(usage_error(format!("Unknown analyze argument: {}", other))),
+        }
+    }
+
+    let selected: HashSet<String> = crate::discover_rust_files(Config::adapter_roots())
+        .await?
+        .iter()
+        .map(|file| repo_paths::to_repo_relative(file))
+        .filter(|file| matches_filters(file, &adapters, &prefixes))
+        .collect();
+    if selected.is_empty() {
+        return Err(usage_error("No adapter files match the given filters"));
+    }
+
+    let state = Arc::new(crate::UnifiedServerState::new().await?);
+    if state.deepseek_client.is_none() {
+        return Err(anyhow!(
+            "DeepSeek client unavailable; set {} to analyze files",
+            Config::settings().provider.api_key_env
+        ));
+    }
+
+    let queue = JobQueue::shared().await?;
+    tokio::spawn(async move {
+        if let Err(e) = job_queue::run_worker(state).await {
+            error!("❌ Analysis worker failed: {}", e);
+        }
+    });
+    let Some(run_id) = crate::analyze_adapter_files_for_bugs(queue, Some(&selected)).await? else {
+        return Ok(Outcome::Success);
+    };
+
+    let summary = run_diff::resolve_summary(&Config::bugs_directory_path(), &run_id)?;
+    let snapshot = run_diff::RunSnapshot::load(&summary).await?;
+    let failures = snapshot.files.values().filter(|f| f.is_failure()).count();
+
+    let mut gated = 0;
+    let mut findings = 0;
+    for (file, outcome) in &snapshot.files {
+        if !outcome.status.starts_with("bug_found") {
+            continue;
+        }
+        findings += 1;
+        let severity = outcome.severity.as_deref().unwrap_or("UNKNOWN");
+        if at_or_above(severity, threshold) {
+            gated += 1;
+        }
+        println!(
+            "🐛 {:<8} {} ({})",
+            severity,
+            file,
+            outcome.bug_id.as_deref().unwrap_or("unstored")
+        );
+    }
+    for (file, outcome) in &snapshot.files {
+        if outcome.is_failure() {
+            println!("❌ {:<8} {} ({})", "FAILED", file, outcome.status);
+        }
+    }
+    println!(
+        "📊 Run {}: {} files, {} findings, {} failed analyses",
+        run_id,
+        snapshot.files.len(),
+        findings,
+        failures
+    );
+    run_outcome(&run_id, failures, snapshot.files.len(), gated, threshold)
+}
+
+// Command to query stored bugs: bugs list|show|export
+pub async fn bugs_command(args: &[String]) -> Result<Outcome> {
+    match args.first().map(String::as_str) {
+        Some("list") => bugs_list(&args[1..]).await,
+        Some("show") => bugs_show(&args[1..]).await,
+        Some("export") => bugs_export(&args[1..]).await,
+        Some("confirm") => bugs_confirm(&args[1..]).await,
+        Some(other) => Err(usage_error(format!("Unknown bugs command: {}", other))),
+        None => Err(usage_error(
+            "bugs requires a command: list, show, export or confirm",
+        )),
+    }
+}
+
+async fn bugs_list(args: &[String]) -> Result<Outcome> {
+    let mut filter = BugFilter::default();
+    let mut as_json = false;
+    let mut threshold = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--severity" => filter.severity = Some(flag_value(&mut iter, arg)?),
+            "--adapter" => filter.adapter = Some(flag_value(&mut iter, arg)?),
+            "--status" => filter.status = Some(flag_value(&mut iter, arg)?),
+            "--json" => as_json = true,
+            "--fail-on" => threshold = Some(parse_severity(&flag_value(&mut iter, arg)?)?),
+            other => {
+                return Err(usage_error(format!(
+                    "Unknown bugs list argument: {}",
+                    other
+                )))
+            }
+        }
+    }
+
+    let bugs = dashboard::filtered_bugs(&Config::bugs_directory_path(), &filter).await?;
+    let gated = bugs
+        .iter()
+        .filter(|bug| bug.status.is_active() && at_or_above(&bug.severity, threshold))
+        .count();
+
+    let entries: Vec<BugListEntry> = bugs.iter().map(BugListEntry::from_bug).collect();
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        for entry in &entries {
+            let location = match (&entry.file, entry.line) {
+                (Some(file), Some(line)) => format!("{}:{}", file, line),
+                (Some(file), None) => file.clone(),
+                _ => "-".to_string(),
+            };
+            println!(
+                "{:<8} {:<9} {:<12} {}  {}",
+                entry.severity, entry.status, entry.adapter, entry.bug_id, location
+            );
+        }
+        println!("{} bugs", entries.len());
+    }
+    Ok(gate(gated, threshold))
+}
+
+async fn bugs_show(args: &[String]) -> Result<Outcome> {
+    let mut bug_id = None;
+    let mut as_json = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => as_json = true,
+            other if bug_id.is_none() => bug_id = Some(other.to_string()),
+            other => {
+                return Err(usage_error(format!(
+                    "Unknown bugs show argument: {}",
+                    other
+                )))
+            }
+        }
+    }
+    let bug_id = bug_id.ok_or_else(|| usage_error("Usage: bugs show <bug_id> [--json]"))?;
+
+    let bug = dashboard::find_bug(&Config::bugs_directory_path(), &bug_id)
+        .await?
+        .ok_or_else(|| anyhow!("Bug {} not found", bug_id))?;
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&bug)?);
+        return Ok(Outcome::Success);
+    }
+
+    let entry = BugListEntry::from_bug(&bug);
+    println!("🐛 {}", entry.bug_id);
+    println!("   Severity: {}", entry.severity);
+    println!("   Status:   {}", entry.status);
+    println!("   Adapter:  {}", entry.adapter);
+    println!("   Category: {}", entry.category);
+    if let Some(file) = &entry.file {
+        match entry.line {
+            Some(line) => println!("   Location: {}:{}", file, line),
+            None => println!("   Location: {}", file),
+        }
+    }
+    println!("   Recorded: {}", entry.timestamp);
+    println!("\n{}", bug.description);
+    if let Some(code) = &bug.code_sample {
+        println!("\nCode sample:\n{}", code);
+    }
+    if let Some(fix) = &bug.fix_suggestion {
+        println!("\nFix suggestion:\n{}", fix);
+    }
+    Ok(Outcome::Success)
+}
+
+async fn bugs_confirm(args: &[String]) -> Result<Outcome> {
+    let [bug_id] = args else {
+        return Err(usage_error("Usage: bugs confirm <bug_id>"));
+    };
+    bug_store::confirm_bug(&Config::bugs_directory_path(), bug_id, "triage").await?;
+    println!(
+        "✅ Marked {} as confirmed; `patterns learn` can now generalize it",
+        bug_id
+    );
+    Ok(Outcome::Success)
+}
+
+async fn bugs_export(args: &[String]) -> Result<Outcome> {
+    let mut format = "json".to_string();
+    let mut output = None;
+    let mut include_fixed = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => format = flag_value(&mut iter, arg)?,
+            "--output" | "-o" => output = Some(flag_value(&mut iter, arg)?),
+            "--include-fixed" => include_fixed = true,
+            other => {
+                return Err(usage_error(format!(
+                    "Unknown bugs export argument: {}",
+                    other
+                )))
+            }
+        }
+    }
+
+    let bugs_dir = Config::bugs_directory_path();
+    let (rendered, count) = match format.as_str() {
+        "json" => {
+            let filter = BugFilter {
+                status: (!include_fixed).then(|| "active".to_string()),
+                ..BugFilter::default()
+            };
+            let bugs = dashboard::filtered_bugs(&bugs_dir, &filter).await?;
+            (serde_json::to_string_pretty(&bugs)?, bugs.len())
+        }
+        "sarif" => {
+            let log = sarif::export_bugs(&bugs_dir, include_fixed).await?;
+            let count = log["runs"][0]["results"]
+                .as_array()
+                .map(|r| r.len())
+                .unwrap_or(0);
+            (serde_json::to_string_pretty(&log)?, count)
+        }
+        other => {
+            return Err(usage_error(format!(
+                "Unknown export format '{}'; use json or sarif",
+                other
+            )))
+        }
+    };
+    match output {
+        Some(path) => {
+            tokio::fs::write(&path, rendered).await?;
+            println!("✅ Exported {} bugs to {}", count, path);
+        }
+        None => println!("{}", rendered),
+    }
+    Ok(Outcome::Success)
+}
+
+// Command to search bug patterns by similarity: search <query> [--limit <n>] [filters] [--json]
+pub async fn search_command(args: &[String]) -> Result<Outcome> {
+    let mut words = Vec::new();
+    let mut limit = Config::settings().embedding.search_limit;
+    let mut filter = vector_store::PatternFilter::default();
+    let mut as_json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--limit" => {
+                let value = flag_value(&mut iter, arg)?;
+                limit = value.parse().map_err(|_| {
+                    usage_error(format!("--limit must be a number, got '{}'", value))
+                })?;
+            }
+            "--category" => filter.category = Some(flag_value(&mut iter, arg)?),
+            "--adapter" => filter.adapter = Some(flag_value(&mut iter, arg)?),
+            "--status" => filter.status = Some(flag_value(&mut iter, arg)?),
+            "--min-severity" => {
+                let value = flag_value(&mut iter, arg)?;
+                let severity = value.parse().map_err(|_| {
+                    usage_error(format!(
+                        "--min-severity must be a number from 0 to 255, got '{}'",
+                        value
+                    ))
+                })?;
+                filter.min_severity = Some(severity);
+            }
+            "--min-score" => {
+                let value = flag_value(&mut iter, arg)?;
+                let score = value.parse().map_err(|_| {
+                    usage_error(format!("--min-score must be a number, got '{}'", value))
+                })?;
+                filter.min_score = Some(score);
+            }
+            "--since" | "--until" => {
+                let day = vector_store::parse_filter_date(&flag_value(&mut iter, arg)?)
+                    .map_err(|e| usage_error(format!("{}: {}", arg, e)))?;
+                if arg == "--since" {
+                    filter.since = Some(day);
+                } else {
+                    filter.until = Some(day);
+                }
+            }
+            "--json" => as_json = true,
+            word => words.push(word.to_string()),
+        }
+    }
+    if words.is_empty() {
+        return Err(usage_error(
+            "Usage: search <query> [--limit <n>] [filters] [--json]; see help",
+        ));
+    }
+
+    let query = words.join(" ");
+    let results = vector_store::shared()
+        .await?
+        .read()
+        .await
+        .similarity_search(&query, &filter, limit)
+        .await?;
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        for result in &results {
+            println!(
+                "{:.3}  [{}] {} (severity {})\n       {}",
+                result["score"].as_f64().unwrap_or_default(),
+                result["category"].as_str().unwrap_or_default(),
+                result["id"].as_str().unwrap_or_default(),
+                result["severity"],
+                result["content"].as_str().unwrap_or_default()
+            );
+        }
+        println!("{} patterns matched '{}'", results.len(), query);
+    }
+    Ok(Outcome::Success)
+}
+
+// Command to list and learn bug patterns: patterns [learn|proposals|accept|reject] ...
+pub async fn patterns_command(args: &[String]) -> Result<Outcome> {
+    match args.first().map(String::as_str) {
+        Some("learn") => patterns_learn(&args[1..]).await,
+        Some("proposals") => patterns_proposals(&args[1..]),
+        Some("accept") | Some("reject") => patterns_review(args).await,
+        _ => patterns_list(args),
+    }
+}
+
+async fn patterns_learn(args: &[String]) -> Result<Outcome> {
+    let mut min_cluster_size = pattern_learning::MIN_CLUSTER_SIZE;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--min-cluster" => {
+                let value = flag_value(&mut iter, arg)?;
+                min_cluster_size = value.parse().map_err(|_| {
+                    usage_error(format!("--min-cluster must be a number, got '{}'", value))
+                })?;
+            }
+            other => {
+                return Err(usage_error(format!(
+                    "Unknown patterns learn argument: {}",
+                    other
+                )))
+            }
+        }
+    }
+
+    let client = DeepSeekClient::from_env()?;
+    let summary = pattern_learning::learn_patterns(&client, min_cluster_size).await?;
+    println!(
+        "🧬 {} unlearned confirmed bugs in {} clusters",
+        summary.unlearned_bugs, summary.clusters
+    );
+    for entry in &summary.proposed {
+        println!(
+            "   📝 {} [{}] from {}",
+            entry.id,
+            entry.category,
+            entry.source_bugs.join(", ")
+        );
+    }
+    for failure in &summary.failed {
+        println!("   ❌ {}", failure);
+    }
+    println!(
+        "{} patterns proposed; review them with `patterns proposals`",
+        summary.proposed.len()
+    );
+    if !summary.failed.is_empty() && summary.proposed.is_empty() {
+        return Err(anyhow!("No cluster could be generalized into a pattern"));
+    }
+    Ok(Outcome::Success)
+}
+
+fn patterns_proposals(args: &[String]) -> Result<Outcome> {
+    let as_json = match args {
+        [] => false,
+        [flag] if flag == "--json" => true,
+        _ => return Err(usage_error("Usage: patterns proposals [--json]")),
+    };
+    let proposals = pattern_learning::load_proposals(&Config::patterns_directory_path())?;
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&proposals)?);
+    } else {
+        for entry in &proposals {
+            println!(
+                "{:<30} {:<9} {:<16} severity {}  from {}",
+                entry.id,
+                entry.status.as_deref().unwrap_or("-"),
+                entry.category,
+                entry.severity,
+                entry.source_bugs.join(", ")
+            );
+            println!("    {}", entry.description);
+        }
+        println!("{} proposals", proposals.len());
+    }
+    Ok(Outcome::Success)
+}
+
+async fn patterns_review(args: &[String]) -> Result<Outcome> {
+    let [action, id] = args else {
+        return Err(usage_error(format!("Usage: patterns {} <id>", args[0])));
+    };
+    if action == "accept" {
+        pattern_learning::accept_proposal(id).await?;
+        println!("✅ Added pattern {} to the catalog", id);
+    } else {
+        pattern_learning::reject_proposal(id)?;
+        println!("🚫 Rejected proposal {}", id);
+    }
+    Ok(Outcome::Success)
+}
+
+fn patterns_list(args: &[String]) -> Result<Outcome> {
+    let mut category = None;
+    let mut as_json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--category" => category = Some(flag_value(&mut iter, arg)?),
+            "--json" => as_json = true,
+            other => return Err(usage_error(format!("Unknown patterns argument: {}", other))),
+        }
+    }
+
+    let patterns: Vec<_> = pattern_catalog::load_catalog(&Config::patterns_directory_path())?
+        .into_iter()
+        .filter(|p| match &category {
+            Some(category) => category.eq_ignore_ascii_case(&p.category),
+            None => true,
+        })
+        .collect();
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&patterns)?);
+    } else {
+        for pattern in &patterns {
+            println!(
+                "{:<26} {:<16} severity {}",
+                pattern.id, pattern.category, pattern.severity
+            );
+            println!("    {}", pattern.description);
+            for reference in &pattern.references {
+                println!("    ↳ {}", reference);
+            }
+        }
+        println!("{} patterns", patterns.len());
+    }
+    Ok(Outcome::Success)
+}
+
+// Command to check every prerequisite and print fixes: doctor [--json]
+pub async fn doctor_command(args: &[String]) -> Result<Outcome> {
+    let mut as_json = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => as_json = true,
+            other => return Err(usage_error(format!("Unknown doctor argument: {}", other))),
+        }
+    }
+
+    let checks = doctor::run_checks().await;
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&checks)?);
+    } else {
+        print!("{}", doctor::render(&checks));
+    }
+    Ok(if doctor::all_passed(&checks) {
+        Outcome::Success
+    } else {
+        Outcome::GateFailed
+    })
+}
+
+// Command to manage local embedding model bundles: models list|import|verify
+pub async fn models_command(args: &[String]) -> Result<Outcome> {
+    let settings = &Config::settings().embedding;
+    let rest = args.get(1..).unwrap_or_default();
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            let as_json = match rest {
+                [] => false,
+                [flag] if flag == "--json" => true,
+                _ => return Err(usage_error("Usage: models list [--json]")),
+            };
+            let configured = models::configured_model()
+                .ok()
+                .map(|model| models::model_name(&model));
+            let entries: Vec<serde_json::Value> = models::supported_models()
+                .iter()
+                .map(|info| {
+                    let name = models::model_name(&info.model);
+                    serde_json::json!({
+                        "name": name,
+                        "code": info.model_code,
+                        "dimension": info.dim,
+                        "description": info.description,
+                        "configured": configured.as_deref() == Some(name.as_str()),
+                    })
+                })
+                .collect();
+            if as_json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                for entry in &entries {
+                    let marker = if entry["configured"].as_bool().unwrap_or_default() {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!(
+                        "{} {:<32} {:>5}  {}",
+                        marker,
+                        entry["name"].as_str().unwrap_or_default(),
+                        entry["dimension"],
+                        entry["code"].as_str().unwrap_or_default()
+                    );
+                }
+                println!("{} models (* = embedding.model)", entries.len());
+            }
+            Ok(Outcome::Success)
+        }
+        Some("import") => {
+            let mut source = None;
+            let mut output = settings.model_dir.clone();
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--output" => output = PathBuf::from(flag_value(&mut iter, arg)?),
+                    value if source.is_none() && !value.starts_with("--") => {
+                        source = Some(PathBuf::from(value))
+                    }
+                    other => {
+                        return Err(usage_error(format!(
+                            "Unknown models import argument: {}",
+                            other
+                        )))
+                    }
+                }
+            }
+            let source = source.ok_or_else(|| {
+                usage_error("Usage: models import <snapshot-dir> [--output <dir>]")
+            })?;
+            let model = models::configured_model()?;
+            let manifest = models::import_bundle(&source, &output, &model)?;
+            println!(
+                "✅ Imported {} ({} dimensions) into {}",
+                manifest.model,
+                manifest.dimension,
+                output.display()
+            );
+            for (file, sha256) in &manifest.files {
+                println!("   {}  {}", sha256, file);
+            }
+            Ok(Outcome::Success)
+        }
+        Some("verify") => {
+            let dir = match rest {
+                [] => settings.model_dir.clone(),
+                [dir] => PathBuf::from(dir),
+                _ => return Err(usage_error("Usage: models verify [<dir>]")),
+            };
+            let model = models::configured_model()?;
+            match models::verify_bundle(&dir, &model) {
+                Ok(manifest) => {
+                    println!(
+                        "✅ {} bundle in {} verified ({} files, imported {})",
+                        manifest.model,
+                        dir.display(),
+                        manifest.files.len(),
+                        manifest.imported_at
+                    );
+                    Ok(Outcome::Success)
+                }
+                Err(e) => {
+                    println!("❌ {:#}", e);
+                    Ok(Outcome::GateFailed)
+                }
+            }
+        }
+        Some("bench") => models_bench(rest).await,
+        Some(other) => Err(usage_error(format!("Unknown models command: {}", other))),
+        None => Err(usage_error(
+            "models requires a command: list, import, verify or bench",
+        )),
+    }
+}
+
+fn positive_flag(iter: &mut std::slice::Iter<'_, String>, flag: &str) -> Result<usize> {
+    let value = flag_value(iter, flag)?;
+    value.parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+        usage_error(format!(
+            "{} must be a positive number, got '{}'",
+            flag, value
+        ))
+    })
+}
+
+// Embedding throughput on adapter source chunks:
+// models bench [--docs <n>] [--batch-size <n>]... [--threads <n>]... [--json]
+async fn models_bench(args: &[String]) -> Result<Outcome> {
+    let mut documents = 1000;
+    let mut batch_sizes = Vec::new();
+    let mut threads = Vec::new();
+    let mut as_json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--docs" => documents = positive_flag(&mut iter, arg)?,
+            "--batch-size" => batch_sizes.push(positive_flag(&mut iter, arg)?),
+            "--threads" => threads.push(positive_flag(&mut iter, arg)?),
+            "--json" => as_json = true,
+            other => {
+                return Err(usage_error(format!(
+                    "Unknown models bench argument: {}",
+                    other
+                )))
+            }
+        }
+    }
+    let defaults = BatchOptions::from_settings();
+    if batch_sizes.is_empty() {
+        batch_sizes.push(defaults.batch_size);
+    }
+    if threads.is_empty() {
+        threads.push(defaults.threads);
+    }
+
+    // Chunks of the adapter sources, repeated when there are fewer than requested
+    let mut corpus = Vec::new();
+    for file in crate::discover_rust_files(Config::adapter_roots()).await? {
+        let Ok(content) = tokio::fs::read_to_string(&file).await else {
+            continue;
+        };
+        let relative_path = repo_paths::to_repo_relative(&file);
+        let adapter =
+            bug_store::adapter_from_path(&relative_path).unwrap_or_else(|| "unknown".to_string());
+        for chunk in code_index::chunk_source(&content) {
+            corpus.extend(to_texts(CodeChunk::new(&relative_path, &adapter, chunk))?);
+        }
+        if corpus.len() >= documents {
+            break;
+        }
+    }
+    if corpus.is_empty() {
+        return Err(anyhow!(
+            "No adapter source found to benchmark with; check paths.roots"
+        ));
+    }
+    let texts: Vec<String> = corpus.iter().cycle().take(documents).cloned().collect();
+
+    // Warm up so the first configuration measured does not pay for session start-up
+    let model = tokio::task::spawn_blocking(models::load_embedding_model).await??;
+    {
+        let model = model.clone();
+        let warm_up: Vec<String> = texts.iter().take(defaults.batch_size).cloned().collect();
+        tokio::task::spawn_blocking(move || batch_embed::benchmark(&model, &warm_up, &defaults))
+            .await??;
+    }
+    let mut results = Vec::new();
+    for &batch_size in &batch_sizes {
+        for &threads in &threads {
+            let options = BatchOptions {
+                batch_size,
+                threads,
+                ..defaults
+            };
+            let model = model.clone();
+            let texts = texts.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                batch_embed::benchmark(&model, &texts, &options)
+            })
+            .await??;
+            if !as_json {
+                println!(
+                    "⚡ batch size {:>5}  workers {:>3}  {:>6} docs in {:>7.2}s  {:>8.1} docs/sec",
+                    result.batch_size,
+                    result.workers,
+                    result.documents,
+                    result.seconds,
+                    result.docs_per_second
+                );
+            }
+            results.push(result);
+        }
+    }
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    }
+    Ok(Outcome::Success)
+}
+
+// Command to share the embedded index: index export <file> | index import <file> [--replace]
+pub async fn index_command(args: &[String]) -> Result<Outcome> {
+    match args {
+        [command, file] if command == "export" => {
+            let path = PathBuf::from(file);
+            let snapshot = index_snapshot::export().await?;
+            snapshot.write(&path)?;
+            println!(
+                "📦 Exported {} patterns and {} bugs embedded with {} ({} dimensions) to {}",
+                snapshot.patterns.len(),
+                snapshot.bugs.len(),
+                snapshot.model,
+                snapshot.dimension,
+                path.display()
+            );
+        }
+        [command, file, rest @ ..] if command == "import" => {
+            let mode = match rest {
+                [] => ImportMode::Merge,
+                [flag] if flag == "--replace" => ImportMode::Replace,
+                _ => return Err(usage_error("Usage: index import <file> [--replace]")),
+            };
+            let summary = index_snapshot::import(&PathBuf::from(file), mode).await?;
+            println!(
+                "📦 Imported {} patterns and {} bugs embedded with {}; removed {} patterns and {} bugs",
+                summary.patterns,
+                summary.bugs,
+                summary.model,
+                summary.removed_patterns,
+                summary.removed_bugs
+            );
+        }
+        [command, ..] if command == "export" || command == "import" => {
+            return Err(usage_error(
+                "Usage: index export <file> | index import <file> [--replace]",
+            ))
+        }
+        [other, ..] => return Err(usage_error(format!("Unknown index command: {}", other))),
+        [] => return Err(usage_error("index requires a command: export or import")),
+    }
+    Ok(Outcome::Success)
+}
+
+// Command to score retrieval quality:
+// eval [--queries <file>] [--model <m>]... [--mode <m>]... [--k <n>] [--json]
+pub async fn eval_command(args: &[String]) -> Result<Outcome> {
+    let mut queries = Config::manifest_dir().join(retrieval_eval::QUERIES_FILE);
+    let mut specs = Vec::new();
+    let mut modes = Vec::new();
+    let mut k = retrieval_eval::DEFAULT_K;
+    let mut as_json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--queries" => queries = PathBuf::from(flag_value(&mut iter, arg)?),
+            "--model" => specs.push(
+                ModelSpec::parse(&flag_value(&mut iter, arg)?)
+                    .map_err(|e| usage_error(format!("{:#}", e)))?,
+            ),
+            "--mode" => modes.push(
+                EvalMode::parse(&flag_value(&mut iter, arg)?)
+                    .map_err(|e| usage_error(format!("{:#}", e)))?,
+            ),
+            "--k" => {
+                let value = flag_value(&mut iter, arg)?;
+                k = value.parse().ok().filter(|k| *k > 0).ok_or_else(|| {
+                    usage_error(format!("--k must be a positive number, got '{}'", value))
+                })?;
+            }
+            "--json" => as_json = true,
+            other => return Err(usage_error(format!("Unknown eval argument: {}", other))),
+        }
+    }
+    if specs.is_empty() {
+        specs.push(ModelSpec::configured()?);
+    }
+    if modes.is_empty() {
+        modes = EvalMode::defaults();
+    }
+
+    let set = QuerySet::load(&queries)?;
+    let report = retrieval_eval::evaluate(&set, &specs, &modes, k).await?;
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!(
+            "📏 Retrieval quality at k={} over {}\n",
+            k,
+            queries.display()
+        );
+        print!("{}", report.to_table());
+    }
+    Ok(Outcome::Success)
+}
+
+// Command to write reports: report html|risk|sarif|diff|themes [arguments]
+pub async fn report_command(args: &[String]) -> Result<Outcome> {
+    let rest = args.get(1..).unwrap_or_default();
+    match args.first().map(String::as_str) {
+        Some("html") => crate::html_report_command(rest).await?,
+        Some("risk") => crate::risk_report_command(rest).await?,
+        Some("sarif") => crate::export_sarif_command(rest).await?,
+        Some("diff") => crate::run_diff_command(rest).await?,
+        Some("themes") => themes_report(rest).await?,
+        Some(other) => return Err(usage_error(format!("Unknown report kind: {}", other))),
+        None => {
+            return Err(usage_error(
+                "report requires a kind: html, risk, sarif, diff or themes",
+            ))
+        }
+    }
+    Ok(Outcome::Success)
+}
+
+async fn themes_report(args: &[String]) -> Result<()> {
+    let mut options = ThemeOptions::default();
+    let mut as_json = false;
+    let mut output = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--adapter" => options.filter.adapter = Some(flag_value(&mut iter, arg)?),
+            "--status" => options.filter.status = Some(flag_value(&mut iter, arg)?),
+            "--threshold" => {
+                let value = flag_value(&mut iter, arg)?;
+                options.threshold = value
+                    .parse()
+                    .ok()
+                    .filter(|t| (0.0..=1.0).contains(t))
+                    .ok_or_else(|| {
+                        usage_error(format!(
+                            "--threshold must be between 0 and 1, got '{}'",
+                            value
+                        ))
+                    })?;
+            }
+            "--min-size" => {
+                let value = flag_value(&mut iter, arg)?;
+                options.min_size = value.parse().map_err(|_| {
+                    usage_error(format!("--min-size must be a number, got '{}'", value))
+                })?;
+            }
+            "--json" => as_json = true,
+            "--output" | "-o" => output = Some(PathBuf::from(flag_value(&mut iter, arg)?)),
+            other => {
+                return Err(usage_error(format!(
+                    "Unknown report themes argument: {}",
+                    other
+                )))
+            }
+        }
+    }
+
+    let client = match DeepSeekClient::from_env() {
+        Ok(client) => Some(client),
+        Err(e) => {
+            eprintln!("⚠️ {:#}; themes are labelled by category instead", e);
+            None
+        }
+    };
+    let report = themes::discover_themes(client.as_ref(), &options).await?;
+    let rendered = if as_json {
+        format!("{}\n", serde_json::to_string_pretty(&report)?)
+    } else {
+        report.to_markdown()
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(&path, rendered)?;
+            println!(
+                "🧭 Wrote {} themes to {}",
+                report.themes.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(exit_code(&Ok(Outcome::Success)), EXIT_SUCCESS);
+        assert_eq!(exit_code(&Ok(Outcome::GateFailed)), EXIT_GATE_FAILED);
+        assert_eq!(exit_code(&Err(usage_error("bad flag"))), EXIT_USAGE);
+        assert_eq!(exit_code(&Err(anyhow!("disk full"))), EXIT_FAILURE);
+    }
+
+    #[test]
+    fn test_severity_gate() {
+        let high = parse_severity("high").unwrap();
+        assert!(at_or_above("CRITICAL", Some(high)));
+        assert!(!at_or_above("medium", Some(high)));
+        assert!(!at_or_above("CRITICAL", None));
+        assert_eq!(gate(2, None), Outcome::Success);
+        assert_eq!(gate(0, Some(high)), Outcome::Success);
+        assert_eq!(gate(1, Some(high)), Outcome::GateFailed);
+        assert!(parse_severity("severe").is_err());
+    }
+
+    #[test]
+    fn test_failed_analyses_fail_the_run() {
+        let high = parse_severity("high").unwrap();
+        let outcome = |status: &str| run_diff::FileOutcome {
+            status: status.to_string(),
+            severity: None,
+            bug_id: None,
+            error: None,
+        };
+        for status in [
+            "analysis_failed",
+            "skipped_no_client",
+            "bug_found_but_storage_failed",
+            "error",
+        ] {
+            assert!(outcome(status).is_failure(), "{}", status);
+        }
+        assert!(!outcome("clean").is_failure());
+        assert!(!outcome("bug_found").is_failure());
+
+        // Nothing analysed must not pass the gate
+        assert_eq!(
+            exit_code(&run_outcome("r1", 3, 3, 0, Some(high))),
+            EXIT_FAILURE
+        );
+        assert_eq!(
+            exit_code(&run_outcome("r1", 1, 3, 1, Some(high))),
+            EXIT_FAILURE
+        );
+        assert_eq!(
+            exit_code(&run_outcome("r1", 0, 3, 1, Some(high))),
+            EXIT_GATE_FAILED
+        );
+        assert_eq!(exit_code(&run_outcome("r1", 0, 3, 0, None)), EXIT_SUCCESS);
+    }
+
+    #[test]
+    fn test_file_filters() {
+        let file = "crates/adapters/okx/src/parse.rs";
+        assert!(matches_filters(file, &[], &[]));
+        assert!(matches_filters(file, &["OKX".to_string()], &[]));
+        assert!(!matches_filters(file, &["bybit".to_string()], &[]));
+        assert!(matches_filters(
+            file,
+            &[],
+            &["crates/adapters/okx/".to_string()]
+        ));
+        assert!(!matches_filters(
+            file,
+            &["okx".to_string()],
+            &["crates/adapters/okx/src/http".to_string()]
+        ));
+    }
+}
@@ -0,0 +1,91 @@
+//! Agglomerative clustering of embeddings
+//!
+//! Average-linkage clustering with a similarity threshold: the two clusters whose
+//! members are most similar on average are merged until no pair reaches the
+//! threshold. Corpora here are dozens to hundreds of bugs, so the quadratic
+//! similarity matrix is cheap and the result does not depend on input order.
+
+/// Cosine similarity of two vectors; 0 when either is all zeros
+pub fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Clusters of `vectors` as lists of indices, largest first
+///
+/// Clusters are merged while their average pairwise cosine similarity is at least
+/// `threshold`; vectors similar to nothing end up in clusters of one.
+pub fn agglomerative(vectors: &[Vec<f64>], threshold: f64) -> Vec<Vec<usize>> {
+    let similarity: Vec<Vec<f64>> = vectors
+        .iter()
+        .map(|a| vectors.iter().map(|b| cosine_similarity(a, b)).collect())
+        .collect();
+    let linkage = |a: &[usize], b: &[usize]| {
+        let total: f64 = a
+            .iter()
+            .flat_map(|&i| b.iter().map(move |&j| (i, j)))
+            .map(|(i, j)| similarity[i][j])
+            .sum();
+        total / (a.len() * b.len()) as f64
+    };
+
+    let mut clusters: Vec<Vec<usize>> = (0..vectors.len()).map(|i| vec![i]).collect();
+    loop {
+        let mut best: Option<(usize, usize, f64)> = None;
+        for i in 0..clusters.len() {
+            for j in i + 1..clusters.len() {
+                let score = linkage(&clusters[i], &clusters[j]);
+                if score >= threshold && best.is_none_or(|(_, _, s)| score > s) {
+                    best = Some((i, j, score));
+                }
+            }
+        }
+        let Some((i, j, _)) = best else {
+            break;
+        };
+        let merged = clusters.remove(j);
+        clusters[i].extend(merged);
+    }
+
+    for cluster in &mut clusters {
+        cluster.sort_unstable();
+    }
+    clusters.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].cmp(&b[0])));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-12);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-12);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn test_agglomerative_groups_by_threshold() {
+        let vectors = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![0.95, 0.05, 0.0],
+            vec![0.0, 0.9, 0.1],
+            vec![0.0, 0.0, 1.0],
+            vec![0.9, 0.1, 0.0],
+        ];
+        let clusters = agglomerative(&vectors, 0.9);
+        assert_eq!(clusters, vec![vec![0, 2, 5], vec![1, 3], vec![4]]);
+
+        assert_eq!(agglomerative(&vectors, 1.1).len(), vectors.len());
+        assert_eq!(agglomerative(&vectors, -1.0), vec![vec![0, 1, 2, 3, 4, 5]]);
+        assert!(agglomerative(&[], 0.5).is_empty());
+    }
+}
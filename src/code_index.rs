@@ -0,0 +1,723 @@
+//! Retrieval over adapter source code
+//!
+//! Adapter files are split into chunks (functions, type definitions, impl and
+//! trait blocks, with large blocks split into their methods) and every chunk is
+//! embedded. The index is refreshed file by file using content hashes, so only
+//! changed files are re-embedded. Analysis prompts get related code from the same
+//! adapter and analogous code from other adapters, and `search_code` exposes the
+//! same retrieval over MCP.
+
+use anyhow::Result;
+use rig::{
+    embeddings::{Embedding, EmbeddingModel as _},
+    Embed, OneOrMany,
+};
+use rig_fastembed::EmbeddingModel;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use tokio::sync::{OnceCell, RwLock};
+use tracing::{debug, info};
+
+use crate::batch_embed;
+use crate::bug_store;
+use crate::clustering::cosine_similarity;
+use crate::config::Config;
+use crate::repo_paths;
+
+/// Impl and trait blocks longer than this are split into their methods
+const MAX_CHUNK_LINES: usize = 80;
+
+/// Lines of a retrieved chunk included in a prompt
+const CONTEXT_CHUNK_LINES: usize = 40;
+
+/// Related chunks from the same adapter added to an analysis prompt
+const SAME_ADAPTER_RESULTS: usize = 3;
+
+/// Analogous chunks from other adapters added to an analysis prompt
+const OTHER_ADAPTER_RESULTS: usize = 2;
+
+/// An item of Rust source located by [`chunk_source`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceChunk {
+    /// fn, struct, enum, trait, impl or macro
+    pub kind: &'static str,
+    /// Item name; methods are prefixed with their impl or trait, e.g. `OKXHttpClient::new`
+    pub name: String,
+    /// 1-based first line, including doc comments and attributes
+    pub start_line: usize,
+    /// 1-based last line
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// Brace depth at the start and end of every line, ignoring comments, strings and char literals
+fn brace_depths(source: &str) -> Vec<(usize, usize)> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Code,
+        BlockComment(usize),
+        Str,
+        RawStr(usize),
+    }
+
+    let mut state = State::Code;
+    let mut depth = 0usize;
+    let mut depths = Vec::new();
+    for line in source.lines() {
+        let start = depth;
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            match state {
+                State::BlockComment(nesting) => {
+                    if c == '*' && next == Some('/') {
+                        state = if nesting == 1 {
+                            State::Code
+                        } else {
+                            State::BlockComment(nesting - 1)
+                        };
+                        i += 1;
+                    } else if c == '/' && next == Some('*') {
+                        state = State::BlockComment(nesting + 1);
+                        i += 1;
+                    }
+                }
+                State::Str => {
+                    if c == '\\' {
+                        i += 1;
+                    } else if c == '"' {
+                        state = State::Code;
+                    }
+                }
+                State::RawStr(hashes) => {
+                    let closes = chars[i + 1..]
+                        .iter()
+                        .take(hashes)
+                        .filter(|h| **h == '#')
+                        .count()
+                        == hashes;
+                    if c == '"' && closes {
+                        state = State::Code;
+                        i += hashes;
+                    }
+                }
+                State::Code => {
+                    let after_ident =
+                        i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_');
+                    if c == '/' && next == Some('/') {
+                        break;
+                    } else if c == '/' && next == Some('*') {
+                        state = State::BlockComment(1);
+                        i += 1;
+                    } else if c == '"' {
+                        state = State::Str;
+                    } else if c == 'r' && !after_ident && matches!(next, Some('"') | Some('#')) {
+                        let hashes = chars[i + 1..].iter().take_while(|h| **h == '#').count();
+                        if chars.get(i + 1 + hashes) == Some(&'"') {
+                            state = State::RawStr(hashes);
+                            i += 1 + hashes;
+                        }
+                    } else if c == '\'' {
+                        // Char literals; a lone quote is a lifetime
+                        if next == Some('\\') {
+                            if let Some(close) = chars[i + 2..].iter().position(|ch| *ch == '\'') {
+                                i += 2 + close;
+                            }
+                        } else if chars.get(i + 2) == Some(&'\'') {
+                            i += 2;
+                        }
+                    } else if c == '{' {
+                        depth += 1;
+                    } else if c == '}' {
+                        depth = depth.saturating_sub(1);
+                    }
+                }
+            }
+            i += 1;
+        }
+        depths.push((start, depth));
+    }
+    depths
+}
+
+/// Kind and name of the item a line declares, if any
+fn item_header(line: &str) -> Option<(&'static str, String)> {
+    const MODIFIERS: [&str; 9] = [
+        "pub(crate) ",
+        "pub(super) ",
+        "pub(self) ",
+        "pub ",
+        "async ",
+        "const ",
+        "unsafe ",
+        "default ",
+        "extern \"C\" ",
+    ];
+    let mut rest = line.trim_start();
+    while let Some(stripped) = MODIFIERS.iter().find_map(|m| rest.strip_prefix(m)) {
+        rest = stripped.trim_start();
+    }
+    let ident = |s: &str| -> String {
+        s.trim_start()
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect()
+    };
+
+    for kind in ["fn", "struct", "enum", "trait"] {
+        if let Some(after) = rest.strip_prefix(kind).and_then(|r| r.strip_prefix(' ')) {
+            let name = ident(after);
+            return (!name.is_empty()).then_some((kind, name));
+        }
+    }
+    if let Some(after) = rest.strip_prefix("macro_rules!") {
+        let name = ident(after);
+        return (!name.is_empty()).then_some(("macro", name));
+    }
+    if let Some(after) = rest
+        .strip_prefix("impl")
+        .filter(|r| r.starts_with([' ', '<']))
+    {
+        // Skip the impl's own generic parameters, then keep `Trait for Type` up to the body
+        let mut header = after.trim_start();
+        if header.starts_with('<') {
+            // The `>` of a `->` in an `Fn() -> T` bound closes nothing
+            let mut nesting = 0usize;
+            let mut prev = ' ';
+            let close = header.char_indices().find(|(_, ch)| {
+                match (prev, *ch) {
+                    (_, '<') => nesting += 1,
+                    ('-', '>') => {}
+                    (_, '>') => nesting -= 1,
+                    _ => {}
+                }
+                prev = *ch;
+                nesting == 0
+            });
+            header = close.map(|(idx, _)| &header[idx + 1..]).unwrap_or("");
+        }
+        let header = header.split('{').next().unwrap_or_default();
+        let header = header.split(" where").next().unwrap_or_default();
+        let name = header.split_whitespace().collect::<Vec<_>>().join(" ");
+        return (!name.is_empty()).then_some(("impl", name));
+    }
+    None
+}
+
+/// Last line (0-based) of the item declared on line `start` at brace depth `level`
+fn item_end(
+    lines: &[&str],
+    depths: &[(usize, usize)],
+    level: usize,
+    start: usize,
+    to: usize,
+) -> usize {
+    let mut opened = false;
+    for (j, (line, (_, depth_after))) in lines.iter().zip(depths).enumerate().take(to).skip(start) {
+        let line = line.trim_end();
+        if *depth_after > level {
+            opened = true;
+        }
+        if opened && *depth_after == level {
+            return j;
+        }
+        // Bodiless items, and bodies opened and closed on one line
+        if !opened && (line.ends_with(';') || (line.contains('{') && line.ends_with('}'))) {
+            return j;
+        }
+    }
+    to.saturating_sub(1)
+}
+
+fn collect_items(
+    lines: &[&str],
+    depths: &[(usize, usize)],
+    level: usize,
+    (from, to): (usize, usize),
+    parent: Option<&str>,
+    chunks: &mut Vec<SourceChunk>,
+) {
+    let mut i = from;
+    while i < to {
+        let header = (depths[i].0 == level)
+            .then(|| item_header(lines[i]))
+            .flatten();
+        let Some((kind, name)) = header else {
+            i += 1;
+            continue;
+        };
+        let end = item_end(lines, depths, level, i, to);
+        // Include the doc comments and attributes above the item
+        let mut start = i;
+        while start > from && depths[start - 1].0 == level {
+            let above = lines[start - 1].trim_start();
+            if above.starts_with("///") || above.starts_with("#[") {
+                start -= 1;
+            } else {
+                break;
+            }
+        }
+        let name = match parent {
+            Some(parent) => format!("{}::{}", parent, name),
+            None => name,
+        };
+
+        let before = chunks.len();
+        if matches!(kind, "impl" | "trait") && end + 1 - start > MAX_CHUNK_LINES {
+            let owner = name.rsplit(" for ").next().unwrap_or(&name).to_string();
+            collect_items(lines, depths, level + 1, (i + 1, end), Some(&owner), chunks);
+        }
+        if chunks.len() == before {
+            chunks.push(SourceChunk {
+                kind,
+                name,
+                start_line: start + 1,
+                end_line: end + 1,
+                text: lines[start..=end].join("\n"),
+            });
+        }
+        i = end + 1;
+    }
+}
+
+/// Split Rust source into item chunks; items inside `mod` blocks such as tests are skipped
+pub fn chunk_source(source: &str) -> Vec<SourceChunk> {
+    let lines: Vec<&str> = source.lines().collect();
+    let depths = brace_depths(source);
+    let mut chunks = Vec::new();
+    collect_items(&lines, &depths, 0, (0, lines.len()), None, &mut chunks);
+    chunks
+}
+
+/// An embedded chunk of adapter source
+#[derive(Embed, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CodeChunk {
+    /// `<path>:<start>-<end>`
+    pub id: String,
+    /// Repository-relative file path
+    pub path: String,
+    pub adapter: String,
+    pub kind: String,
+    pub name: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    #[embed]
+    pub text: String,
+}
+
+impl CodeChunk {
+    pub fn new(path: &str, adapter: &str, chunk: SourceChunk) -> Self {
+        Self {
+            id: format!("{}:{}-{}", path, chunk.start_line, chunk.end_line),
+            path: path.to_string(),
+            adapter: adapter.to_string(),
+            kind: chunk.kind.to_string(),
+            name: chunk.name,
+            start_line: chunk.start_line,
+            end_line: chunk.end_line,
+            text: chunk.text,
+        }
+    }
+
+    /// First lines of the chunk with a location header, for prompts and tool output
+    pub fn excerpt(&self, max_lines: usize) -> String {
+        let mut lines: Vec<&str> = self.text.lines().take(max_lines).collect();
+        if self.text.lines().count() > max_lines {
+            lines.push("// ...");
+        }
+        format!(
+            "// {}:{}-{} ({} {})\n{}",
+            self.path,
+            self.start_line,
+            self.end_line,
+            self.kind,
+            self.name,
+            lines.join("\n")
+        )
+    }
+}
+
+/// A chunk matching a code search
+#[derive(Debug, Clone, Serialize)]
+pub struct CodeHit {
+    pub score: f64,
+    #[serde(flatten)]
+    pub chunk: CodeChunk,
+}
+
+struct IndexedFile {
+    content_hash: String,
+    chunks: Vec<(CodeChunk, OneOrMany<Embedding>)>,
+}
+
+/// What a refresh changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RefreshSummary {
+    pub files_embedded: usize,
+    pub files_removed: usize,
+    pub chunks: usize,
+}
+
+/// Embedded chunks of every adapter file, keyed by repository-relative path
+pub struct CodeIndex {
+    embedding_model: EmbeddingModel,
+    files: BTreeMap<String, IndexedFile>,
+}
+
+impl CodeIndex {
+    pub fn new(embedding_model: EmbeddingModel) -> Self {
+        Self {
+            embedding_model,
+            files: BTreeMap::new(),
+        }
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.files.values().map(|file| file.chunks.len()).sum()
+    }
+
+    /// Chunks of a file whose content changed since it was indexed, with the new content hash
+    fn changed_chunks(
+        &self,
+        relative_path: &str,
+        content: &str,
+    ) -> Option<(String, Vec<CodeChunk>)> {
+        let content_hash = bug_store::content_hash(content);
+        if self
+            .files
+            .get(relative_path)
+            .is_some_and(|file| file.content_hash == content_hash)
+        {
+            return None;
+        }
+
+        let adapter =
+            bug_store::adapter_from_path(relative_path).unwrap_or_else(|| "unknown".to_string());
+        let chunks = chunk_source(content)
+            .into_iter()
+            .map(|chunk| CodeChunk::new(relative_path, &adapter, chunk))
+            .collect();
+        Some((content_hash, chunks))
+    }
+
+    /// Re-chunk and re-embed a file if its content changed; returns whether it did
+    pub async fn update_file(&mut self, relative_path: &str, content: &str) -> Result<bool> {
+        let Some((content_hash, chunks)) = self.changed_chunks(relative_path, content) else {
+            return Ok(false);
+        };
+        let chunks = batch_embed::embed_configured(&self.embedding_model, chunks).await?;
+        debug!("Indexed {} chunks of {}", chunks.len(), relative_path);
+        self.files.insert(
+            relative_path.to_string(),
+            IndexedFile {
+                content_hash,
+                chunks,
+            },
+        );
+        Ok(true)
+    }
+
+    /// Re-embed changed files and drop files that no longer exist
+    ///
+    /// Chunks of all changed files are embedded together so large refreshes fill whole batches.
+    pub async fn refresh(&mut self, files: &[String]) -> Result<RefreshSummary> {
+        let current: HashSet<String> = files
+            .iter()
+            .map(|file| repo_paths::to_repo_relative(file))
+            .collect();
+        let before = self.files.len();
+        self.files.retain(|path, _| current.contains(path));
+        let mut summary = RefreshSummary {
+            files_removed: before - self.files.len(),
+            ..Default::default()
+        };
+
+        let mut changed = Vec::new();
+        let mut chunks = Vec::new();
+        for file in files {
+            let content = match tokio::fs::read_to_string(file).await {
+                Ok(content) => content,
+                Err(e) => {
+                    debug!("Skipping {} in code index: {}", file, e);
+                    continue;
+                }
+            };
+            let relative_path = repo_paths::to_repo_relative(file);
+            if let Some((content_hash, file_chunks)) = self.changed_chunks(&relative_path, &content)
+            {
+                changed.push((relative_path, content_hash, file_chunks.len()));
+                chunks.extend(file_chunks);
+            }
+        }
+
+        let mut embedded = batch_embed::embed_configured(&self.embedding_model, chunks)
+            .await?
+            .into_iter();
+        for (relative_path, content_hash, count) in changed {
+            let chunks: Vec<_> = embedded.by_ref().take(count).collect();
+            debug!("Indexed {} chunks of {}", chunks.len(), relative_path);
+            self.files.insert(
+                relative_path,
+                IndexedFile {
+                    content_hash,
+                    chunks,
+                },
+            );
+            summary.files_embedded += 1;
+        }
+        summary.chunks = self.chunk_count();
+        Ok(summary)
+    }
+
+    /// Chunks most similar to `query` among those accepted by `keep`
+    pub async fn search(
+        &self,
+        query: &str,
+        keep: impl Fn(&CodeChunk) -> bool,
+        limit: usize,
+    ) -> Result<Vec<CodeHit>> {
+        if limit == 0 || self.files.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = self.embedding_model.embed_text(query).await?;
+
+        let mut hits: Vec<CodeHit> = self
+            .files
+            .values()
+            .flat_map(|file| file.chunks.iter())
+            .filter(|(chunk, _)| keep(chunk))
+            .map(|(chunk, embeddings)| CodeHit {
+                score: embeddings
+                    .iter()
+                    .map(|embedding| cosine_similarity(&query.vec, &embedding.vec))
+                    .fold(f64::MIN, f64::max),
+                chunk: chunk.clone(),
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+}
+
+static SHARED: OnceCell<RwLock<CodeIndex>> = OnceCell::const_new();
+
+/// Code index shared by analysis and every MCP session, built over all adapter roots on first use
+pub async fn shared() -> Result<&'static RwLock<CodeIndex>> {
+    SHARED
+        .get_or_try_init(|| async {
+            let mut index = CodeIndex::new(crate::models::shared_embedding_model().await?);
+            let files = crate::discover_rust_files(Config::adapter_roots()).await?;
+            info!(
+                "🔧 Building code index over {} adapter files...",
+                files.len()
+            );
+            let summary = index.refresh(&files).await?;
+            info!("✅ Code index built with {} chunks", summary.chunks);
+            Ok::<_, anyhow::Error>(RwLock::new(index))
+        })
+        .await
+}
+
+/// Pick up changed adapter files if the index has been built; a no-op otherwise
+pub async fn refresh() -> Result<()> {
+    if let Some(index) = SHARED.get() {
+        let files = crate::discover_rust_files(Config::adapter_roots()).await?;
+        let summary = index.write().await.refresh(&files).await?;
+        if summary.files_embedded > 0 || summary.files_removed > 0 {
+            info!(
+                "🔄 Code index refreshed: {} files re-embedded, {} removed, {} chunks",
+                summary.files_embedded, summary.files_removed, summary.chunks
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Search adapter code, optionally within one adapter
+pub async fn search(query: &str, adapter: Option<&str>, limit: usize) -> Result<Vec<CodeHit>> {
+    let index = shared().await?.read().await;
+    index
+        .search(
+            query,
+            |chunk| adapter.is_none_or(|a| chunk.adapter.eq_ignore_ascii_case(a)),
+            limit,
+        )
+        .await
+}
+
+/// Related code from the same adapter and analogous code from other adapters, for a prompt
+///
+/// `file_content` refreshes the analyzed file in the index; `analyzed` is the part under
+/// analysis and its item signatures form the query. Returns `None` when code context is
+/// disabled or nothing matched.
+pub async fn related_context(
+    relative_path: &str,
+    file_content: &str,
+    analyzed: &str,
+) -> Result<Option<String>> {
+    if !Config::settings().embedding.code_context {
+        return Ok(None);
+    }
+    let index = shared().await?;
+    index
+        .write()
+        .await
+        .update_file(relative_path, file_content)
+        .await?;
+
+    let signatures: Vec<String> = chunk_source(analyzed)
+        .iter()
+        .filter_map(|chunk| chunk.text.lines().find(|line| item_header(line).is_some()))
+        .map(|line| line.trim().trim_end_matches('{').trim().to_string())
+        .collect();
+    let query = if signatures.is_empty() {
+        analyzed.to_string()
+    } else {
+        signatures.join("\n")
+    };
+    let adapter =
+        bug_store::adapter_from_path(relative_path).unwrap_or_else(|| "unknown".to_string());
+
+    let index = index.read().await;
+    let same = index
+        .search(
+            &query,
+            |chunk| chunk.adapter == adapter && chunk.path != relative_path,
+            SAME_ADAPTER_RESULTS,
+        )
+        .await?;
+    let other = index
+        .search(
+            &query,
+            |chunk| chunk.adapter != adapter,
+            OTHER_ADAPTER_RESULTS,
+        )
+        .await?;
+    if same.is_empty() && other.is_empty() {
+        return Ok(None);
+    }
+
+    let mut context = String::new();
+    if !same.is_empty() {
+        context.push_str(&format!(
+            "Related code elsewhere in the {} adapter:\n",
+            adapter
+        ));
+        for hit in &same {
+            context.push_str(&format!(
+                "```rust\n{}\n```\n",
+                hit.chunk.excerpt(CONTEXT_CHUNK_LINES)
+            ));
+        }
+    }
+    if !other.is_empty() {
+        context.push_str("Analogous code in other adapters:\n");
+        for hit in &other {
+            context.push_str(&format!(
+                "```rust\n{}\n```\n",
+                hit.chunk.excerpt(CONTEXT_CHUNK_LINES)
+            ));
+        }
+    }
+    Ok(Some(context))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"use std::fmt;
+
+/// Order book level
+#[derive(Debug)]
+pub struct Level {
+    price: f64, // "{" in a comment
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{{}}}", self.price)
+    }
+}
+
+pub(crate) async fn parse(raw: &str) -> Option<char> {
+    let brace = '{';
+    raw.chars().find(|c| *c == brace)
+}
+
+fn noop() {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_parse() {}
+}
+"#;
+
+    #[test]
+    fn test_chunks_top_level_items() {
+        let chunks = chunk_source(SOURCE);
+        let summary: Vec<(&str, &str, usize, usize)> = chunks
+            .iter()
+            .map(|c| (c.kind, c.name.as_str(), c.start_line, c.end_line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("struct", "Level", 3, 7),
+                ("impl", "fmt::Display for Level", 9, 13),
+                ("fn", "parse", 15, 18),
+                ("fn", "noop", 20, 20),
+            ]
+        );
+        assert!(chunks[0].text.starts_with("/// Order book level"));
+    }
+
+    #[test]
+    fn test_large_impl_is_split_into_methods() {
+        let mut source = String::from("impl<T: Clone> Client<T> {\n");
+        for i in 0..30 {
+            source.push_str(&format!(
+                "    pub fn method_{}(&self) -> u32 {{\n        {}\n    }}\n",
+                i, i
+            ));
+        }
+        source.push_str("}\n");
+        let chunks = chunk_source(&source);
+        assert_eq!(chunks.len(), 30);
+        assert_eq!(chunks[0].name, "Client<T>::method_0");
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (2, 4));
+    }
+
+    #[test]
+    fn test_item_headers() {
+        assert_eq!(
+            item_header("pub const fn new() -> Self {"),
+            Some(("fn", "new".to_string()))
+        );
+        assert_eq!(
+            item_header("impl<'a> Trait for &'a Foo where"),
+            Some(("impl", "Trait for &'a Foo".to_string()))
+        );
+        assert_eq!(
+            item_header("macro_rules! check {"),
+            Some(("macro", "check".to_string()))
+        );
+        assert_eq!(item_header("let fn_name = 1;"), None);
+    }
+
+    #[test]
+    fn test_impl_header_with_fn_bound() {
+        assert_eq!(
+            item_header("impl<F: Fn() -> T, T> Handler for Callback<F> {"),
+            Some(("impl", "Handler for Callback<F>".to_string()))
+        );
+        assert_eq!(
+            item_header("impl<F: FnMut(u8) -> Result<(), Error>> Foo for Bar<F> {"),
+            Some(("impl", "Foo for Bar<F>".to_string()))
+        );
+    }
+}
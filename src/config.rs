@@ -1,10 +1,19 @@
 // Configuration module for Nautilus Trader Rig
 //
-// This module contains configuration constants and paths used throughout the application
+// Settings are layered: built-in defaults, then an optional TOML file, then `RIG_*`
+// environment variables, then command line flags. Relative paths in the file are
+// resolved against the file's directory; relative paths given through the
+// environment or the command line are resolved against the current directory.
 
-use std::path::Path;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-/// Application configuration constants
+use crate::repo_paths::lexical_normalize;
+use crate::scheduler::{parse_schedule, JobKind};
+
+/// Application configuration constants and accessors for the loaded settings
 pub struct Config;
 
 #[allow(dead_code)]
@@ -12,18 +21,24 @@ impl Config {
     /// Path to the environment file (relative to crate dir)
     pub const ENV_FILE_PATH: &'static str = ".env";
 
-    /// Default MCP server port
-    pub const DEFAULT_MCP_PORT: u16 = 3000;
+    /// Configuration file looked up in the crate directory when none is given
+    pub const CONFIG_FILE: &'static str = "rig.toml";
+
+    /// Environment variable naming the configuration file
+    pub const CONFIG_FILE_ENV: &'static str = "RIG_CONFIG";
+
+    /// Default MCP server bind address
+    pub const DEFAULT_MCP_BIND: &'static str = "localhost:8080";
 
     /// Default bugs directory (relative name; use bugs_directory_path() for absolute)
     pub const BUGS_DIRECTORY: &'static str = "bugs";
 
+    /// Default bug pattern catalog directory
+    pub const PATTERNS_DIRECTORY: &'static str = "patterns";
+
     /// Default logs directory
     pub const LOGS_DIRECTORY: &'static str = "logs";
 
-    /// Nautilus Trader adapters directory (Python files)
-    pub const ADAPTERS_DIRECTORY: &'static str = "../nautilus_trader/adapters";
-
     /// Core Rust adapters directory
     /// Note: This path is relative to the nautilus-trader-rig crate directory.
     /// The adapters live at the repo root under `crates/adapters`, so from
@@ -36,15 +51,621 @@ impl Config {
     /// Vector similarity search limit
     pub const DEFAULT_SEARCH_LIMIT: usize = 10;
 
+    /// Upper bound accepted for the search limit
+    pub const MAX_SEARCH_LIMIT: usize = 100;
+
     /// DeepSeek model name
     pub const DEEPSEEK_MODEL: &'static str = "deepseek-chat";
 
-    /// FastEmbed model dimension
-    pub const FASTEMBED_DIMENSION: usize = 384;
+    /// Environment variable holding the DeepSeek API key
+    pub const DEEPSEEK_API_KEY_ENV: &'static str = "DEEPSEEK_API_KEY";
+
+    /// Candidates each retriever contributes before fusion and re-ranking
+    pub const DEFAULT_SEARCH_CANDIDATES: usize = 20;
+
+    /// Cross-encoder re-ranking of search results is off unless a model is named
+    pub const DEFAULT_RERANKER: &'static str = "off";
+
+    /// FastEmbed cache directory of the reranker model
+    pub const RERANKER_DIRECTORY: &'static str = "models/rerankers";
+
+    /// Texts per embedding batch
+    pub const DEFAULT_EMBED_BATCH_SIZE: usize = 64;
+
+    /// Largest embedding batch rig passes to FastEmbed
+    pub const MAX_EMBED_BATCH_SIZE: usize = 1024;
+
+    /// Text held by embedding batches in flight at once, in MiB
+    pub const DEFAULT_EMBED_MEMORY_MB: usize = 256;
+
+    /// Embeddings imported with `index import`, reused instead of re-embedding
+    pub const INDEX_CACHE_FILE: &'static str = "index/embeddings.bin";
+
+    /// FastEmbed model used for embeddings; its dimension comes from the model info
+    pub const FASTEMBED_MODEL: &'static str = "AllMiniLML6V2";
+
+    /// Default location of the pre-downloaded FastEmbed model files
+    pub const FASTEMBED_MODEL_DIRECTORY: &'static str =
+        "models/Qdrant--all-MiniLM-L6-v2-onnx/snapshots";
+
+    /// Default provider request budget
+    pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 30;
+
+    /// Default number of provider requests allowed in flight at once
+    pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 2;
+
+    /// Default jobs directory for scheduler and queue state
+    pub const JOBS_DIRECTORY: &'static str = "jobs";
+
+    /// Default cron schedule for full sweeps (sec min hour day month weekday): daily at 02:00 UTC
+    pub const DEFAULT_FULL_SWEEP_SCHEDULE: &'static str = "0 0 2 * * *";
+
+    /// Default cron schedule for diff sweeps of changed files: hourly
+    pub const DEFAULT_DIFF_SWEEP_SCHEDULE: &'static str = "0 15 * * * *";
+
+    /// Default cron schedule for re-verifying open bugs: Sundays at 04:00 UTC
+    pub const DEFAULT_REVERIFY_SCHEDULE: &'static str = "0 0 4 * * Sun";
+
+    /// Environment variables overriding the schedules; set to `off` to disable a job
+    pub const FULL_SWEEP_SCHEDULE_ENV: &'static str = "RIG_FULL_SWEEP_SCHEDULE";
+    pub const DIFF_SWEEP_SCHEDULE_ENV: &'static str = "RIG_DIFF_SWEEP_SCHEDULE";
+    pub const REVERIFY_SCHEDULE_ENV: &'static str = "RIG_REVERIFY_SCHEDULE";
+}
+
+/// Directories the rig reads from and writes to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathSettings {
+    /// Adapter directories to analyze; each contains `<adapter>/src/*.rs`
+    pub roots: Vec<PathBuf>,
+    pub bugs_dir: PathBuf,
+    pub jobs_dir: PathBuf,
+    pub logs_dir: PathBuf,
+    /// Bug pattern catalog, one JSON file per pattern
+    pub patterns_dir: PathBuf,
+}
+
+impl Default for PathSettings {
+    fn default() -> Self {
+        Self {
+            roots: vec![PathBuf::from(Config::CORE_ADAPTERS_DIRECTORY)],
+            bugs_dir: PathBuf::from(Config::BUGS_DIRECTORY),
+            jobs_dir: PathBuf::from(Config::JOBS_DIRECTORY),
+            logs_dir: PathBuf::from(Config::LOGS_DIRECTORY),
+            patterns_dir: PathBuf::from(Config::PATTERNS_DIRECTORY),
+        }
+    }
+}
+
+/// LLM provider settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProviderSettings {
+    pub model: String,
+    /// Environment variable the API key is read from
+    pub api_key_env: String,
+}
+
+impl Default for ProviderSettings {
+    fn default() -> Self {
+        Self {
+            model: Config::DEEPSEEK_MODEL.to_string(),
+            api_key_env: Config::DEEPSEEK_API_KEY_ENV.to_string(),
+        }
+    }
+}
+
+/// Local embedding model settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingSettings {
+    /// FastEmbed model name (`AllMiniLML6V2`) or code (`Qdrant/all-MiniLM-L6-v2-onnx`)
+    pub model: String,
+    /// Directory holding `model.onnx` and the tokenizer files
+    pub model_dir: PathBuf,
+    /// Never download the model; fail when `model_dir` has no bundle
+    pub offline: bool,
+    pub search_limit: usize,
+    /// Add related adapter code retrieved from the code index to analysis prompts
+    pub code_context: bool,
+    /// Vector and lexical candidates fused per pattern or bug search
+    pub search_candidates: usize,
+    /// FastEmbed cross-encoder re-ranking fused results (`BGERerankerBase`), or `off`
+    pub reranker: String,
+    /// FastEmbed cache directory the reranker is loaded from
+    pub reranker_dir: PathBuf,
+    /// Imported index whose embeddings are reused for unchanged patterns and bugs
+    pub index_cache: PathBuf,
+    /// Texts per embedding batch
+    pub batch_size: usize,
+    /// Batches embedded at once; 0 uses one worker per core. ONNX Runtime runs each batch on
+    /// every core, so workers overlap tokenization and session calls rather than add CPU
+    pub threads: usize,
+    /// Text held by batches in flight at once, in MiB; each batch gets an equal share
+    pub memory_cap_mb: usize,
+    /// Deprecated and ignored: the dimension comes from `model`. Still accepted so
+    /// config files written before it was removed keep loading.
+    #[serde(skip_serializing)]
+    pub dimension: Option<usize>,
+}
+
+impl Default for EmbeddingSettings {
+    fn default() -> Self {
+        Self {
+            model: Config::FASTEMBED_MODEL.to_string(),
+            model_dir: PathBuf::from(Config::FASTEMBED_MODEL_DIRECTORY),
+            offline: false,
+            code_context: true,
+            search_limit: Config::DEFAULT_SEARCH_LIMIT,
+            search_candidates: Config::DEFAULT_SEARCH_CANDIDATES,
+            reranker: Config::DEFAULT_RERANKER.to_string(),
+            reranker_dir: PathBuf::from(Config::RERANKER_DIRECTORY),
+            index_cache: PathBuf::from(Config::INDEX_CACHE_FILE),
+            batch_size: Config::DEFAULT_EMBED_BATCH_SIZE,
+            threads: 0,
+            memory_cap_mb: Config::DEFAULT_EMBED_MEMORY_MB,
+            dimension: None,
+        }
+    }
+}
+
+/// Limits on provider requests
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitSettings {
+    pub requests_per_minute: u32,
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: Config::DEFAULT_REQUESTS_PER_MINUTE,
+            max_concurrent_requests: Config::DEFAULT_MAX_CONCURRENT_REQUESTS,
+        }
+    }
+}
+
+/// MCP server settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpSettings {
+    /// `host:port` the MCP server, dashboard and health endpoints listen on
+    pub bind: String,
+}
+
+impl Default for McpSettings {
+    fn default() -> Self {
+        Self {
+            bind: Config::DEFAULT_MCP_BIND.to_string(),
+        }
+    }
+}
+
+/// Cron schedules of the background jobs; `off` disables a job
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleSettings {
+    pub full_sweep: String,
+    pub diff_sweep: String,
+    pub reverify: String,
+}
+
+impl Default for ScheduleSettings {
+    fn default() -> Self {
+        Self {
+            full_sweep: Config::DEFAULT_FULL_SWEEP_SCHEDULE.to_string(),
+            diff_sweep: Config::DEFAULT_DIFF_SWEEP_SCHEDULE.to_string(),
+            reverify: Config::DEFAULT_REVERIFY_SCHEDULE.to_string(),
+        }
+    }
+}
+
+/// Complete application settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub paths: PathSettings,
+    pub provider: ProviderSettings,
+    pub embedding: EmbeddingSettings,
+    pub rate_limits: RateLimitSettings,
+    pub mcp: McpSettings,
+    pub schedules: ScheduleSettings,
+    /// Configuration file the settings were read from, if any
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+/// Global flags accepted before or after any command
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliOverrides {
+    pub config: Option<PathBuf>,
+    pub roots: Vec<PathBuf>,
+    pub bugs_dir: Option<PathBuf>,
+    pub model: Option<String>,
+    pub bind: Option<String>,
+    pub offline: bool,
+}
+
+impl CliOverrides {
+    /// Remove the global configuration flags from `args`, leaving command arguments in place
+    pub fn extract(args: &mut Vec<String>) -> Result<Self> {
+        let mut overrides = Self::default();
+        let mut remaining = Vec::with_capacity(args.len());
+        let mut iter = std::mem::take(args).into_iter();
+        while let Some(arg) = iter.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            if flag == "--offline" && inline.is_none() {
+                overrides.offline = true;
+                continue;
+            }
+            if !matches!(
+                flag.as_str(),
+                "--config" | "--root" | "--bugs-dir" | "--model" | "--bind"
+            ) {
+                remaining.push(arg);
+                continue;
+            }
+            let value = match inline {
+                Some(value) => value,
+                None => iter
+                    .next()
+                    .ok_or_else(|| anyhow!("{} requires a value", flag))?,
+            };
+            match flag.as_str() {
+                "--config" => overrides.config = Some(PathBuf::from(value)),
+                "--root" => overrides.roots.push(PathBuf::from(value)),
+                "--bugs-dir" => overrides.bugs_dir = Some(PathBuf::from(value)),
+                "--model" => overrides.model = Some(value),
+                _ => overrides.bind = Some(value),
+            }
+        }
+        *args = remaining;
+        Ok(overrides)
+    }
+}
+
+/// Make `path` absolute against `base` and drop `.`/`..` segments
+fn resolve_against(base: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        lexical_normalize(path)
+    } else {
+        lexical_normalize(&base.join(path))
+    }
 }
 
+fn current_dir() -> PathBuf {
+    std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+}
+
+impl Settings {
+    /// Built-in defaults with paths resolved against the crate directory
+    pub fn built_in() -> Self {
+        let mut settings = Self::default();
+        settings.resolve_paths(Config::manifest_dir());
+        settings
+    }
+
+    /// Parse a TOML document; relative paths resolve against `base`
+    pub fn from_toml(text: &str, base: &Path) -> Result<Self> {
+        let mut settings: Settings = toml::from_str(text)?;
+        settings.resolve_paths(base);
+        Ok(settings)
+    }
+
+    /// Load every layer and validate the result
+    pub fn load(cli: &CliOverrides) -> Result<Self> {
+        let mut settings = match Self::config_file(cli) {
+            Some(path) => {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read config file {}", path.display()))?;
+                let base = path
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_else(current_dir);
+                let mut settings = Self::from_toml(&text, &base)
+                    .with_context(|| format!("Invalid config file {}", path.display()))?;
+                settings.source = Some(resolve_against(&current_dir(), &path));
+                if settings.embedding.dimension.is_some() {
+                    eprintln!(
+                        "⚠️ embedding.dimension in {} is deprecated and ignored; the dimension comes from embedding.model",
+                        path.display()
+                    );
+                }
+                settings
+            }
+            None => Self::built_in(),
+        };
+        settings.apply_env(|key| std::env::var(key).ok())?;
+        settings.apply_cli(cli);
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Configuration file chosen by `--config`, `RIG_CONFIG` or the crate directory default
+    fn config_file(cli: &CliOverrides) -> Option<PathBuf> {
+        if let Some(path) = &cli.config {
+            return Some(path.clone());
+        }
+        if let Ok(path) = std::env::var(Config::CONFIG_FILE_ENV) {
+            if !path.trim().is_empty() {
+                return Some(PathBuf::from(path));
+            }
+        }
+        let default = Config::manifest_dir().join(Config::CONFIG_FILE);
+        default.exists().then_some(default)
+    }
+
+    fn resolve_paths(&mut self, base: &Path) {
+        let paths = &mut self.paths;
+        paths.roots = paths
+            .roots
+            .iter()
+            .map(|root| resolve_against(base, root))
+            .collect();
+        paths.bugs_dir = resolve_against(base, &paths.bugs_dir);
+        paths.jobs_dir = resolve_against(base, &paths.jobs_dir);
+        paths.logs_dir = resolve_against(base, &paths.logs_dir);
+        paths.patterns_dir = resolve_against(base, &paths.patterns_dir);
+        self.embedding.model_dir = resolve_against(base, &self.embedding.model_dir);
+        self.embedding.reranker_dir = resolve_against(base, &self.embedding.reranker_dir);
+        self.embedding.index_cache = resolve_against(base, &self.embedding.index_cache);
+    }
+
+    /// Apply `RIG_*` environment overrides read through `lookup`
+    pub fn apply_env(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let cwd = current_dir();
+        let path = |key: &str| {
+            lookup(key)
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| resolve_against(&cwd, Path::new(v.trim())))
+        };
+        let text = |key: &str| {
+            lookup(key)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        fn number<T: std::str::FromStr>(key: &str, value: Option<String>) -> Result<Option<T>> {
+            value
+                .map(|v| {
+                    v.parse::<T>()
+                        .map_err(|_| anyhow!("{} must be a whole number, got '{}'", key, v))
+                })
+                .transpose()
+        }
+        fn flag(key: &str, value: &str) -> Result<bool> {
+            match value.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Ok(true),
+                "0" | "false" | "no" | "off" => Ok(false),
+                _ => bail!("{} must be true or false, got '{}'", key, value),
+            }
+        }
+
+        if let Some(roots) = lookup("RIG_ROOTS").filter(|v| !v.trim().is_empty()) {
+            self.paths.roots = std::env::split_paths(roots.trim())
+                .map(|root| resolve_against(&cwd, &root))
+                .collect();
+        }
+        if let Some(dir) = path("RIG_BUGS_DIR") {
+            self.paths.bugs_dir = dir;
+        }
+        if let Some(dir) = path("RIG_JOBS_DIR") {
+            self.paths.jobs_dir = dir;
+        }
+        if let Some(dir) = path("RIG_LOGS_DIR") {
+            self.paths.logs_dir = dir;
+        }
+        if let Some(dir) = path("RIG_PATTERNS_DIR") {
+            self.paths.patterns_dir = dir;
+        }
+        if let Some(model) = text("RIG_MODEL") {
+            self.provider.model = model;
+        }
+        if let Some(model) = text("RIG_EMBEDDING_MODEL") {
+            self.embedding.model = model;
+        }
+        if let Some(dir) = path("RIG_EMBEDDING_MODEL_DIR") {
+            self.embedding.model_dir = dir;
+        }
+        if let Some(offline) = text("RIG_OFFLINE") {
+            self.embedding.offline = flag("RIG_OFFLINE", &offline)?;
+        }
+        if let Some(code_context) = text("RIG_CODE_CONTEXT") {
+            self.embedding.code_context = flag("RIG_CODE_CONTEXT", &code_context)?;
+        }
+        if let Some(limit) = number("RIG_SEARCH_LIMIT", text("RIG_SEARCH_LIMIT"))? {
+            self.embedding.search_limit = limit;
+        }
+        if let Some(candidates) = number("RIG_SEARCH_CANDIDATES", text("RIG_SEARCH_CANDIDATES"))? {
+            self.embedding.search_candidates = candidates;
+        }
+        if let Some(reranker) = text("RIG_RERANKER") {
+            self.embedding.reranker = reranker;
+        }
+        if let Some(dir) = path("RIG_RERANKER_DIR") {
+            self.embedding.reranker_dir = dir;
+        }
+        if let Some(file) = path("RIG_INDEX_CACHE") {
+            self.embedding.index_cache = file;
+        }
+        if let Some(batch_size) = number("RIG_EMBED_BATCH_SIZE", text("RIG_EMBED_BATCH_SIZE"))? {
+            self.embedding.batch_size = batch_size;
+        }
+        if let Some(threads) = number("RIG_EMBED_THREADS", text("RIG_EMBED_THREADS"))? {
+            self.embedding.threads = threads;
+        }
+        if let Some(memory) = number("RIG_EMBED_MEMORY_MB", text("RIG_EMBED_MEMORY_MB"))? {
+            self.embedding.memory_cap_mb = memory;
+        }
+        if let Some(rpm) = number("RIG_REQUESTS_PER_MINUTE", text("RIG_REQUESTS_PER_MINUTE"))? {
+            self.rate_limits.requests_per_minute = rpm;
+        }
+        if let Some(concurrent) = number(
+            "RIG_MAX_CONCURRENT_REQUESTS",
+            text("RIG_MAX_CONCURRENT_REQUESTS"),
+        )? {
+            self.rate_limits.max_concurrent_requests = concurrent;
+        }
+        if let Some(bind) = text("RIG_MCP_BIND") {
+            self.mcp.bind = bind;
+        }
+        if let Some(schedule) = text(Config::FULL_SWEEP_SCHEDULE_ENV) {
+            self.schedules.full_sweep = schedule;
+        }
+        if let Some(schedule) = text(Config::DIFF_SWEEP_SCHEDULE_ENV) {
+            self.schedules.diff_sweep = schedule;
+        }
+        if let Some(schedule) = text(Config::REVERIFY_SCHEDULE_ENV) {
+            self.schedules.reverify = schedule;
+        }
+        Ok(())
+    }
+
+    /// Apply command line flags, the highest priority layer
+    pub fn apply_cli(&mut self, cli: &CliOverrides) {
+        let cwd = current_dir();
+        if !cli.roots.is_empty() {
+            self.paths.roots = cli
+                .roots
+                .iter()
+                .map(|root| resolve_against(&cwd, root))
+                .collect();
+        }
+        if let Some(dir) = &cli.bugs_dir {
+            self.paths.bugs_dir = resolve_against(&cwd, dir);
+        }
+        if let Some(model) = &cli.model {
+            self.provider.model = model.clone();
+        }
+        if let Some(bind) = &cli.bind {
+            self.mcp.bind = bind.clone();
+        }
+        if cli.offline {
+            self.embedding.offline = true;
+        }
+    }
+
+    /// Check every setting, reporting all problems at once
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if self.paths.roots.is_empty() {
+            problems.push("paths.roots must list at least one adapters directory".to_string());
+        }
+        for (i, root) in self.paths.roots.iter().enumerate() {
+            if self.paths.roots[..i].contains(root) {
+                problems.push(format!(
+                    "paths.roots lists {} more than once",
+                    root.display()
+                ));
+            }
+        }
+        if self.provider.model.trim().is_empty() {
+            problems.push("provider.model must not be empty".to_string());
+        }
+        if self.provider.api_key_env.trim().is_empty() {
+            problems.push("provider.api_key_env must name an environment variable".to_string());
+        }
+        if let Err(e) = crate::models::resolve_model(&self.embedding.model) {
+            problems.push(format!("embedding.model: {}", e));
+        }
+        if !(1..=Config::MAX_SEARCH_LIMIT).contains(&self.embedding.search_limit) {
+            problems.push(format!(
+                "embedding.search_limit must be between 1 and {}, got {}",
+                Config::MAX_SEARCH_LIMIT,
+                self.embedding.search_limit
+            ));
+        }
+        if !(1..=Config::MAX_SEARCH_LIMIT).contains(&self.embedding.search_candidates) {
+            problems.push(format!(
+                "embedding.search_candidates must be between 1 and {}, got {}",
+                Config::MAX_SEARCH_LIMIT,
+                self.embedding.search_candidates
+            ));
+        }
+        if !(1..=Config::MAX_EMBED_BATCH_SIZE).contains(&self.embedding.batch_size) {
+            problems.push(format!(
+                "embedding.batch_size must be between 1 and {}, got {}",
+                Config::MAX_EMBED_BATCH_SIZE,
+                self.embedding.batch_size
+            ));
+        }
+        if self.embedding.memory_cap_mb == 0 {
+            problems.push("embedding.memory_cap_mb must be greater than 0".to_string());
+        }
+        if crate::hybrid::reranker_enabled(&self.embedding.reranker) {
+            if let Err(e) = crate::hybrid::resolve_reranker(&self.embedding.reranker) {
+                problems.push(format!("embedding.reranker: {}", e));
+            }
+        }
+        if self.rate_limits.requests_per_minute == 0 {
+            problems.push("rate_limits.requests_per_minute must be greater than 0".to_string());
+        }
+        if self.rate_limits.max_concurrent_requests == 0 {
+            problems.push("rate_limits.max_concurrent_requests must be greater than 0".to_string());
+        }
+        let valid_bind = self
+            .mcp
+            .bind
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+        if !valid_bind {
+            problems.push(format!(
+                "mcp.bind must be host:port, got '{}'",
+                self.mcp.bind
+            ));
+        }
+        for kind in JobKind::ALL {
+            let expression = self.schedule_of(kind).trim();
+            if !expression.eq_ignore_ascii_case("off") {
+                if let Err(e) = parse_schedule(kind, expression) {
+                    problems.push(format!("schedules.{}: {}", kind, e));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            bail!("Invalid configuration:\n  - {}", problems.join("\n  - "))
+        }
+    }
+
+    /// Configured cron expression of a scheduled job
+    pub fn schedule_of(&self, kind: JobKind) -> &str {
+        match kind {
+            JobKind::FullSweep => &self.schedules.full_sweep,
+            JobKind::DiffSweep => &self.schedules.diff_sweep,
+            JobKind::Reverify => &self.schedules.reverify,
+        }
+    }
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
 #[allow(dead_code)]
 impl Config {
+    /// Load, validate and install the settings for this process
+    pub fn init(cli: &CliOverrides) -> Result<&'static Settings> {
+        let settings = Settings::load(cli)?;
+        Ok(SETTINGS.get_or_init(|| settings))
+    }
+
+    /// Settings of this process; loads defaults, file and environment if `init` was not called
+    pub fn settings() -> &'static Settings {
+        SETTINGS.get_or_init(|| {
+            Settings::load(&CliOverrides::default()).unwrap_or_else(|e| {
+                eprintln!("⚠️ {:#}; falling back to built-in settings", e);
+                Settings::built_in()
+            })
+        })
+    }
+
     /// Absolute path to this crate's directory at compile time
     pub fn manifest_dir() -> &'static Path {
         Path::new(env!("CARGO_MANIFEST_DIR"))
@@ -55,19 +676,40 @@ impl Config {
         Path::new(Self::ENV_FILE_PATH)
     }
 
-    /// Get the full path to the bugs directory
-    pub fn bugs_directory() -> &'static Path {
-        Path::new(Self::BUGS_DIRECTORY)
+    /// Absolute bugs directory path, independent of current working directory
+    pub fn bugs_directory_path() -> PathBuf {
+        Self::settings().paths.bugs_dir.clone()
     }
 
-    /// Get the full path to the logs directory
-    pub fn logs_directory() -> &'static Path {
-        Path::new(Self::LOGS_DIRECTORY)
+    /// Absolute jobs directory path, independent of current working directory
+    pub fn jobs_directory_path() -> PathBuf {
+        Self::settings().paths.jobs_dir.clone()
     }
 
-    /// Absolute bugs directory path, independent of current working directory
-    pub fn bugs_directory_path() -> std::path::PathBuf {
-        Self::manifest_dir().join("bugs")
+    /// Absolute logs directory path
+    pub fn logs_directory_path() -> PathBuf {
+        Self::settings().paths.logs_dir.clone()
+    }
+
+    /// Absolute bug pattern catalog directory path
+    pub fn patterns_directory_path() -> PathBuf {
+        Self::settings().paths.patterns_dir.clone()
+    }
+
+    /// Locations searched for the `.env` file, in order
+    pub fn env_file_candidates() -> Vec<PathBuf> {
+        vec![
+            Self::manifest_dir().join(Self::ENV_FILE_PATH),
+            PathBuf::from(Self::ENV_FILE_PATH),
+            PathBuf::from("nautilus-trader-rig").join(Self::ENV_FILE_PATH),
+        ]
+    }
+
+    /// First existing `.env` file, which is the one loaded at startup
+    pub fn env_file_in_use() -> Option<PathBuf> {
+        Self::env_file_candidates()
+            .into_iter()
+            .find(|path| path.exists())
     }
 
     /// Check if environment file exists
@@ -75,68 +717,166 @@ impl Config {
         Self::env_file_path().exists()
     }
 
-    /// Check if bugs directory exists
-    pub fn bugs_directory_exists() -> bool {
-        Self::bugs_directory().exists()
-    }
-
     /// Check if logs directory exists
     pub fn logs_directory_exists() -> bool {
-        Self::logs_directory().exists()
+        Self::logs_directory_path().exists()
     }
 
     /// Generate a log file path with timestamp
-    pub fn generate_log_file_path() -> std::path::PathBuf {
+    pub fn generate_log_file_path() -> PathBuf {
         let timestamp = chrono::Utc::now().format("%Y-%m-%d_%H-%M-%S").to_string();
-        Self::logs_directory().join(format!("nautilus_trader_rig_{}.log", timestamp))
+        Self::logs_directory_path().join(format!("nautilus_trader_rig_{}.log", timestamp))
+    }
+
+    /// Configured adapter roots, each an absolute path
+    pub fn adapter_roots() -> &'static [PathBuf] {
+        &Self::settings().paths.roots
+    }
+
+    /// Absolute paths for Rust adapter directories (preferred for robust execution)
+    pub fn all_rust_adapter_directories_abs() -> Vec<PathBuf> {
+        Self::adapter_roots().to_vec()
     }
 
-    /// Get the full path to the Rust adapters directory
-    pub fn rust_adapters_directory() -> &'static Path {
-        Path::new(Self::CORE_ADAPTERS_DIRECTORY)
+    /// Absolute path of the first adapter root
+    pub fn core_adapters_directory_abs() -> PathBuf {
+        Self::adapter_roots()
+            .first()
+            .cloned()
+            .unwrap_or_else(|| Self::manifest_dir().join(Self::CORE_ADAPTERS_DIRECTORY))
     }
 
-    /// Get the full path to the Rust core directory
-    pub fn rust_core_directory() -> &'static Path {
-        Path::new(Self::CORE_ADAPTERS_DIRECTORY)
+    /// Get list of supported Rust file extensions
+    pub fn rust_extensions() -> &'static [&'static str] {
+        Self::RUST_FILE_EXTENSIONS
     }
+}
 
-    /// Check if Rust adapters directory exists
-    pub fn rust_adapters_directory_exists() -> bool {
-        Self::rust_adapters_directory().exists()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_defaults_are_valid_and_deduplicated() {
+        let settings = Settings::built_in();
+        settings.validate().unwrap();
+        assert_eq!(settings.paths.roots.len(), 1);
+        assert!(settings.paths.roots[0].ends_with("crates/adapters"));
+        assert!(settings.paths.bugs_dir.is_absolute());
     }
 
-    /// Check if Rust core directory exists
-    pub fn rust_core_directory_exists() -> bool {
-        Self::rust_core_directory().exists()
+    #[test]
+    fn test_file_layer_resolves_against_file_directory() {
+        let text = r#"
+            [paths]
+            roots = ["../nautilus_trader/crates/adapters", "/opt/extra/adapters"]
+            bugs_dir = "out/bugs"
+
+            [mcp]
+            bind = "0.0.0.0:9090"
+        "#;
+        let settings = Settings::from_toml(text, Path::new("/srv/rig")).unwrap();
+        assert_eq!(
+            settings.paths.roots,
+            vec![
+                PathBuf::from("/srv/nautilus_trader/crates/adapters"),
+                PathBuf::from("/opt/extra/adapters")
+            ]
+        );
+        assert_eq!(settings.paths.bugs_dir, PathBuf::from("/srv/rig/out/bugs"));
+        assert_eq!(settings.paths.jobs_dir, PathBuf::from("/srv/rig/jobs"));
+        assert_eq!(settings.mcp.bind, "0.0.0.0:9090");
+        assert_eq!(settings.provider.model, Config::DEEPSEEK_MODEL);
     }
 
-    /// Get all Rust adapter directories
-    pub fn all_rust_adapter_directories() -> Vec<&'static Path> {
-        vec![Self::rust_adapters_directory(), Self::rust_core_directory()]
+    #[test]
+    fn test_example_file_matches_defaults() {
+        let example =
+            Settings::from_toml(include_str!("../rig.example.toml"), Config::manifest_dir())
+                .unwrap();
+        assert_eq!(example, Settings::built_in());
     }
 
-    /// Absolute paths for Rust adapter directories (preferred for robust execution)
-    pub fn all_rust_adapter_directories_abs() -> Vec<std::path::PathBuf> {
-        let base = Self::manifest_dir();
-        vec![
-            base.join("../crates/adapters"),
-            base.join("../crates/adapters"), // kept twice to mirror existing API semantics
-        ]
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let err = Settings::from_toml("[paths]\nroot = [\"x\"]\n", Path::new("/srv")).unwrap_err();
+        assert!(err.to_string().contains("unknown field"), "{}", err);
     }
 
-    /// Absolute path to core adapters directory
-    pub fn core_adapters_directory_abs() -> std::path::PathBuf {
-        Self::manifest_dir().join("../crates/adapters")
+    #[test]
+    fn test_deprecated_dimension_is_ignored() {
+        let settings =
+            Settings::from_toml("[embedding]\ndimension = 384\n", Path::new("/srv")).unwrap();
+        assert_eq!(settings.embedding.dimension, Some(384));
+        settings.validate().unwrap();
     }
 
-    /// Get list of supported Rust file extensions
-    pub fn rust_extensions() -> &'static [&'static str] {
-        Self::RUST_FILE_EXTENSIONS
+    #[test]
+    fn test_env_then_cli_override() {
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("RIG_MODEL", "deepseek-reasoner"),
+            ("RIG_MCP_BIND", "127.0.0.1:7000"),
+            ("RIG_REQUESTS_PER_MINUTE", "12"),
+            ("RIG_EMBEDDING_MODEL", "Qdrant/all-MiniLM-L6-v2-onnx"),
+            (Config::REVERIFY_SCHEDULE_ENV, "off"),
+        ]);
+        let mut settings = Settings::built_in();
+        settings
+            .apply_env(|key| env.get(key).map(|v| v.to_string()))
+            .unwrap();
+        assert_eq!(settings.provider.model, "deepseek-reasoner");
+        assert_eq!(settings.rate_limits.requests_per_minute, 12);
+        assert_eq!(settings.schedules.reverify, "off");
+
+        let mut args: Vec<String> = [
+            "report",
+            "risk",
+            "--bind",
+            "localhost:9000",
+            "--root=/a",
+            "--offline",
+            "--root",
+            "/b",
+            "--json",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let cli = CliOverrides::extract(&mut args).unwrap();
+        assert_eq!(args, vec!["report", "risk", "--json"]);
+        settings.apply_cli(&cli);
+        assert_eq!(settings.mcp.bind, "localhost:9000");
+        assert_eq!(
+            settings.paths.roots,
+            vec![PathBuf::from("/a"), PathBuf::from("/b")]
+        );
+        assert!(settings.embedding.offline);
+        settings.validate().unwrap();
+
+        let bad = settings.apply_env(|key| (key == "RIG_SEARCH_LIMIT").then(|| "ten".to_string()));
+        assert!(bad.unwrap_err().to_string().contains("RIG_SEARCH_LIMIT"));
+        let bad = settings.apply_env(|key| (key == "RIG_OFFLINE").then(|| "maybe".to_string()));
+        assert!(bad.unwrap_err().to_string().contains("RIG_OFFLINE"));
     }
 
-    /// Get Rust adapter directory path by name
-    pub fn rust_adapter_path(adapter_name: &str) -> std::path::PathBuf {
-        Path::new(Self::CORE_ADAPTERS_DIRECTORY).join(adapter_name)
+    #[test]
+    fn test_validation_reports_every_problem() {
+        let mut settings = Settings::built_in();
+        settings.paths.roots.push(settings.paths.roots[0].clone());
+        settings.rate_limits.max_concurrent_requests = 0;
+        settings.mcp.bind = "8080".to_string();
+        settings.schedules.diff_sweep = "hourly".to_string();
+        settings.embedding.model = "word2vec".to_string();
+        let message = settings.validate().unwrap_err().to_string();
+        assert!(message.contains("more than once"), "{}", message);
+        assert!(
+            message.contains("rate_limits.max_concurrent_requests"),
+            "{}",
+            message
+        );
+        assert!(message.contains("mcp.bind"), "{}", message);
+        assert!(message.contains("schedules.diff_sweep"), "{}", message);
+        assert!(message.contains("embedding.model"), "{}", message);
     }
 }
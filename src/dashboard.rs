@@ -0,0 +1,599 @@
+//! Web dashboard for Nautilus Trader Rig
+//!
+//! Served from the same listener as the MCP endpoint. JSON APIs live under
+//! `/api` and server-rendered HTML pages under `/dashboard`. Every request reads
+//! the bugs directory afresh, so new runs show up without a restart.
+
+use anyhow::Result;
+use axum::{
+    extract::{Path as UrlPath, Query},
+    http::StatusCode,
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::get,
+    Json, Router,
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use tracing::{debug, error};
+
+use crate::bug_store::{self, BugFilter, StoredBug};
+use crate::config::Config;
+use crate::html_report::{escape_html, report_status, source_link, STYLE};
+use crate::repo_paths;
+use crate::run_diff;
+
+/// Severity labels, most severe first
+const SEVERITIES: [&str; 4] = ["CRITICAL", "HIGH", "MEDIUM", "LOW"];
+
+/// Lifecycle statuses offered by the bug list filter
+const STATUSES: [&str; 4] = ["active", "open", "regressed", "fixed"];
+
+/// Condensed bug entry for list views
+#[derive(Debug, Clone, Serialize)]
+pub struct BugListEntry {
+    pub bug_id: String,
+    pub severity: String,
+    pub status: String,
+    pub adapter: String,
+    pub category: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    pub timestamp: String,
+}
+
+impl BugListEntry {
+    pub fn from_bug(bug: &StoredBug) -> Self {
+        Self {
+            bug_id: bug.bug_id.clone(),
+            severity: bug.severity.to_uppercase(),
+            status: bug.status.to_string(),
+            adapter: bug.adapter(),
+            category: bug.category().to_string(),
+            description: bug.description.clone(),
+            file: bug.source_path().map(repo_paths::to_repo_relative),
+            line: bug.line_number(),
+            timestamp: bug.timestamp.clone(),
+        }
+    }
+}
+
+/// One analysis run in the run history
+#[derive(Debug, Clone, Serialize)]
+pub struct RunEntry {
+    pub run_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis_timestamp: Option<String>,
+    pub files_analyzed: u64,
+    pub bugs_found: u64,
+    pub failed_files: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lifecycle: Option<Value>,
+}
+
+impl RunEntry {
+    fn from_summary(run_id: String, summary: &Value) -> Self {
+        let info = &summary["analysis_summary"];
+        let failed_files = summary["file_results"]
+            .as_array()
+            .map(|results| {
+                results
+                    .iter()
+                    .filter(|r| {
+                        report_status(r["status"].as_str().unwrap_or("")) == "analysis_failed"
+                    })
+                    .count()
+            })
+            .unwrap_or(0);
+
+        Self {
+            run_id: info["run_id"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or(run_id),
+            analysis_timestamp: info["analysis_timestamp"].as_str().map(str::to_string),
+            files_analyzed: info["files_analyzed"].as_u64().unwrap_or(0),
+            bugs_found: info["bugs_found"].as_u64().unwrap_or(0),
+            failed_files,
+            lifecycle: info.get("lifecycle").cloned(),
+        }
+    }
+}
+
+/// Per-adapter overview of stored bugs and the latest run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AdapterOverview {
+    pub adapter: String,
+    /// Active (open or regressed) bugs per severity label
+    pub active_by_severity: BTreeMap<String, usize>,
+    pub active_bugs: usize,
+    pub fixed_bugs: usize,
+    pub files_in_latest_run: usize,
+    pub failed_in_latest_run: usize,
+}
+
+impl AdapterOverview {
+    /// Sum of severity ranks over active bugs, used to order the overview
+    fn weight(&self) -> usize {
+        self.active_by_severity
+            .iter()
+            .map(|(severity, count)| bug_store::severity_rank(severity) as usize * count)
+            .sum()
+    }
+}
+
+/// Load bugs matching a filter, most severe and most recent first
+pub async fn filtered_bugs(bugs_dir: &Path, filter: &BugFilter) -> Result<Vec<StoredBug>> {
+    let mut bugs: Vec<StoredBug> = bug_store::load_bugs(bugs_dir)
+        .await?
+        .into_iter()
+        .map(|(_, bug)| bug)
+        .filter(|bug| filter.matches(bug))
+        .collect();
+    bugs.sort_by(|a, b| {
+        b.severity_rank()
+            .cmp(&a.severity_rank())
+            .then_with(|| b.timestamp.cmp(&a.timestamp))
+    });
+    Ok(bugs)
+}
+
+/// Find a stored bug by ID
+pub async fn find_bug(bugs_dir: &Path, bug_id: &str) -> Result<Option<StoredBug>> {
+    Ok(bug_store::load_bugs(bugs_dir)
+        .await?
+        .into_iter()
+        .map(|(_, bug)| bug)
+        .find(|bug| bug.bug_id == bug_id))
+}
+
+/// Read the analysis run history, newest first
+pub async fn run_history(bugs_dir: &Path) -> Result<Vec<RunEntry>> {
+    let mut runs = Vec::new();
+    for path in run_diff::list_summary_files(bugs_dir).into_iter().rev() {
+        let content = tokio::fs::read_to_string(&path).await?;
+        match serde_json::from_str::<Value>(&content) {
+            Ok(summary) => runs.push(RunEntry::from_summary(
+                run_diff::run_id_from_path(&path),
+                &summary,
+            )),
+            Err(e) => debug!("Skipping unparseable summary {}: {}", path.display(), e),
+        }
+    }
+    Ok(runs)
+}
+
+/// Build the per-adapter overview from stored bugs and the latest run summary
+pub fn adapter_overview(
+    bugs: &[StoredBug],
+    latest_summary: Option<&Value>,
+) -> Vec<AdapterOverview> {
+    let mut adapters: BTreeMap<String, AdapterOverview> = BTreeMap::new();
+    for bug in bugs {
+        let overview = adapters.entry(bug.adapter()).or_default();
+        if bug.status.is_active() {
+            *overview
+                .active_by_severity
+                .entry(bug.severity.to_uppercase())
+                .or_default() += 1;
+            overview.active_bugs += 1;
+        } else {
+            overview.fixed_bugs += 1;
+        }
+    }
+
+    let results = latest_summary
+        .and_then(|summary| summary["file_results"].as_array())
+        .map(|r| r.as_slice())
+        .unwrap_or(&[]);
+    for result in results {
+        let path = repo_paths::to_repo_relative(result["file_path"].as_str().unwrap_or(""));
+        let adapter = bug_store::adapter_from_path(&path).unwrap_or_else(|| "unknown".to_string());
+        let overview = adapters.entry(adapter).or_default();
+        overview.files_in_latest_run += 1;
+        if report_status(result["status"].as_str().unwrap_or("")) == "analysis_failed" {
+            overview.failed_in_latest_run += 1;
+        }
+    }
+
+    let mut overviews: Vec<AdapterOverview> = adapters
+        .into_iter()
+        .map(|(adapter, overview)| AdapterOverview {
+            adapter,
+            ..overview
+        })
+        .collect();
+    overviews.sort_by(|a, b| {
+        b.weight()
+            .cmp(&a.weight())
+            .then_with(|| a.adapter.cmp(&b.adapter))
+    });
+    overviews
+}
+
+/// Load bugs and the latest summary and build the adapter overview
+pub async fn load_adapter_overview(bugs_dir: &Path) -> Result<Vec<AdapterOverview>> {
+    let bugs: Vec<StoredBug> = bug_store::load_bugs(bugs_dir)
+        .await?
+        .into_iter()
+        .map(|(_, bug)| bug)
+        .collect();
+    let latest = match run_diff::list_summary_files(bugs_dir).last() {
+        Some(path) => serde_json::from_str::<Value>(&tokio::fs::read_to_string(path).await?).ok(),
+        None => None,
+    };
+    Ok(adapter_overview(&bugs, latest.as_ref()))
+}
+
+/// Router with the dashboard pages and JSON APIs
+pub fn router() -> Router {
+    Router::new()
+        .route("/api/bugs", get(api_bugs))
+        .route("/api/bugs/:bug_id", get(api_bug))
+        .route("/api/runs", get(api_runs))
+        .route("/api/adapters", get(api_adapters))
+        .route(
+            "/dashboard",
+            get(|| async { Redirect::to("/dashboard/bugs") }),
+        )
+        .route("/dashboard/bugs", get(bugs_page))
+        .route("/dashboard/bugs/:bug_id", get(bug_page))
+        .route("/dashboard/runs", get(runs_page))
+        .route("/dashboard/adapters", get(adapters_page))
+}
+
+fn internal_error(e: anyhow::Error) -> Response {
+    error!("❌ Dashboard request failed: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("❌ {}", e)).into_response()
+}
+
+fn not_found(bug_id: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        format!("❌ Bug '{}' not found", bug_id),
+    )
+        .into_response()
+}
+
+async fn api_bugs(Query(filter): Query<BugFilter>) -> Response {
+    match filtered_bugs(&Config::bugs_directory_path(), &filter).await {
+        Ok(bugs) => {
+            let entries: Vec<BugListEntry> = bugs.iter().map(BugListEntry::from_bug).collect();
+            Json(json!({ "count": entries.len(), "bugs": entries })).into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn api_bug(UrlPath(bug_id): UrlPath<String>) -> Response {
+    match find_bug(&Config::bugs_directory_path(), &bug_id).await {
+        Ok(Some(bug)) => Json(bug).into_response(),
+        Ok(None) => not_found(&bug_id),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn api_runs() -> Response {
+    match run_history(&Config::bugs_directory_path()).await {
+        Ok(runs) => Json(json!({ "count": runs.len(), "runs": runs })).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn api_adapters() -> Response {
+    match load_adapter_overview(&Config::bugs_directory_path()).await {
+        Ok(adapters) => {
+            Json(json!({ "count": adapters.len(), "adapters": adapters })).into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Wrap page content with the shared head and navigation
+/// Characters left as they are in URL components: RFC 3986 unreserved characters
+const URL_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Percent-encode a value for a URL path segment or query value
+fn encode_url_component(value: &str) -> String {
+    utf8_percent_encode(value, URL_COMPONENT).to_string()
+}
+
+fn page(title: &str, body: &str) -> Html<String> {
+    Html(format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Nautilus Trader Rig – {title}</title>\n<style>{style}</style>\n</head>\n<body>\n\
+         <nav><a href=\"/dashboard/bugs\">Bugs</a> · <a href=\"/dashboard/runs\">Runs</a> · \
+         <a href=\"/dashboard/adapters\">Adapters</a></nav>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+        title = escape_html(title),
+        style = STYLE,
+        body = body,
+    ))
+}
+
+fn select(name: &str, options: &[&str], selected: Option<&str>) -> String {
+    let mut html = format!("<select name=\"{}\"><option value=\"\">any</option>", name);
+    for option in options {
+        let is_selected = selected.is_some_and(|s| s.eq_ignore_ascii_case(option));
+        let _ = write!(
+            html,
+            "<option value=\"{0}\"{1}>{0}</option>",
+            option,
+            if is_selected { " selected" } else { "" }
+        );
+    }
+    html.push_str("</select>");
+    html
+}
+
+async fn bugs_page(Query(filter): Query<BugFilter>) -> Response {
+    let bugs = match filtered_bugs(&Config::bugs_directory_path(), &filter).await {
+        Ok(bugs) => bugs,
+        Err(e) => return internal_error(e),
+    };
+
+    let mut body = String::new();
+    let _ = writeln!(
+        body,
+        "<form method=\"get\">Severity {} Adapter <input name=\"adapter\" value=\"{}\"> Status {} \
+         <button type=\"submit\">Filter</button></form>",
+        select("severity", &SEVERITIES, filter.severity.as_deref()),
+        escape_html(filter.adapter.as_deref().unwrap_or("")),
+        select("status", &STATUSES, filter.status.as_deref()),
+    );
+    let _ = writeln!(body, "<p>{} bugs</p>", bugs.len());
+    body.push_str(
+        "<table>\n<tr><th>Bug</th><th>Severity</th><th>Status</th><th>Adapter</th><th>Category</th><th>Description</th></tr>\n",
+    );
+    for bug in &bugs {
+        let entry = BugListEntry::from_bug(bug);
+        let _ = writeln!(
+            body,
+            "<tr><td><a href=\"/dashboard/bugs/{link}\">{id}</a></td><td class=\"sev-{severity}\">{severity}</td>\
+             <td>{status}</td><td>{adapter}</td><td>{category}</td><td>{description}</td></tr>",
+            link = escape_html(&encode_url_component(&entry.bug_id)),
+            id = escape_html(&entry.bug_id),
+            severity = escape_html(&entry.severity),
+            status = entry.status,
+            adapter = escape_html(&entry.adapter),
+            category = entry.category,
+            description = escape_html(&entry.description),
+        );
+    }
+    body.push_str("</table>\n");
+
+    page("Bugs", &body).into_response()
+}
+
+async fn bug_page(UrlPath(bug_id): UrlPath<String>) -> Response {
+    let bug = match find_bug(&Config::bugs_directory_path(), &bug_id).await {
+        Ok(Some(bug)) => bug,
+        Ok(None) => return not_found(&bug_id),
+        Err(e) => return internal_error(e),
+    };
+
+    let severity = bug.severity.to_uppercase();
+    let mut body = String::new();
+    let _ = writeln!(
+        body,
+        "<p><span class=\"sev-{severity}\">{severity}</span> · {adapter} · {category} · {status} · recorded {timestamp}</p>\n\
+         <h2>Description</h2>\n<p>{description}</p>",
+        severity = escape_html(&severity),
+        adapter = escape_html(&bug.adapter()),
+        category = bug.category(),
+        status = bug.status,
+        timestamp = escape_html(&bug.timestamp),
+        description = escape_html(&bug.description),
+    );
+    if let Some(path) = bug.source_path() {
+        let path = repo_paths::to_repo_relative(path);
+        let line = bug.line_number();
+        let _ = writeln!(
+            body,
+            "<p>Location: <a href=\"{}\">{}{}</a></p>",
+            escape_html(&source_link(&path, line, None)),
+            escape_html(&path),
+            line.map(|l| format!(":{}", l)).unwrap_or_default(),
+        );
+    }
+    if let Some(functions) = bug
+        .affected_functions
+        .as_deref()
+        .filter(|f| !f.trim().is_empty())
+    {
+        let _ = writeln!(
+            body,
+            "<p>Affected functions: <code>{}</code></p>",
+            escape_html(functions)
+        );
+    }
+    if let Some(sample) = bug.code_sample.as_deref().filter(|s| !s.trim().is_empty()) {
+        let _ = writeln!(
+            body,
+            "<h2>Code sample</h2>\n<pre><code>{}</code></pre>",
+            escape_html(sample)
+        );
+    }
+    if let Some(fix) = bug
+        .fix_suggestion
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+    {
+        let _ = writeln!(body, "<h2>Fix suggestion</h2>\n<p>{}</p>", escape_html(fix));
+    }
+    if !bug.status_history.is_empty() {
+        body.push_str("<h2>Status history</h2>\n<table>\n<tr><th>Status</th><th>Timestamp</th><th>Commit</th><th>Note</th></tr>\n");
+        for change in &bug.status_history {
+            let _ = writeln!(
+                body,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                change.status,
+                escape_html(&change.timestamp),
+                escape_html(change.commit_hash.as_deref().unwrap_or("")),
+                escape_html(change.note.as_deref().unwrap_or("")),
+            );
+        }
+        body.push_str("</table>\n");
+    }
+
+    page(&format!("Bug {}", bug.bug_id), &body).into_response()
+}
+
+async fn runs_page() -> Response {
+    let runs = match run_history(&Config::bugs_directory_path()).await {
+        Ok(runs) => runs,
+        Err(e) => return internal_error(e),
+    };
+
+    let mut body = String::from(
+        "<table>\n<tr><th>Run</th><th>Analyzed at</th><th>Files analyzed</th><th>Bugs found</th>\
+         <th>Failed files</th><th>New</th><th>Fixed</th><th>Regressed</th></tr>\n",
+    );
+    for run in &runs {
+        let lifecycle = |key: &str| {
+            run.lifecycle
+                .as_ref()
+                .and_then(|l| l[key].as_u64())
+                .map(|n| n.to_string())
+                .unwrap_or_default()
+        };
+        let _ = writeln!(
+            body,
+            "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+             <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            escape_html(&run.run_id),
+            escape_html(run.analysis_timestamp.as_deref().unwrap_or("")),
+            run.files_analyzed,
+            run.bugs_found,
+            run.failed_files,
+            lifecycle("new"),
+            lifecycle("fixed"),
+            lifecycle("regressed"),
+        );
+    }
+    body.push_str("</table>\n");
+    if runs.is_empty() {
+        body.push_str("<p>No analysis runs recorded yet.</p>\n");
+    }
+
+    page("Run history", &body).into_response()
+}
+
+async fn adapters_page() -> Response {
+    let adapters = match load_adapter_overview(&Config::bugs_directory_path()).await {
+        Ok(adapters) => adapters,
+        Err(e) => return internal_error(e),
+    };
+
+    let mut body = String::from("<table>\n<tr><th>Adapter</th>");
+    for severity in SEVERITIES {
+        let _ = write!(body, "<th class=\"sev-{0}\">{0}</th>", severity);
+    }
+    body.push_str("<th>Active</th><th>Fixed</th><th>Files (latest run)</th><th>Failed (latest run)</th></tr>\n");
+    for overview in &adapters {
+        let _ = write!(
+            body,
+            "<tr><td><a href=\"/dashboard/bugs?adapter={}\">{}</a></td>",
+            escape_html(&encode_url_component(&overview.adapter)),
+            escape_html(&overview.adapter)
+        );
+        for severity in SEVERITIES {
+            let count = overview
+                .active_by_severity
+                .get(severity)
+                .copied()
+                .unwrap_or(0);
+            let _ = write!(body, "<td class=\"num\">{}</td>", count);
+        }
+        let _ = writeln!(
+            body,
+            "<td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            overview.active_bugs,
+            overview.fixed_bugs,
+            overview.files_in_latest_run,
+            overview.failed_in_latest_run,
+        );
+    }
+    body.push_str("</table>\n");
+
+    page("Adapter overview", &body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bug_store::test_bug;
+
+    #[test]
+    fn test_adapter_overview() {
+        let bugs = vec![
+            test_bug(json!({
+                "bug_id": "B1", "severity": "HIGH", "status": "open",
+                "file_location": { "details": { "relative_path": "crates/adapters/okx/src/parse.rs" } }
+            })),
+            test_bug(json!({
+                "bug_id": "B2", "severity": "LOW", "status": "fixed",
+                "file_location": { "details": { "relative_path": "crates/adapters/okx/src/lib.rs" } }
+            })),
+            test_bug(json!({
+                "bug_id": "B3", "severity": "CRITICAL", "status": "regressed",
+                "file_location": { "details": { "relative_path": "crates/adapters/bitmex/src/lib.rs" } }
+            })),
+        ];
+        let summary = json!({
+            "file_results": [
+                { "file_path": "crates/adapters/okx/src/parse.rs", "status": "bug_found" },
+                { "file_path": "crates/adapters/okx/src/lib.rs", "status": "analysis_failed" },
+                { "file_path": "crates/adapters/tardis/src/lib.rs", "status": "clean" }
+            ]
+        });
+
+        let overview = adapter_overview(&bugs, Some(&summary));
+        let names: Vec<&str> = overview.iter().map(|o| o.adapter.as_str()).collect();
+        assert_eq!(names, ["bitmex", "okx", "tardis"]);
+
+        let okx = &overview[1];
+        assert_eq!(okx.active_bugs, 1);
+        assert_eq!(okx.fixed_bugs, 1);
+        assert_eq!(okx.active_by_severity.get("HIGH"), Some(&1));
+        assert_eq!(okx.files_in_latest_run, 2);
+        assert_eq!(okx.failed_in_latest_run, 1);
+    }
+
+    #[test]
+    fn test_encode_url_component() {
+        assert_eq!(encode_url_component("okx"), "okx");
+        assert_eq!(
+            encode_url_component("AUTO_BUG_lib-1.2~3"),
+            "AUTO_BUG_lib-1.2~3"
+        );
+        assert_eq!(
+            encode_url_component("a&b=c d#e/\"f"),
+            "a%26b%3Dc%20d%23e%2F%22f"
+        );
+    }
+
+    #[test]
+    fn test_run_entry_counts_failures() {
+        let summary = json!({
+            "analysis_summary": { "files_analyzed": 3, "bugs_found": 1, "lifecycle": { "new": 1 } },
+            "file_results": [
+                { "file_path": "a.rs", "status": "error" },
+                { "file_path": "b.rs", "status": "bug_found" }
+            ]
+        });
+        let run = RunEntry::from_summary("20250101_000000".to_string(), &summary);
+        assert_eq!(run.run_id, "20250101_000000");
+        assert_eq!(run.failed_files, 1);
+        assert_eq!(run.lifecycle.unwrap()["new"], 1);
+    }
+}
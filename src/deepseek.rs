@@ -6,40 +6,94 @@
 // for consistent AI interactions across the application.
 
 use anyhow::{anyhow, Result};
-use rig::client::{CompletionClient, ProviderClient};
+use rig::client::CompletionClient;
 use rig::completion::Prompt;
 use rig::providers::deepseek;
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+use tokio::time::Instant;
 use tracing::{debug, info};
 
+use crate::config::{Config, RateLimitSettings};
+
+/// The outermost JSON object in an LLM reply, which may wrap it in prose or a code fence
+pub fn extract_json_object(reply: &str) -> Result<&str> {
+    match (reply.find('{'), reply.rfind('}')) {
+        (Some(start), Some(end)) if start < end => Ok(&reply[start..=end]),
+        _ => Err(anyhow!("reply contains no JSON object")),
+    }
+}
+
+/// Spaces provider requests to the configured rate and caps how many run at once
+#[derive(Debug)]
+struct RequestLimiter {
+    permits: Semaphore,
+    min_interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RequestLimiter {
+    fn new(limits: &RateLimitSettings) -> Self {
+        Self {
+            permits: Semaphore::new(limits.max_concurrent_requests.max(1)),
+            min_interval: Duration::from_secs(60) / limits.requests_per_minute.max(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait for a free request slot; the permit is held until the request completes
+    async fn acquire(&self) -> SemaphorePermit<'_> {
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("request limiter semaphore is never closed");
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.min_interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+        permit
+    }
+}
+
 /// DeepSeek client using rig framework
 #[derive(Clone, Debug)]
 pub struct DeepSeekClient {
     client: deepseek::Client,
+    model: String,
+    limiter: Arc<RequestLimiter>,
 }
 
 impl DeepSeekClient {
     /// Create a new DeepSeek client with the provided API key
-    #[allow(dead_code)]
     pub fn new(api_key: String) -> Self {
+        let settings = Config::settings();
         let client = deepseek::Client::new(&api_key);
-        Self { client }
+        Self {
+            client,
+            model: settings.provider.model.clone(),
+            limiter: Arc::new(RequestLimiter::new(&settings.rate_limits)),
+        }
     }
 
-    /// Create a DeepSeek client from the DEEPSEEK_API_KEY environment variable
+    /// Create a DeepSeek client from the configured API key environment variable
     pub fn from_env() -> Result<Self> {
+        let api_key_env = &Config::settings().provider.api_key_env;
         // Check if the environment variable exists first
-        match env::var("DEEPSEEK_API_KEY") {
+        match env::var(api_key_env) {
             Ok(api_key) => {
                 if api_key.trim().is_empty() {
-                    return Err(anyhow!("DEEPSEEK_API_KEY environment variable is empty"));
+                    return Err(anyhow!("{} environment variable is empty", api_key_env));
                 }
-                // Use the rig framework client
-                let client = deepseek::Client::from_env();
-                Ok(Self { client })
+                Ok(Self::new(api_key.trim().to_string()))
             }
-            Err(_) => Err(anyhow!("DEEPSEEK_API_KEY environment variable not set")),
+            Err(_) => Err(anyhow!("{} environment variable not set", api_key_env)),
         }
     }
 
@@ -57,7 +111,7 @@ impl DeepSeekClient {
 
         let agent = self
             .client
-            .agent(deepseek::DEEPSEEK_CHAT)
+            .agent(&self.model)
             .preamble("You are a helpful assistant specialized in code analysis and improvement.")
             .name(agent_name)
             .build();
@@ -67,6 +121,7 @@ impl DeepSeekClient {
             agent_name,
             prompt.len()
         );
+        let _permit = self.limiter.acquire().await;
         let response = agent.prompt(prompt).await?;
         log::info!(
             "📥 Received response from {}: {} chars",
@@ -83,7 +138,7 @@ impl DeepSeekClient {
 
         let agent = self
             .client
-            .agent(deepseek::DEEPSEEK_CHAT)
+            .agent(&self.model)
             .preamble(
                 "You are an expert code quality analyst specializing in commit message analysis, \
                  typo detection, and pattern consistency. You help identify inconsistencies, \
@@ -93,6 +148,7 @@ impl DeepSeekClient {
             .build();
 
         log::debug!("📤 Sending commit analysis prompt: {} chars", prompt.len());
+        let _permit = self.limiter.acquire().await;
         let response = agent.prompt(prompt).await?;
         log::info!(
             "📥 Received commit analysis response: {} chars",
@@ -102,6 +158,16 @@ impl DeepSeekClient {
         Ok(response)
     }
 
+    /// Whether a `confirm_critical_bug` analysis answered `CRITICAL_CONFIRMED: true`
+    pub fn confirms_critical(analysis: &str) -> bool {
+        let normalized: String = analysis
+            .chars()
+            .filter(|c| !matches!(c, '*' | '`' | '_' | ' ' | '\t'))
+            .collect::<String>()
+            .to_lowercase();
+        normalized.contains("criticalconfirmed:true")
+    }
+
     /// Analyze critical bugs and create tests to reproduce criticality
     pub async fn confirm_critical_bug(
         &self,
@@ -112,7 +178,7 @@ impl DeepSeekClient {
 
         let agent = self
             .client
-            .agent(deepseek::DEEPSEEK_CHAT)
+            .agent(&self.model)
             .preamble(
                 "You are an expert security researcher and test engineer specializing in critical bug analysis. \
                  Your task is to:\
@@ -157,6 +223,7 @@ impl DeepSeekClient {
             "📤 Sending critical bug confirmation prompt: {} chars",
             prompt.len()
         );
+        let _permit = self.limiter.acquire().await;
         let response = agent.prompt(&prompt).await?;
         log::info!(
             "📥 Received critical bug confirmation: {} chars",
@@ -166,13 +233,38 @@ impl DeepSeekClient {
         Ok(response)
     }
 
+    /// Send `prompt` under the `system` preamble and return the reply, asked to be one JSON object
+    ///
+    /// Models still wrap the object in prose or a code fence now and then; see
+    /// `extract_json_object`.
+    pub async fn complete_json(&self, system: &str, prompt: &str) -> Result<String> {
+        log::info!("🧩 Requesting a JSON answer from DeepSeek");
+
+        let agent = self
+            .client
+            .agent(&self.model)
+            .preamble(&format!(
+                "{} Reply with one JSON object and nothing else.",
+                system.trim_end()
+            ))
+            .name("JSON-Responder")
+            .build();
+
+        log::debug!("📤 Sending JSON prompt: {} chars", prompt.len());
+        let _permit = self.limiter.acquire().await;
+        let response = agent.prompt(prompt).await?;
+        log::info!("📥 Received JSON answer: {} chars", response.len());
+
+        Ok(response)
+    }
+
     /// Send a prompt for critical code analysis with appropriate context
     pub async fn analyze_code(&self, prompt: &str) -> Result<String> {
         log::info!("🔍 Starting critical code analysis with DeepSeek");
 
         let agent = self
             .client
-            .agent(deepseek::DEEPSEEK_CHAT)
+            .agent(&self.model)
             .preamble(
                 "You are an expert critical code analyst specializing in security vulnerabilities, \
                  reliability issues, financial calculation errors, and performance problems. \
@@ -185,6 +277,7 @@ impl DeepSeekClient {
             "📤 Sending critical code analysis prompt: {} chars",
             prompt.len()
         );
+        let _permit = self.limiter.acquire().await;
         let response = agent.prompt(prompt).await?;
         log::info!(
             "📥 Received critical code analysis response: {} chars",
@@ -348,6 +441,30 @@ impl DeepSeekEmbeddingClient {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_confirms_critical() {
+        assert!(DeepSeekClient::confirms_critical(
+            "- **CRITICAL_CONFIRMED**: true\n- RISK_LEVEL: HIGH"
+        ));
+        assert!(DeepSeekClient::confirms_critical(
+            "critical_confirmed: TRUE"
+        ));
+        assert!(!DeepSeekClient::confirms_critical(
+            "CRITICAL_CONFIRMED: false"
+        ));
+        assert!(!DeepSeekClient::confirms_critical("RISK_LEVEL: CRITICAL"));
+    }
+
+    #[test]
+    fn test_extract_json_object() {
+        assert_eq!(
+            extract_json_object("Sure:\n```json\n{\"a\": {\"b\": 1}}\n```").unwrap(),
+            "{\"a\": {\"b\": 1}}"
+        );
+        assert!(extract_json_object("no json").is_err());
+        assert!(extract_json_object("} backwards {").is_err());
+    }
+
     #[test]
     fn test_deepseek_client_creation() {
         let _client = DeepSeekClient::new("test-api-key".to_string());
@@ -0,0 +1,485 @@
+//! Environment diagnosis for Nautilus Trader Rig
+//!
+//! `doctor` checks every prerequisite of a working setup (configuration, the
+//! `.env` file and API key, local model files, adapter roots, git, writable output
+//! directories and the MCP port) and prints what to change for each one that is
+//! missing, instead of letting a later run fail in a confusing way.
+
+use fastembed::EmbeddingModel as FastembedModel;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::models::{self, MODEL_FILES};
+use crate::pattern_catalog;
+use crate::repo_paths;
+
+/// Outcome of a check; only failures make `doctor` exit non-zero
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Warn => "⚠️",
+            CheckStatus::Fail => "❌",
+        }
+    }
+}
+
+/// Result of one prerequisite check
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Which configuration file was loaded; invalid settings never get this far
+pub fn check_config() -> Check {
+    match &Config::settings().source {
+        Some(path) => Check::pass("config", format!("{} loaded and valid", path.display())),
+        None => Check::pass(
+            "config",
+            format!(
+                "No config file, using defaults (copy rig.example.toml to {} to customize)",
+                Config::manifest_dir().join(Config::CONFIG_FILE).display()
+            ),
+        ),
+    }
+}
+
+/// Whether a `.env` file was found in one of the searched locations
+pub fn check_env_file() -> Check {
+    match Config::env_file_in_use() {
+        Some(path) => Check::pass("env_file", format!("{} loaded", path.display())),
+        None => {
+            let searched: Vec<String> = Config::env_file_candidates()
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect();
+            Check::warn(
+                "env_file",
+                format!("No .env file in {}", searched.join(", ")),
+                format!(
+                    "Create {} with {}=<key>, or export the variables in your shell",
+                    Config::manifest_dir().join(Config::ENV_FILE_PATH).display(),
+                    Config::settings().provider.api_key_env
+                ),
+            )
+        }
+    }
+}
+
+/// Whether the provider API key is available
+pub fn check_api_key() -> Check {
+    let api_key_env = &Config::settings().provider.api_key_env;
+    match std::env::var(api_key_env) {
+        Ok(key) if !key.trim().is_empty() => {
+            Check::pass("api_key", format!("{} is set", api_key_env))
+        }
+        Ok(_) => Check::fail(
+            "api_key",
+            format!("{} is empty", api_key_env),
+            format!(
+                "Put a valid key in {} (in .env or the environment)",
+                api_key_env
+            ),
+        ),
+        Err(_) => Check::fail(
+            "api_key",
+            format!(
+                "{} is not set; analysis and bug confirmation are unavailable",
+                api_key_env
+            ),
+            format!(
+                "Add {}=<key> to {} or export it",
+                api_key_env,
+                Config::manifest_dir().join(Config::ENV_FILE_PATH).display()
+            ),
+        ),
+    }
+}
+
+/// Whether the local embedding model bundle is complete and matches its checksums
+pub fn check_model_dir(model_dir: &Path, model: &FastembedModel, offline: bool) -> Check {
+    let import_fix = "Run `models import <snapshot-dir>` to create it, or set embedding.model_dir / RIG_EMBEDDING_MODEL_DIR";
+    if !model_dir.is_dir() {
+        if offline {
+            return Check::fail(
+                "embedding_model",
+                format!(
+                    "{} not found and offline mode forbids downloading the model",
+                    model_dir.display()
+                ),
+                import_fix,
+            );
+        }
+        return Check::warn(
+            "embedding_model",
+            format!(
+                "{} not found; FastEmbed will download the model from the internet on first use",
+                model_dir.display()
+            ),
+            import_fix,
+        );
+    }
+
+    let missing: Vec<&str> = MODEL_FILES
+        .iter()
+        .copied()
+        .filter(|file| !model_dir.join(file).is_file())
+        .collect();
+    if !missing.is_empty() {
+        return Check::fail(
+            "embedding_model",
+            format!("{} is missing {}", model_dir.display(), missing.join(", ")),
+            "Re-import the bundle from the model snapshot with `models import <snapshot-dir>`",
+        );
+    }
+
+    match models::read_manifest(model_dir) {
+        Ok(None) => Check::warn(
+            "embedding_model",
+            format!("All model files present in {}, but no checksums are recorded", model_dir.display()),
+            format!("Run `models import {}` to record checksums", model_dir.display()),
+        ),
+        Ok(Some(_)) => match models::verify_bundle(model_dir, model) {
+            Ok(manifest) => Check::pass(
+                "embedding_model",
+                format!(
+                    "{} bundle verified in {} ({} dimensions)",
+                    manifest.model,
+                    model_dir.display(),
+                    manifest.dimension
+                ),
+            ),
+            Err(e) => Check::fail(
+                "embedding_model",
+                format!("{:#}", e),
+                "Re-import the bundle from a clean model snapshot with `models import <snapshot-dir>`",
+            ),
+        },
+        Err(e) => Check::fail(
+            "embedding_model",
+            format!("{:#}", e),
+            "Re-import the bundle with `models import <snapshot-dir>`",
+        ),
+    }
+}
+
+/// Whether each adapter root exists and contains adapter sources
+pub async fn check_adapter_roots() -> Vec<Check> {
+    let fix = "Point paths.roots (or --root / RIG_ROOTS) at the crates/adapters directory of a nautilus_trader checkout";
+    let mut checks = Vec::new();
+    for root in Config::adapter_roots() {
+        if !root.is_dir() {
+            checks.push(Check::fail(
+                "adapter_root",
+                format!("{} does not exist", root.display()),
+                fix,
+            ));
+            continue;
+        }
+        let files = crate::discover_rust_files(std::slice::from_ref(root))
+            .await
+            .map(|files| files.len())
+            .unwrap_or(0);
+        if files == 0 {
+            checks.push(Check::fail(
+                "adapter_root",
+                format!("{} has no <adapter>/src/*.rs files", root.display()),
+                fix,
+            ));
+        } else {
+            checks.push(Check::pass(
+                "adapter_root",
+                format!("{} has {} Rust files", root.display(), files),
+            ));
+        }
+    }
+    checks
+}
+
+/// Whether git is installed and the repository root is a git checkout
+pub async fn check_git() -> Check {
+    let git = match which::which("git") {
+        Ok(git) => git,
+        Err(_) => {
+            return Check::warn(
+                "git",
+                "git not found on PATH",
+                "Install git; commit hashes, branch names and adapter churn scores need it",
+            )
+        }
+    };
+    let Some(root) = repo_paths::repo_root() else {
+        return Check::warn(
+            "git",
+            format!(
+                "{} found, but the nautilus_trader repository root was not detected",
+                git.display()
+            ),
+            format!(
+                "Set {} to the nautilus_trader checkout",
+                repo_paths::REPO_ROOT_ENV
+            ),
+        );
+    };
+    let inside = tokio::process::Command::new(&git)
+        .arg("-C")
+        .arg(root)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()
+        .await
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if inside {
+        Check::pass(
+            "git",
+            format!(
+                "{} found; {} is a git checkout",
+                git.display(),
+                root.display()
+            ),
+        )
+    } else {
+        Check::warn(
+            "git",
+            format!("{} is not a git checkout", root.display()),
+            "Clone nautilus_trader with git so runs record commits and churn",
+        )
+    }
+}
+
+/// Whether a directory can be created and written to
+pub async fn check_writable_dir(name: &'static str, dir: &Path, setting: &str) -> Check {
+    let probe_file = dir.join(".doctor_probe");
+    let outcome = async {
+        tokio::fs::create_dir_all(dir).await?;
+        tokio::fs::write(&probe_file, b"ok").await?;
+        tokio::fs::remove_file(&probe_file).await
+    }
+    .await;
+    match outcome {
+        Ok(()) => Check::pass(name, format!("{} is writable", dir.display())),
+        Err(e) => Check::fail(
+            name,
+            format!("{} is not writable: {}", dir.display(), e),
+            format!(
+                "Fix the directory permissions or set {} to a writable directory",
+                setting
+            ),
+        ),
+    }
+}
+
+/// Whether the bug pattern catalog loads and holds any patterns
+pub fn check_pattern_catalog(dir: &Path) -> Check {
+    match pattern_catalog::load_catalog(dir) {
+        Ok(entries) if entries.is_empty() => Check::warn(
+            "pattern_catalog",
+            format!("{} has no patterns; similarity search will find nothing", dir.display()),
+            "Restore the shipped patterns/ directory or set paths.patterns_dir (or RIG_PATTERNS_DIR)",
+        ),
+        Ok(entries) => Check::pass("pattern_catalog", format!("{} patterns in {}", entries.len(), dir.display())),
+        Err(e) => Check::fail(
+            "pattern_catalog",
+            format!("{:#}", e),
+            "Fix or remove the listed pattern files",
+        ),
+    }
+}
+
+/// Whether the MCP bind address is free
+pub fn check_port(bind: &str) -> Check {
+    let fix =
+        "Stop the process using it (another rig instance?) or set mcp.bind / --bind / RIG_MCP_BIND";
+    match std::net::TcpListener::bind(bind) {
+        Ok(_) => Check::pass("mcp_port", format!("{} is available", bind)),
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+            Check::fail("mcp_port", format!("{} is already in use", bind), fix)
+        }
+        Err(e) => Check::fail("mcp_port", format!("Cannot bind {}: {}", bind, e), fix),
+    }
+}
+
+/// Run every check in order
+pub async fn run_checks() -> Vec<Check> {
+    let settings = Config::settings();
+    let mut checks = vec![check_config(), check_env_file(), check_api_key()];
+    match models::configured_model() {
+        Ok(model) => checks.push(check_model_dir(
+            &settings.embedding.model_dir,
+            &model,
+            settings.embedding.offline,
+        )),
+        Err(e) => checks.push(Check::fail(
+            "embedding_model",
+            format!("{:#}", e),
+            "Run `models list` and set embedding.model",
+        )),
+    }
+    checks.extend(check_adapter_roots().await);
+    checks.push(check_git().await);
+    checks.push(
+        check_writable_dir(
+            "bugs_dir",
+            &settings.paths.bugs_dir,
+            "paths.bugs_dir (or --bugs-dir / RIG_BUGS_DIR)",
+        )
+        .await,
+    );
+    checks.push(
+        check_writable_dir(
+            "jobs_dir",
+            &settings.paths.jobs_dir,
+            "paths.jobs_dir (or RIG_JOBS_DIR)",
+        )
+        .await,
+    );
+    checks.push(check_pattern_catalog(&settings.paths.patterns_dir));
+    checks.push(check_port(&settings.mcp.bind));
+    checks
+}
+
+/// Human-readable check list with fixes
+pub fn render(checks: &[Check]) -> String {
+    let mut text = String::from("🩺 Nautilus Trader Rig doctor\n\n");
+    for check in checks {
+        text.push_str(&format!(
+            "{} {}: {}\n",
+            check.status.icon(),
+            check.name,
+            check.detail
+        ));
+        if let Some(fix) = &check.fix {
+            text.push_str(&format!("   → {}\n", fix));
+        }
+    }
+    let failed = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Fail)
+        .count();
+    let warned = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Warn)
+        .count();
+    text.push_str(&format!(
+        "\n{} checks, {} failed, {} warnings\n",
+        checks.len(),
+        failed,
+        warned
+    ));
+    text
+}
+
+/// Whether no check failed
+pub fn all_passed(checks: &[Check]) -> bool {
+    checks.iter().all(|c| c.status != CheckStatus::Fail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_dir_check() {
+        let model = FastembedModel::AllMiniLML6V2;
+        let dir = tempfile::tempdir().unwrap();
+        let missing = check_model_dir(&dir.path().join("absent"), &model, false);
+        assert_eq!(missing.status, CheckStatus::Warn);
+        assert!(missing.detail.contains("download"));
+        let offline = check_model_dir(&dir.path().join("absent"), &model, true);
+        assert_eq!(offline.status, CheckStatus::Fail);
+        assert!(offline.detail.contains("offline"));
+
+        std::fs::write(dir.path().join("model.onnx"), b"onnx").unwrap();
+        let partial = check_model_dir(dir.path(), &model, false);
+        assert_eq!(partial.status, CheckStatus::Fail);
+        assert!(partial.detail.contains("tokenizer.json"));
+
+        for file in MODEL_FILES {
+            std::fs::write(dir.path().join(file), b"{}").unwrap();
+        }
+        let unverified = check_model_dir(dir.path(), &model, false);
+        assert_eq!(unverified.status, CheckStatus::Warn);
+        assert!(unverified.fix.unwrap().contains("models import"));
+
+        models::import_bundle(dir.path(), dir.path(), &model).unwrap();
+        assert_eq!(
+            check_model_dir(dir.path(), &model, false).status,
+            CheckStatus::Pass
+        );
+        std::fs::write(dir.path().join("config.json"), b"[]").unwrap();
+        assert_eq!(
+            check_model_dir(dir.path(), &model, false).status,
+            CheckStatus::Fail
+        );
+    }
+
+    #[test]
+    fn test_port_in_use_fails() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let bind = listener.local_addr().unwrap().to_string();
+        let check = check_port(&bind);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.fix.unwrap().contains("--bind"));
+        drop(listener);
+        assert_eq!(check_port(&bind).status, CheckStatus::Pass);
+    }
+
+    #[tokio::test]
+    async fn test_writable_dir_and_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let check =
+            check_writable_dir("bugs_dir", &dir.path().join("bugs"), "paths.bugs_dir").await;
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert!(!dir.path().join("bugs").join(".doctor_probe").exists());
+
+        let checks = vec![
+            check,
+            Check::warn("git", "git not found on PATH", "Install git"),
+        ];
+        assert!(all_passed(&checks));
+        assert!(render(&checks).contains("→ Install git"));
+    }
+}
@@ -1,23 +1,19 @@
 //! FastEmbed implementation for testing and reference
-//! 
+//!
 //! This module provides FastEmbed integration with local model files
 //! and implements the embedding and vector search workflow.
 
 use anyhow::Result;
-use fastembed::{
-    EmbeddingModel as FastembedModel, Pooling, TextEmbedding as FastembedTextEmbedding,
-    TokenizerFiles, UserDefinedEmbeddingModel, read_file_to_bytes,
-};
 use rig::{
-    Embed,
     embeddings::EmbeddingsBuilder,
     vector_store::{
-        VectorStoreIndex, in_memory_store::InMemoryVectorStore, request::VectorSearchRequest,
+        in_memory_store::InMemoryVectorStore, request::VectorSearchRequest, VectorStoreIndex,
     },
+    Embed,
 };
-use rig_fastembed::EmbeddingModel;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+
+use crate::config::Config;
 
 // Shape of data that needs to be RAG'ed.
 // The definition field will be used to generate embeddings.
@@ -31,49 +27,12 @@ struct WordDefinition {
 
 #[allow(dead_code)]
 pub async fn run_fastembed_test() -> Result<()> {
-    // Get model info
-    let test_model_info =
-        FastembedTextEmbedding::get_model_info(&FastembedModel::AllMiniLML6V2).unwrap();
-
-    // Set up model directory
-    let model_dir = Path::new("./models/Qdrant--all-MiniLM-L6-v2-onnx/snapshots");
-    println!("Loading model from: {model_dir:?}");
-
-    let embedding_model = if model_dir.exists() {
-        // Load model files
-        let onnx_file =
-            read_file_to_bytes(&model_dir.join("model.onnx")).expect("Could not read model.onnx file");
-
-        let tokenizer_files = TokenizerFiles {
-            tokenizer_file: read_file_to_bytes(&model_dir.join("tokenizer.json"))
-                .expect("Could not read tokenizer.json"),
-            config_file: read_file_to_bytes(&model_dir.join("config.json"))
-                .expect("Could not read config.json"),
-            special_tokens_map_file: read_file_to_bytes(&model_dir.join("special_tokens_map.json"))
-                .expect("Could not read special_tokens_map.json"),
-            tokenizer_config_file: read_file_to_bytes(&model_dir.join("tokenizer_config.json"))
-                .expect("Could not read tokenizer_config.json"),
-        };
-
-        // Create embedding model
-        let user_defined_model =
-            UserDefinedEmbeddingModel::new(onnx_file, tokenizer_files).with_pooling(Pooling::Mean);
-
-        EmbeddingModel::new_from_user_defined(user_defined_model, 384, test_model_info)
-    } else {
-        println!("Model directory not found, using automatic download");
-        // Create init options for the FastEmbed model
-        let init_options = fastembed::InitOptions::new(FastembedModel::AllMiniLML6V2);
-        
-        // First create the FastEmbed model instance
-        let _fastembed_model = FastembedTextEmbedding::try_new(init_options)?;
-        
-        // Get the model info 
-        let model_info = FastembedTextEmbedding::get_model_info(&FastembedModel::AllMiniLML6V2)?;
-        
-        // Create the rig embedding model - use reference to the model enum, not the instance
-        EmbeddingModel::new(&FastembedModel::AllMiniLML6V2, model_info.dim)
-    };
+    // Load the configured model from its bundle
+    println!(
+        "Loading model from: {:?}",
+        Config::settings().embedding.model_dir
+    );
+    let embedding_model = crate::models::load_embedding_model()?;
 
     // Create documents
     let documents = vec![
@@ -0,0 +1,435 @@
+//! Health probes for Nautilus Trader Rig
+//!
+//! Cheap, LLM-free checks of the pieces the analysis depends on: a vector store
+//! search against a known bug pattern, reachability of the DeepSeek API via its
+//! models list, writability of the bugs directory and presence of the adapters
+//! directory. Probes run periodically in the background; the latest report backs
+//! `get_status`, the `/healthz` and `/readyz` endpoints and structured log lines.
+
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+use serde::Serialize;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::config::Config;
+use crate::vector_store::{self, VectorStoreManager};
+
+/// Interval between background probe runs
+pub const PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Timeout for the provider reachability request
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// DeepSeek endpoint listing available models; cheap and never billed
+const DEEPSEEK_MODELS_URL: &str = "https://api.deepseek.com/models";
+
+/// Query that must match one of the built-in bug patterns
+const VECTOR_PROBE_QUERY: &str = "precision loss converting price to f64";
+
+/// Outcome of a probe
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeStatus {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+impl ProbeStatus {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            ProbeStatus::Healthy => "✅",
+            ProbeStatus::Degraded => "⚠️",
+            ProbeStatus::Unhealthy => "❌",
+        }
+    }
+}
+
+impl std::fmt::Display for ProbeStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ProbeStatus::Healthy => "healthy",
+            ProbeStatus::Degraded => "degraded",
+            ProbeStatus::Unhealthy => "unhealthy",
+        })
+    }
+}
+
+/// Result of a single probe
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    pub name: &'static str,
+    pub status: ProbeStatus,
+    /// Whether a failure of this probe makes the service not ready
+    pub critical: bool,
+    pub latency_ms: u64,
+    pub detail: String,
+}
+
+/// Results of one probe run
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: ProbeStatus,
+    pub ready: bool,
+    pub checked_at: String,
+    pub probes: Vec<ProbeResult>,
+}
+
+impl HealthReport {
+    fn from_probes(probes: Vec<ProbeResult>) -> Self {
+        let ready = !probes
+            .iter()
+            .any(|p| p.critical && p.status == ProbeStatus::Unhealthy);
+        let worst = probes
+            .iter()
+            .map(|p| p.status)
+            .max()
+            .unwrap_or(ProbeStatus::Healthy);
+        // Non-critical failures only degrade the service
+        let status = if ready {
+            worst.min(ProbeStatus::Degraded)
+        } else {
+            ProbeStatus::Unhealthy
+        };
+        Self {
+            status,
+            ready,
+            checked_at: chrono::Utc::now().to_rfc3339(),
+            probes,
+        }
+    }
+
+    /// Multi-line summary for status output
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{} Health: {} (checked {})\n",
+            self.status.icon(),
+            self.status,
+            self.checked_at
+        );
+        for probe in &self.probes {
+            text.push_str(&format!(
+                "   {} {}: {} ({} ms)\n",
+                probe.status.icon(),
+                probe.name,
+                probe.detail,
+                probe.latency_ms
+            ));
+        }
+        text
+    }
+}
+
+fn probe_result(
+    name: &'static str,
+    critical: bool,
+    started: Instant,
+    status: ProbeStatus,
+    detail: String,
+) -> ProbeResult {
+    ProbeResult {
+        name,
+        status,
+        critical,
+        latency_ms: started.elapsed().as_millis() as u64,
+        detail,
+    }
+}
+
+/// Search the vector store for a query that must hit a built-in pattern
+pub async fn probe_vector_store(
+    vector_store: Option<&tokio::sync::RwLock<VectorStoreManager>>,
+) -> ProbeResult {
+    let started = Instant::now();
+    let Some(vector_store) = vector_store else {
+        return probe_result(
+            "vector_store",
+            false,
+            started,
+            ProbeStatus::Degraded,
+            "Not loaded".to_string(),
+        );
+    };
+    match vector_store
+        .read()
+        .await
+        .similarity_search(VECTOR_PROBE_QUERY, &Default::default(), 1)
+        .await
+    {
+        Ok(results) if !results.is_empty() => probe_result(
+            "vector_store",
+            false,
+            started,
+            ProbeStatus::Healthy,
+            "Known pattern found".to_string(),
+        ),
+        Ok(_) => probe_result(
+            "vector_store",
+            false,
+            started,
+            ProbeStatus::Degraded,
+            "Search returned no results".to_string(),
+        ),
+        Err(e) => probe_result(
+            "vector_store",
+            false,
+            started,
+            ProbeStatus::Unhealthy,
+            format!("Search failed: {}", e),
+        ),
+    }
+}
+
+/// Check that the DeepSeek API is reachable by listing models
+pub async fn probe_provider() -> ProbeResult {
+    let started = Instant::now();
+    let api_key_env = &Config::settings().provider.api_key_env;
+    let api_key = std::env::var(api_key_env).unwrap_or_default();
+    if api_key.trim().is_empty() {
+        return probe_result(
+            "provider",
+            false,
+            started,
+            ProbeStatus::Degraded,
+            format!("{} not set", api_key_env),
+        );
+    }
+
+    let client = match reqwest::Client::builder().timeout(PROVIDER_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            return probe_result(
+                "provider",
+                false,
+                started,
+                ProbeStatus::Unhealthy,
+                format!("HTTP client error: {}", e),
+            )
+        }
+    };
+    match client
+        .get(DEEPSEEK_MODELS_URL)
+        .bearer_auth(api_key.trim())
+        .send()
+        .await
+    {
+        Ok(response) if response.status().is_success() => probe_result(
+            "provider",
+            false,
+            started,
+            ProbeStatus::Healthy,
+            "DeepSeek API reachable".to_string(),
+        ),
+        Ok(response) => probe_result(
+            "provider",
+            false,
+            started,
+            ProbeStatus::Degraded,
+            format!("DeepSeek API answered {}", response.status()),
+        ),
+        Err(e) => probe_result(
+            "provider",
+            false,
+            started,
+            ProbeStatus::Unhealthy,
+            format!("DeepSeek API unreachable: {}", e),
+        ),
+    }
+}
+
+/// Check that bug records can be written
+pub async fn probe_bugs_directory() -> ProbeResult {
+    let started = Instant::now();
+    let bugs_dir = Config::bugs_directory_path();
+    let probe_file = bugs_dir.join(".health_probe");
+    let outcome = async {
+        tokio::fs::create_dir_all(&bugs_dir).await?;
+        tokio::fs::write(&probe_file, chrono::Utc::now().to_rfc3339()).await?;
+        tokio::fs::remove_file(&probe_file).await
+    }
+    .await;
+    match outcome {
+        Ok(()) => probe_result(
+            "bugs_directory",
+            true,
+            started,
+            ProbeStatus::Healthy,
+            format!("{} writable", bugs_dir.display()),
+        ),
+        Err(e) => probe_result(
+            "bugs_directory",
+            true,
+            started,
+            ProbeStatus::Unhealthy,
+            format!("{} not writable: {}", bugs_dir.display(), e),
+        ),
+    }
+}
+
+/// Check that every configured adapter root exists and contains adapters
+pub async fn probe_adapters_directory() -> ProbeResult {
+    let started = Instant::now();
+    let mut adapters = 0;
+    for root in Config::adapter_roots() {
+        match std::fs::read_dir(root) {
+            Ok(entries) => {
+                adapters += entries
+                    .flatten()
+                    .filter(|e| e.path().join("src").is_dir())
+                    .count()
+            }
+            Err(e) => {
+                return probe_result(
+                    "adapters_directory",
+                    true,
+                    started,
+                    ProbeStatus::Unhealthy,
+                    format!("{} not readable: {}", root.display(), e),
+                )
+            }
+        }
+    }
+    let status = if adapters > 0 {
+        ProbeStatus::Healthy
+    } else {
+        ProbeStatus::Degraded
+    };
+    let detail = format!(
+        "{} adapters found in {} root(s)",
+        adapters,
+        Config::adapter_roots().len()
+    );
+    probe_result("adapters_directory", true, started, status, detail)
+}
+
+/// Run every probe and store the report as the latest one
+///
+/// The shared pattern index is probed only once something has loaded it.
+pub async fn run_probes() -> HealthReport {
+    let (vector, provider, bugs, adapters) = tokio::join!(
+        probe_vector_store(vector_store::loaded()),
+        probe_provider(),
+        probe_bugs_directory(),
+        probe_adapters_directory(),
+    );
+    let report = HealthReport::from_probes(vec![vector, provider, bugs, adapters]);
+
+    for probe in &report.probes {
+        match probe.status {
+            ProbeStatus::Healthy => {
+                debug!(
+                    probe = probe.name,
+                    status = %probe.status,
+                    latency_ms = probe.latency_ms,
+                    detail = %probe.detail,
+                    "health probe"
+                )
+            }
+            _ => {
+                warn!(
+                    probe = probe.name,
+                    status = %probe.status,
+                    latency_ms = probe.latency_ms,
+                    detail = %probe.detail,
+                    "health probe"
+                )
+            }
+        }
+    }
+    info!(status = %report.status, ready = report.ready, "health check completed");
+
+    *latest_slot().write().unwrap_or_else(|e| e.into_inner()) = Some(report.clone());
+    report
+}
+
+fn latest_slot() -> &'static RwLock<Option<HealthReport>> {
+    static LATEST: RwLock<Option<HealthReport>> = RwLock::new(None);
+    &LATEST
+}
+
+/// Most recent health report, if probes have run in this process
+pub fn latest() -> Option<HealthReport> {
+    latest_slot()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Run probes every `PROBE_INTERVAL` until the process stops
+pub async fn run_monitor() {
+    let mut interval = tokio::time::interval(PROBE_INTERVAL);
+    loop {
+        interval.tick().await;
+        run_probes().await;
+    }
+}
+
+/// Router with the `/healthz` liveness and `/readyz` readiness endpoints
+pub fn router() -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+async fn healthz() -> impl IntoResponse {
+    Json(serde_json::json!({
+        "status": "alive",
+        "last_report": latest(),
+    }))
+}
+
+async fn readyz() -> impl IntoResponse {
+    match latest() {
+        Some(report) => {
+            let code = if report.ready {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            (
+                code,
+                Json(serde_json::to_value(&report).unwrap_or_default()),
+            )
+        }
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "ready": false, "detail": "Health probes have not run yet" })),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(name: &'static str, critical: bool, status: ProbeStatus) -> ProbeResult {
+        ProbeResult {
+            name,
+            status,
+            critical,
+            latency_ms: 0,
+            detail: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_non_critical_failure_only_degrades() {
+        let report = HealthReport::from_probes(vec![
+            probe("provider", false, ProbeStatus::Unhealthy),
+            probe("bugs_directory", true, ProbeStatus::Healthy),
+        ]);
+        assert!(report.ready);
+        assert_eq!(report.status, ProbeStatus::Degraded);
+    }
+
+    #[test]
+    fn test_critical_failure_is_not_ready() {
+        let report = HealthReport::from_probes(vec![
+            probe("provider", false, ProbeStatus::Healthy),
+            probe("adapters_directory", true, ProbeStatus::Unhealthy),
+        ]);
+        assert!(!report.ready);
+        assert_eq!(report.status, ProbeStatus::Unhealthy);
+    }
+}
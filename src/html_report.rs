@@ -0,0 +1,323 @@
+//! Self-contained HTML report of an analysis run
+//!
+//! Renders an `analysis_summary_*.json` snapshot and its bug records into a single
+//! static HTML file with inline styles and no external assets, so it can be
+//! opened offline or attached to an email.
+
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::bug_store::{self, StoredBug};
+use crate::repo_paths;
+use crate::run_diff;
+
+/// Severity columns shown in the per-adapter table
+const SEVERITIES: [&str; 4] = ["CRITICAL", "HIGH", "MEDIUM", "LOW"];
+
+pub(crate) const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2rem; color: #1f2328; }
+h1, h2 { border-bottom: 1px solid #d0d7de; padding-bottom: .3rem; }
+table { border-collapse: collapse; margin: 1rem 0; }
+th, td { border: 1px solid #d0d7de; padding: .35rem .7rem; text-align: left; }
+th { background: #f6f8fa; }
+td.num { text-align: right; }
+.status { font-weight: 600; padding: .1rem .4rem; border-radius: 4px; }
+.clean { background: #dafbe1; }
+.bug_found { background: #ffebe9; }
+.analysis_failed { background: #fff8c5; }
+.skipped { background: #eaeef2; }
+.sev-CRITICAL { color: #a40e26; font-weight: 700; }
+.sev-HIGH { color: #cf222e; font-weight: 600; }
+.sev-MEDIUM { color: #9a6700; }
+.sev-LOW { color: #57606a; }
+details { border: 1px solid #d0d7de; border-radius: 6px; padding: .5rem 1rem; margin: .5rem 0; }
+summary { cursor: pointer; }
+pre { background: #f6f8fa; padding: .75rem; overflow-x: auto; }
+"#;
+
+/// Escape text for inclusion in HTML
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Collapse the raw file statuses of a summary into report statuses
+pub fn report_status(status: &str) -> &'static str {
+    match status {
+        "clean" => "clean",
+        "bug_found" | "bug_found_but_storage_failed" => "bug_found",
+        "analysis_failed" | "error" => "analysis_failed",
+        _ => "skipped",
+    }
+}
+
+/// Link to a source location, either under a web base URL or as a local file URL
+pub fn source_link(
+    relative_path: &str,
+    line: Option<u64>,
+    source_base_url: Option<&str>,
+) -> String {
+    let anchor = line.map(|l| format!("#L{}", l)).unwrap_or_default();
+    match source_base_url {
+        Some(base) => format!("{}/{}{}", base.trim_end_matches('/'), relative_path, anchor),
+        None => format!(
+            "file://{}{}",
+            repo_paths::resolve(relative_path).display(),
+            anchor
+        ),
+    }
+}
+
+/// Render the HTML report for a summary and the bug records it references
+pub fn render_report(
+    summary: &Value,
+    bugs: &HashMap<String, StoredBug>,
+    source_base_url: Option<&str>,
+) -> String {
+    let info = &summary["analysis_summary"];
+    let run_id = info["run_id"].as_str().unwrap_or("unknown");
+    let timestamp = info["analysis_timestamp"].as_str().unwrap_or("unknown");
+    let results: &[Value] = summary["file_results"]
+        .as_array()
+        .map(|r| r.as_slice())
+        .unwrap_or(&[]);
+
+    // Per-adapter severity counts
+    let mut adapters: BTreeMap<String, [usize; 4]> = BTreeMap::new();
+    for result in results {
+        let path = repo_paths::to_repo_relative(result["file_path"].as_str().unwrap_or(""));
+        let adapter = bug_store::adapter_from_path(&path).unwrap_or_else(|| "unknown".to_string());
+        let counts = adapters.entry(adapter).or_default();
+        if report_status(result["status"].as_str().unwrap_or("")) == "bug_found" {
+            let severity = result["severity"].as_str().unwrap_or("").to_uppercase();
+            if let Some(column) = SEVERITIES.iter().position(|s| *s == severity) {
+                counts[column] += 1;
+            }
+        }
+    }
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Nautilus Trader Rig – run {run}</title>\n<style>{style}</style>\n</head>\n<body>\n\
+         <h1>Analysis run {run}</h1>\n<p>Analyzed at {timestamp} · {analyzed} of {total} files analyzed · {bugs} bugs found</p>\n",
+        run = escape_html(run_id),
+        style = STYLE,
+        timestamp = escape_html(timestamp),
+        analyzed = info["files_analyzed"].as_u64().unwrap_or(0),
+        total = info["total_files_discovered"].as_u64().unwrap_or(0),
+        bugs = info["bugs_found"].as_u64().unwrap_or(0),
+    );
+
+    if let Some(lifecycle) = info.get("lifecycle") {
+        let _ = writeln!(
+            html,
+            "<p>Lifecycle: {} new · {} fixed · {} regressed · {} persisting</p>",
+            lifecycle["new"].as_u64().unwrap_or(0),
+            lifecycle["fixed"].as_u64().unwrap_or(0),
+            lifecycle["regressed"].as_u64().unwrap_or(0),
+            lifecycle["persisting"].as_u64().unwrap_or(0),
+        );
+    }
+
+    // Severity table
+    html.push_str("<h2>Severity by adapter</h2>\n<table>\n<tr><th>Adapter</th>");
+    for severity in SEVERITIES {
+        let _ = write!(html, "<th class=\"sev-{0}\">{0}</th>", severity);
+    }
+    html.push_str("<th>Total</th></tr>\n");
+    for (adapter, counts) in &adapters {
+        let _ = write!(html, "<tr><td>{}</td>", escape_html(adapter));
+        for count in counts {
+            let _ = write!(html, "<td class=\"num\">{}</td>", count);
+        }
+        let _ = writeln!(
+            html,
+            "<td class=\"num\">{}</td></tr>",
+            counts.iter().sum::<usize>()
+        );
+    }
+    html.push_str("</table>\n");
+
+    // File list
+    html.push_str("<h2>Files</h2>\n<table>\n<tr><th>File</th><th>Status</th><th>Severity</th><th>Bug</th></tr>\n");
+    for result in results {
+        let path = repo_paths::to_repo_relative(result["file_path"].as_str().unwrap_or(""));
+        let status = report_status(result["status"].as_str().unwrap_or(""));
+        let severity = result["severity"].as_str().unwrap_or("").to_uppercase();
+        let bug_cell = match result["bug_id"].as_str() {
+            Some(bug_id) => format!("<a href=\"#{0}\">{0}</a>", escape_html(bug_id)),
+            None => String::new(),
+        };
+        let _ = writeln!(
+            html,
+            "<tr><td><a href=\"{link}\">{path}</a></td><td><span class=\"status {status}\">{status}</span></td>\
+             <td class=\"sev-{severity}\">{severity}</td><td>{bug}</td></tr>",
+            link = escape_html(&source_link(&path, None, source_base_url)),
+            path = escape_html(&path),
+            status = status,
+            severity = escape_html(&severity),
+            bug = bug_cell,
+        );
+    }
+    html.push_str("</table>\n");
+
+    // Bug details
+    html.push_str("<h2>Bugs</h2>\n");
+    // A bug found in several line ranges of a file appears in more than one result
+    let mut seen = HashSet::new();
+    let bug_ids: Vec<&str> = results
+        .iter()
+        .filter_map(|r| r["bug_id"].as_str())
+        .filter(|bug_id| seen.insert(*bug_id))
+        .collect();
+    if bug_ids.is_empty() {
+        html.push_str("<p>No bugs found in this run.</p>\n");
+    }
+    for bug_id in bug_ids {
+        let Some(bug) = bugs.get(bug_id) else {
+            let _ = writeln!(
+                html,
+                "<details id=\"{0}\"><summary>{0} (record not found)</summary></details>",
+                escape_html(bug_id)
+            );
+            continue;
+        };
+        let severity = bug.severity.to_uppercase();
+        let _ = write!(
+            html,
+            "<details id=\"{id}\">\n<summary><span class=\"sev-{severity}\">{severity}</span> {id} · {adapter} · {status}</summary>\n\
+             <h3>Description</h3>\n<p>{description}</p>\n",
+            id = escape_html(&bug.bug_id),
+            severity = escape_html(&severity),
+            adapter = escape_html(&bug.adapter()),
+            status = bug.status,
+            description = escape_html(&bug.description),
+        );
+        if let Some(path) = bug.source_path() {
+            let path = repo_paths::to_repo_relative(path);
+            let line = bug.line_number();
+            let _ = writeln!(
+                html,
+                "<p>Location: <a href=\"{}\">{}{}</a></p>",
+                escape_html(&source_link(&path, line, source_base_url)),
+                escape_html(&path),
+                line.map(|l| format!(":{}", l)).unwrap_or_default(),
+            );
+        }
+        if let Some(sample) = bug.code_sample.as_deref().filter(|s| !s.trim().is_empty()) {
+            let _ = writeln!(
+                html,
+                "<h3>Code sample</h3>\n<pre><code>{}</code></pre>",
+                escape_html(sample)
+            );
+        }
+        if let Some(fix) = bug
+            .fix_suggestion
+            .as_deref()
+            .filter(|s| !s.trim().is_empty())
+        {
+            let _ = writeln!(html, "<h3>Fix suggestion</h3>\n<p>{}</p>", escape_html(fix));
+        }
+        html.push_str("</details>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Generate the HTML report for a run and write it next to the summary
+///
+/// `run` accepts anything `run_diff::resolve_summary` does. Returns the report path.
+pub async fn generate_report(
+    bugs_dir: &Path,
+    run: &str,
+    output: Option<PathBuf>,
+    source_base_url: Option<&str>,
+) -> Result<PathBuf> {
+    let summary_path = run_diff::resolve_summary(bugs_dir, run)?;
+    let mut summary: Value =
+        serde_json::from_str(&tokio::fs::read_to_string(&summary_path).await?)?;
+    if summary["analysis_summary"]["run_id"].is_null() {
+        summary["analysis_summary"]["run_id"] =
+            Value::String(run_diff::run_id_from_path(&summary_path));
+    }
+
+    let bugs: HashMap<String, StoredBug> = bug_store::load_bugs(bugs_dir)
+        .await?
+        .into_iter()
+        .map(|(_, bug)| (bug.bug_id.clone(), bug))
+        .collect();
+
+    let output = output.unwrap_or_else(|| {
+        bugs_dir.join(format!(
+            "analysis_report_{}.html",
+            run_diff::run_id_from_path(&summary_path)
+        ))
+    });
+    tokio::fs::write(&output, render_report(&summary, &bugs, source_base_url)).await?;
+    info!("HTML report written: {}", output.display());
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&'"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;"
+        );
+    }
+
+    #[test]
+    fn test_render_report_is_self_contained() {
+        let summary = serde_json::json!({
+            "analysis_summary": { "run_id": "20250101_000000", "bugs_found": 1 },
+            "file_results": [
+                { "file_path": "crates/adapters/okx/src/parse.rs", "status": "bug_found", "severity": "HIGH", "bug_id": "B1" },
+                { "file_path": "crates/adapters/okx/src/lib.rs", "status": "clean" },
+                { "file_path": "crates/adapters/bitmex/src/lib.rs", "status": "skipped_no_client" },
+                { "file_path": "crates/adapters/okx/src/parse.rs", "status": "bug_found", "severity": "HIGH", "bug_id": "B1" }
+            ]
+        });
+        let bug: StoredBug = serde_json::from_value(serde_json::json!({
+            "bug_id": "B1",
+            "severity": "HIGH",
+            "description": "Uses <f64> for prices",
+            "fix_suggestion": "Use Price",
+            "file_location": { "details": { "relative_path": "crates/adapters/okx/src/parse.rs" } }
+        }))
+        .unwrap();
+        let bugs = HashMap::from([("B1".to_string(), bug)]);
+
+        let html = render_report(
+            &summary,
+            &bugs,
+            Some("https://github.com/nautechsystems/nautilus_trader/blob/develop"),
+        );
+        assert_eq!(html.matches("<details id=\"B1\">").count(), 1);
+        assert!(html.contains("Uses &lt;f64&gt; for prices"));
+        assert!(html.contains("https://github.com/nautechsystems/nautilus_trader/blob/develop/crates/adapters/okx/src/parse.rs"));
+        assert!(html.contains("status skipped"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<link"));
+    }
+}
@@ -0,0 +1,407 @@
+//! Hybrid lexical + vector retrieval
+//!
+//! Embeddings alone rank identifier-heavy queries such as `DatabentoImbalance
+//! c_char` poorly, so pattern and bug search also query a SQLite FTS5 index over
+//! the same documents. The two rankings are combined with reciprocal rank fusion
+//! and, when `embedding.reranker` names a FastEmbed cross-encoder, the fused
+//! candidates are re-scored by it. Every hit carries the per-component scores.
+
+use anyhow::{anyhow, bail, Result};
+use fastembed::{RerankInitOptions, RerankerModel, TextRerank};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::OnceCell;
+use tracing::info;
+
+use crate::config::Config;
+
+/// Rank offset of reciprocal rank fusion; 60 is the value from the original paper
+pub const RRF_K: f64 = 60.0;
+
+/// How a hit was ranked
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ScoreBreakdown {
+    /// Reciprocal rank fusion of the vector and lexical ranks
+    pub fused: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector_rank: Option<usize>,
+    /// Cosine similarity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector_score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lexical_rank: Option<usize>,
+    /// Negated FTS5 bm25, higher is better
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lexical_score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f64>,
+}
+
+impl ScoreBreakdown {
+    /// Score the final order is based on
+    pub fn score(&self) -> f64 {
+        self.rerank_score.unwrap_or(self.fused)
+    }
+}
+
+/// Lower-case words of `snake_case` and `CamelCase` identifiers, so FTS matches their parts
+pub fn identifier_parts(text: &str) -> String {
+    let mut parts = Vec::new();
+    for identifier in text.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        let has_camel = identifier.chars().skip(1).any(|c| c.is_uppercase())
+            && identifier.chars().any(|c| c.is_lowercase());
+        if !has_camel {
+            continue;
+        }
+        for word in identifier.split('_') {
+            let mut current = String::new();
+            let mut prev_lower = false;
+            for c in word.chars() {
+                if c.is_uppercase() && prev_lower && !current.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+                prev_lower = c.is_lowercase() || c.is_ascii_digit();
+                current.extend(c.to_lowercase());
+            }
+            if !current.is_empty() {
+                parts.push(current);
+            }
+        }
+    }
+    parts.join(" ")
+}
+
+/// FTS5 query matching any term of `query`; identifiers become quoted phrases
+pub fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|term| term.chars().any(char::is_alphanumeric))
+        .map(|term| format!("\"{}\"", term))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" OR "))
+}
+
+/// In-memory SQLite FTS5 index of document texts keyed by ID
+pub struct LexicalIndex {
+    conn: Mutex<Connection>,
+}
+
+impl LexicalIndex {
+    pub fn new() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch("CREATE VIRTUAL TABLE docs USING fts5(id UNINDEXED, body)")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Index `text` under `id`, replacing any previous text
+    pub fn upsert(&self, id: &str, text: &str) -> Result<()> {
+        let body = format!("{}\n{}", text, identifier_parts(text));
+        let conn = self.conn();
+        conn.execute("DELETE FROM docs WHERE id = ?1", params![id])?;
+        conn.execute(
+            "INSERT INTO docs (id, body) VALUES (?1, ?2)",
+            params![id, body],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove(&self, id: &str) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM docs WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Best bm25 matches as `(id, score)`, best first
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<(String, f64)>> {
+        let Some(fts) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let conn = self.conn();
+        let mut statement = conn.prepare(
+            "SELECT id, bm25(docs) FROM docs WHERE docs MATCH ?1 ORDER BY bm25(docs) LIMIT ?2",
+        )?;
+        let rows = statement.query_map(params![fts, limit as i64], |row| {
+            Ok((row.get::<_, String>(0)?, -row.get::<_, f64>(1)?))
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+/// Fuse two best-first rankings of `(id, score)` by reciprocal rank, best first
+pub fn reciprocal_rank_fusion(
+    vector: &[(String, f64)],
+    lexical: &[(String, f64)],
+) -> Vec<(String, ScoreBreakdown)> {
+    let mut fused: HashMap<&str, ScoreBreakdown> = HashMap::new();
+    for (rank, (id, score)) in vector.iter().enumerate() {
+        let entry = fused.entry(id).or_default();
+        entry.vector_rank = Some(rank + 1);
+        entry.vector_score = Some(*score);
+        entry.fused += 1.0 / (RRF_K + (rank + 1) as f64);
+    }
+    for (rank, (id, score)) in lexical.iter().enumerate() {
+        let entry = fused.entry(id).or_default();
+        entry.lexical_rank = Some(rank + 1);
+        entry.lexical_score = Some(*score);
+        entry.fused += 1.0 / (RRF_K + (rank + 1) as f64);
+    }
+
+    let mut ranked: Vec<(String, ScoreBreakdown)> = fused
+        .into_iter()
+        .map(|(id, scores)| (id.to_string(), scores))
+        .collect();
+    ranked.sort_by(|a, b| b.1.fused.total_cmp(&a.1.fused).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+/// Set the cosine similarity of hits that ranked outside the vector candidates
+///
+/// `similarity` holds the similarity of every searched document, so each hit reports one
+/// whatever ranking found it.
+pub fn fill_similarity(ranked: &mut [(String, ScoreBreakdown)], similarity: &HashMap<String, f64>) {
+    for (id, scores) in ranked.iter_mut() {
+        if scores.vector_score.is_none() {
+            scores.vector_score = similarity.get(id).copied();
+        }
+    }
+}
+
+/// Which rankings a search uses; production search is `Hybrid`, the others exist for evaluation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    Vector,
+    Lexical,
+    Hybrid,
+}
+
+impl SearchMode {
+    pub const ALL: [SearchMode; 3] = [SearchMode::Vector, SearchMode::Lexical, SearchMode::Hybrid];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SearchMode::Vector => "vector",
+            SearchMode::Lexical => "lexical",
+            SearchMode::Hybrid => "hybrid",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "unknown search mode '{}'; expected vector, lexical or hybrid",
+                    name
+                )
+            })
+    }
+
+    /// Fuse the rankings this mode uses; a single ranking keeps its order
+    pub fn fuse(
+        self,
+        vector: &[(String, f64)],
+        lexical: &[(String, f64)],
+    ) -> Vec<(String, ScoreBreakdown)> {
+        match self {
+            SearchMode::Vector => reciprocal_rank_fusion(vector, &[]),
+            SearchMode::Lexical => reciprocal_rank_fusion(&[], lexical),
+            SearchMode::Hybrid => reciprocal_rank_fusion(vector, lexical),
+        }
+    }
+}
+
+/// Find a supported cross-encoder by name (`BGERerankerBase`) or code (`BAAI/bge-reranker-base`)
+pub fn resolve_reranker(name: &str) -> Result<RerankerModel> {
+    let name = name.trim();
+    TextRerank::list_supported_models()
+        .into_iter()
+        .find(|info| {
+            format!("{:?}", info.model).eq_ignore_ascii_case(name)
+                || info.model_code.eq_ignore_ascii_case(name)
+        })
+        .map(|info| info.model)
+        .ok_or_else(|| {
+            let supported: Vec<String> = TextRerank::list_supported_models()
+                .iter()
+                .map(|info| format!("{:?}", info.model))
+                .collect();
+            anyhow!(
+                "unknown reranker '{}'; supported: {}",
+                name,
+                supported.join(", ")
+            )
+        })
+}
+
+/// Whether `embedding.reranker` turns re-ranking on
+pub fn reranker_enabled(name: &str) -> bool {
+    !(name.trim().is_empty() || name.trim().eq_ignore_ascii_case("off"))
+}
+
+fn load_reranker() -> Result<Option<Mutex<TextRerank>>> {
+    let settings = Config::settings();
+    let name = &settings.embedding.reranker;
+    if !reranker_enabled(name) {
+        return Ok(None);
+    }
+    let model = resolve_reranker(name)?;
+    let cache_dir = &settings.embedding.reranker_dir;
+    if settings.embedding.offline && !cache_dir.is_dir() {
+        bail!(
+            "Reranker {} is not available offline: {} does not exist. Copy the FastEmbed reranker cache there, \
+             set embedding.reranker = \"off\", or disable embedding.offline",
+            name,
+            cache_dir.display()
+        );
+    }
+    info!("Loading reranker {} from {}", name, cache_dir.display());
+    let reranker =
+        TextRerank::try_new(RerankInitOptions::new(model).with_cache_dir(cache_dir.clone()))?;
+    Ok(Some(Mutex::new(reranker)))
+}
+
+/// Configured cross-encoder, loaded once; `None` when re-ranking is off
+async fn shared_reranker() -> Result<Option<&'static Mutex<TextRerank>>> {
+    static RERANKER: OnceCell<Option<Mutex<TextRerank>>> = OnceCell::const_new();
+    let reranker = RERANKER
+        .get_or_try_init(|| async { tokio::task::spawn_blocking(load_reranker).await? })
+        .await?;
+    Ok(reranker.as_ref())
+}
+
+/// Re-score fused candidates with the configured cross-encoder and re-order them
+///
+/// `texts` holds the document text of each candidate, in the same order. A no-op when
+/// re-ranking is off.
+pub async fn rerank(
+    query: &str,
+    candidates: &mut [(String, ScoreBreakdown)],
+    texts: Vec<String>,
+) -> Result<()> {
+    let Some(reranker) = shared_reranker().await? else {
+        return Ok(());
+    };
+    if candidates.is_empty() {
+        return Ok(());
+    }
+
+    let query = query.to_string();
+    let results = tokio::task::spawn_blocking(move || {
+        let documents: Vec<&str> = texts.iter().map(String::as_str).collect();
+        reranker.lock().unwrap_or_else(|e| e.into_inner()).rerank(
+            query.as_str(),
+            documents,
+            false,
+            None,
+        )
+    })
+    .await??;
+    for result in results {
+        if let Some((_, scores)) = candidates.get_mut(result.index) {
+            scores.rerank_score = Some(result.score as f64);
+        }
+    }
+    candidates.sort_by(|a, b| b.1.score().total_cmp(&a.1.score()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranking(ids: &[&str]) -> Vec<(String, f64)> {
+        ids.iter()
+            .enumerate()
+            .map(|(i, id)| (id.to_string(), 1.0 - i as f64 / 10.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_identifier_parts_and_query() {
+        assert_eq!(
+            identifier_parts("DatabentoImbalance parse_okx_instrument"),
+            "databento imbalance"
+        );
+        assert_eq!(identifier_parts("OKXHttpClient"), "okxhttp client");
+        assert_eq!(
+            fts_query("DatabentoImbalance c_char?").unwrap(),
+            "\"DatabentoImbalance\" OR \"c_char\""
+        );
+        assert_eq!(fts_query(" -- "), None);
+    }
+
+    #[test]
+    fn test_lexical_index_matches_identifiers() {
+        let index = LexicalIndex::new().unwrap();
+        index
+            .upsert(
+                "a",
+                "Imbalance record built from DatabentoImbalance with c_char side",
+            )
+            .unwrap();
+        index
+            .upsert("b", "Precision loss converting prices to f64")
+            .unwrap();
+        index
+            .upsert("c", "parse_okx_instrument ignores the lot size")
+            .unwrap();
+
+        let hits = index.search("DatabentoImbalance c_char", 10).unwrap();
+        assert_eq!(hits[0].0, "a");
+        assert_eq!(index.search("parse_okx_instrument", 10).unwrap()[0].0, "c");
+        assert_eq!(index.search("imbalance", 10).unwrap()[0].0, "a");
+
+        index.remove("a").unwrap();
+        index.upsert("c", "lot size").unwrap();
+        assert!(index.search("DatabentoImbalance", 10).unwrap().is_empty());
+        assert!(index.search("parse_okx_instrument", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rrf_rewards_agreement() {
+        let fused = reciprocal_rank_fusion(&ranking(&["x", "y", "z"]), &ranking(&["y", "w"]));
+        let order: Vec<&str> = fused.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(order, vec!["y", "x", "w", "z"]);
+
+        let y = &fused[0].1;
+        assert_eq!((y.vector_rank, y.lexical_rank), (Some(2), Some(1)));
+        assert!((y.fused - (1.0 / 62.0 + 1.0 / 61.0)).abs() < 1e-12);
+        assert_eq!(fused[2].1.vector_rank, None);
+        assert_eq!(y.score(), y.fused);
+
+        let mut fused = fused;
+        let similarity = HashMap::from([("w".to_string(), 0.2), ("y".to_string(), 0.9)]);
+        fill_similarity(&mut fused, &similarity);
+        assert_eq!(
+            (fused[2].1.vector_rank, fused[2].1.vector_score),
+            (None, Some(0.2))
+        );
+        assert_eq!(fused[0].1.vector_score, Some(0.9));
+    }
+
+    #[test]
+    fn test_single_mode_keeps_order() {
+        let vector = ranking(&["x", "y"]);
+        let lexical = ranking(&["y", "w"]);
+        let ids = |mode: SearchMode| -> Vec<String> {
+            mode.fuse(&vector, &lexical)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect()
+        };
+        assert_eq!(ids(SearchMode::Vector), vec!["x", "y"]);
+        assert_eq!(ids(SearchMode::Lexical), vec!["y", "w"]);
+        assert_eq!(SearchMode::parse("Hybrid").unwrap(), SearchMode::Hybrid);
+        assert!(SearchMode::parse("bm25").is_err());
+    }
+}
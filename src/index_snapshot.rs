@@ -0,0 +1,640 @@
+//! Portable export and import of the pattern and bug index
+//!
+//! `index export` writes every embedded pattern and bug, with the model name and
+//! dimension they were embedded with, to one bincode file. `index import` refuses
+//! files embedded with a different model, writes their patterns to the catalog and
+//! their bugs to the bugs directory, and keeps the embeddings in
+//! `embedding.index_cache`. Patterns and bugs whose text matches a cached embedding
+//! are not embedded again, so team members and CI can load a curated index as is.
+//!
+//! Records travel as JSON strings because their optional and flattened fields
+//! cannot be represented in bincode.
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use fastembed::EmbeddingModel as FastembedModel;
+use rig::{embeddings::Embedding, OneOrMany};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use crate::bug_index::{self, BugDocument};
+use crate::bug_store::{self, BugFilter, StoredBug};
+use crate::config::Config;
+use crate::models;
+use crate::pattern_catalog::{self, PatternEntry};
+use crate::vector_store::{self, BugPatternDocument};
+
+/// Format version written to new snapshots
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// An embedded pattern and its catalog entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotPattern {
+    /// `BugPatternDocument` as JSON; its content is the embedded text
+    pub document: String,
+    /// `PatternEntry` as JSON, unless the pattern is not in the catalog
+    pub entry: Option<String>,
+    pub embedding: Vec<f64>,
+}
+
+/// An embedded bug and its record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotBug {
+    pub bug_id: String,
+    /// Name of the record in the bugs directory
+    pub file_name: String,
+    /// `StoredBug` as JSON
+    pub record: String,
+    /// Embedded text, see `BugDocument`
+    pub text: String,
+    pub embedding: Vec<f64>,
+}
+
+/// Contents of an exported index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexSnapshot {
+    pub version: u32,
+    /// FastEmbed model name, e.g. `AllMiniLML6V2`
+    pub model: String,
+    pub dimension: usize,
+    pub exported_at: String,
+    pub patterns: Vec<SnapshotPattern>,
+    pub bugs: Vec<SnapshotBug>,
+}
+
+impl IndexSnapshot {
+    pub fn read(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read index {}", path.display()))?;
+        let snapshot: IndexSnapshot = bincode::deserialize(&bytes)
+            .with_context(|| format!("{} is not an exported index", path.display()))?;
+        if snapshot.version == 0 || snapshot.version > SNAPSHOT_VERSION {
+            bail!(
+                "Index {} has format version {}; this build reads versions 1 to {}",
+                path.display(),
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+        Ok(snapshot)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(path, bincode::serialize(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Fail unless the embeddings were made by `model` with `dimension` values each
+    pub fn check_compatible(&self, model: &str, dimension: usize) -> Result<()> {
+        if self.model != model || self.dimension != dimension {
+            bail!(
+                "Index was embedded with {} ({} dimensions) but embedding.model is {} ({} dimensions); \
+                 export it again with the configured model or switch embedding.model",
+                self.model,
+                self.dimension,
+                model,
+                dimension
+            );
+        }
+        let wrong = self
+            .patterns
+            .iter()
+            .map(|p| p.embedding.len())
+            .chain(self.bugs.iter().map(|b| b.embedding.len()))
+            .find(|len| *len != dimension);
+        if let Some(len) = wrong {
+            bail!(
+                "Index holds a {}-dimensional embedding but declares {} dimensions",
+                len,
+                dimension
+            );
+        }
+        Ok(())
+    }
+
+    /// Add `other` to this snapshot; its patterns and bugs replace those with the same ID
+    pub fn merge(&mut self, other: IndexSnapshot) {
+        let ids: HashSet<String> = other
+            .patterns
+            .iter()
+            .filter_map(|p| pattern_id(p).ok())
+            .collect();
+        self.patterns
+            .retain(|p| !pattern_id(p).is_ok_and(|id| ids.contains(&id)));
+        self.patterns.extend(other.patterns);
+        let ids: HashSet<&str> = other.bugs.iter().map(|b| b.bug_id.as_str()).collect();
+        self.bugs.retain(|b| !ids.contains(b.bug_id.as_str()));
+        self.bugs.extend(other.bugs);
+        self.exported_at = other.exported_at;
+    }
+}
+
+fn pattern_id(pattern: &SnapshotPattern) -> Result<String> {
+    Ok(serde_json::from_str::<BugPatternDocument>(&pattern.document)?.id)
+}
+
+fn embedding(text: &str, vec: &[f64]) -> OneOrMany<Embedding> {
+    OneOrMany::one(Embedding {
+        document: text.to_string(),
+        vec: vec.to_vec(),
+    })
+}
+
+/// Imported embeddings by ID, with the text they were made from and the model that made them
+#[derive(Debug, Default)]
+pub struct EmbeddingCache {
+    model: String,
+    patterns: HashMap<String, (String, Vec<f64>)>,
+    bugs: HashMap<String, (String, Vec<f64>)>,
+}
+
+impl EmbeddingCache {
+    pub fn from_snapshot(snapshot: IndexSnapshot) -> Self {
+        let mut cache = Self {
+            model: snapshot.model,
+            ..Self::default()
+        };
+        for pattern in snapshot.patterns {
+            if let Ok(document) = serde_json::from_str::<BugPatternDocument>(&pattern.document) {
+                cache
+                    .patterns
+                    .insert(document.id, (document.content, pattern.embedding));
+            }
+        }
+        for bug in snapshot.bugs {
+            cache.bugs.insert(bug.bug_id, (bug.text, bug.embedding));
+        }
+        cache
+    }
+
+    /// Cached embedding of `pattern` made by `model`, if its text is unchanged
+    pub fn pattern_embedding(
+        &self,
+        model: &FastembedModel,
+        pattern: &BugPatternDocument,
+    ) -> Option<OneOrMany<Embedding>> {
+        let (text, vec) = self
+            .patterns
+            .get(&pattern.id)
+            .filter(|_| self.made_by(model))?;
+        (*text == pattern.content).then(|| embedding(text, vec))
+    }
+
+    /// Cached embedding of `document` made by `model`, if its text is unchanged
+    pub fn bug_embedding(
+        &self,
+        model: &FastembedModel,
+        document: &BugDocument,
+    ) -> Option<OneOrMany<Embedding>> {
+        let (text, vec) = self
+            .bugs
+            .get(&document.bug_id)
+            .filter(|_| self.made_by(model))?;
+        (*text == document.text).then(|| embedding(text, vec))
+    }
+
+    fn made_by(&self, model: &FastembedModel) -> bool {
+        self.model == models::model_name(model)
+    }
+}
+
+/// Model name and dimension of `embedding.model`
+fn configured_model() -> Result<(String, usize)> {
+    let model = models::configured_model()?;
+    Ok((models::model_name(&model), models::model_dimension(&model)?))
+}
+
+/// Cache read from `embedding.index_cache`, kept until an import rewrites the file
+static CACHE: Mutex<Option<Arc<EmbeddingCache>>> = Mutex::new(None);
+
+/// Embeddings in `embedding.index_cache`; empty when there are none
+///
+/// The file is read once per process; `import` drops the copy so the next call reads it again.
+pub fn load_cache() -> Arc<EmbeddingCache> {
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.get_or_insert_with(|| Arc::new(read_cache())).clone()
+}
+
+fn read_cache() -> EmbeddingCache {
+    let path = &Config::settings().embedding.index_cache;
+    if !path.is_file() {
+        return EmbeddingCache::default();
+    }
+    let snapshot = IndexSnapshot::read(path).and_then(|snapshot| {
+        snapshot.check_compatible(&snapshot.model, snapshot.dimension)?;
+        Ok(snapshot)
+    });
+    match snapshot {
+        Ok(snapshot) => EmbeddingCache::from_snapshot(snapshot),
+        Err(e) => {
+            warn!("Ignoring index cache {}: {:#}", path.display(), e);
+            EmbeddingCache::default()
+        }
+    }
+}
+
+/// Snapshot of the shared pattern and bug indexes, after syncing the bugs directory
+pub async fn export() -> Result<IndexSnapshot> {
+    let (model, dimension) = configured_model()?;
+    let catalog: HashMap<String, PatternEntry> =
+        pattern_catalog::load_catalog(&Config::patterns_directory_path())?
+            .into_iter()
+            .map(|entry| (entry.id.clone(), entry))
+            .collect();
+
+    let mut patterns = Vec::new();
+    for (document, embedding) in vector_store::shared().await?.read().await.embedded() {
+        let entry = catalog
+            .get(&document.id)
+            .map(serde_json::to_string)
+            .transpose()?;
+        patterns.push(SnapshotPattern {
+            document: serde_json::to_string(&document)?,
+            entry,
+            embedding,
+        });
+    }
+
+    let bugs_dir = Config::bugs_directory_path();
+    let index = bug_index::shared().await?;
+    index.write().await.sync(&bugs_dir).await?;
+    let files: HashMap<String, String> = bug_store::load_bugs(&bugs_dir)
+        .await?
+        .into_iter()
+        .filter_map(|(path, bug)| {
+            Some((bug.bug_id, path.file_name()?.to_string_lossy().to_string()))
+        })
+        .collect();
+    let mut bugs = Vec::new();
+    for (bug, embedding) in index.read().await.vectors(&BugFilter::default()) {
+        let Some(file_name) = files.get(&bug.bug_id) else {
+            continue;
+        };
+        bugs.push(SnapshotBug {
+            bug_id: bug.bug_id.clone(),
+            file_name: file_name.clone(),
+            record: serde_json::to_string(&bug)?,
+            text: BugDocument::from_bug(&bug).text,
+            embedding,
+        });
+    }
+
+    Ok(IndexSnapshot {
+        version: SNAPSHOT_VERSION,
+        model,
+        dimension,
+        exported_at: Utc::now().to_rfc3339(),
+        patterns,
+        bugs,
+    })
+}
+
+/// How an import treats patterns and bugs missing from the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Keep them; bugs already stored keep their status, history and confirmation
+    Merge,
+    /// Delete them from the catalog and the bugs directory, once the file's records are written
+    Replace,
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub mode: ImportMode,
+    pub model: String,
+    pub patterns: usize,
+    pub bugs: usize,
+    pub removed_patterns: usize,
+    pub removed_bugs: usize,
+}
+
+/// Bug record file name that stays inside the bugs directory
+fn safe_file_name(name: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(name);
+    let plain = path.components().count() == 1 && path.file_name().is_some_and(|f| f == name);
+    (plain && bug_store::is_bug_file(&path)).then_some(path)
+}
+
+/// Keep the status, history and confirmation of a bug already stored here, so a merge
+/// never undoes local triage
+fn keep_local_review(incoming: &mut StoredBug, local: &StoredBug) {
+    incoming.status = local.status;
+    incoming.status_history = local.status_history.clone();
+    if local.is_confirmed() {
+        for key in ["confirmed", "confirmed_at", "confirmed_by"] {
+            if let Some(value) = local.extra.get(key) {
+                incoming.extra.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+}
+
+/// `requested` resolved inside `dir`; absolute paths and `..` are refused
+fn path_within(dir: &Path, requested: &str) -> Result<PathBuf> {
+    let relative = Path::new(requested.trim());
+    let plain = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !plain || relative.as_os_str().is_empty() {
+        bail!(
+            "Index path '{}' must be relative to {} without '..'",
+            requested,
+            dir.display()
+        );
+    }
+    Ok(dir.join(relative))
+}
+
+/// Index file named by an MCP client, kept inside the directory of `embedding.index_cache`
+pub fn shared_index_path(requested: &str) -> Result<PathBuf> {
+    let cache = &Config::settings().embedding.index_cache;
+    path_within(cache.parent().unwrap_or(Path::new(".")), requested)
+}
+
+/// Load an exported index into the catalog, the bugs directory, the cache and the live indexes
+pub async fn import(path: &Path, mode: ImportMode) -> Result<ImportSummary> {
+    let snapshot = IndexSnapshot::read(path)?;
+    let (model, dimension) = configured_model()?;
+    snapshot.check_compatible(&model, dimension)?;
+
+    // Parse everything before writing anything
+    let mut patterns: Vec<(BugPatternDocument, Option<PatternEntry>, Vec<f64>)> = Vec::new();
+    for pattern in &snapshot.patterns {
+        let document: BugPatternDocument =
+            serde_json::from_str(&pattern.document).context("Invalid pattern in index")?;
+        let entry = match &pattern.entry {
+            Some(entry) => Some(
+                serde_json::from_str::<PatternEntry>(entry)
+                    .context("Invalid catalog entry in index")?,
+            ),
+            None => None,
+        };
+        patterns.push((document, entry, pattern.embedding.clone()));
+    }
+    let mut bugs: BTreeMap<String, (PathBuf, StoredBug)> = BTreeMap::new();
+    for bug in &snapshot.bugs {
+        let Some(file_name) = safe_file_name(&bug.file_name) else {
+            bail!(
+                "Index holds bug {} with unsafe file name '{}'",
+                bug.bug_id,
+                bug.file_name
+            );
+        };
+        let record: StoredBug = serde_json::from_str(&bug.record)
+            .with_context(|| format!("Invalid bug {} in index", bug.bug_id))?;
+        bugs.insert(record.bug_id.clone(), (file_name, record));
+    }
+
+    let mut summary = ImportSummary {
+        mode,
+        model,
+        patterns: patterns.len(),
+        bugs: bugs.len(),
+        removed_patterns: 0,
+        removed_bugs: 0,
+    };
+
+    // Everything in the file is written before anything missing from it is removed,
+    // so a failed import never leaves the catalog or the bugs directory emptied
+
+    // Pattern catalog
+    let patterns_dir = Config::patterns_directory_path();
+    let local_patterns = pattern_catalog::load_catalog(&patterns_dir)?;
+    for entry in patterns.iter().filter_map(|(_, entry, _)| entry.as_ref()) {
+        pattern_catalog::save_pattern(&patterns_dir, entry)?;
+    }
+
+    // Bug records, overwriting a record of the same bug wherever it is stored
+    let bugs_dir = Config::bugs_directory_path();
+    std::fs::create_dir_all(&bugs_dir)
+        .with_context(|| format!("Failed to create {}", bugs_dir.display()))?;
+    let existing: HashMap<String, (PathBuf, StoredBug)> = bug_store::load_bugs(&bugs_dir)
+        .await?
+        .into_iter()
+        .map(|(path, bug)| (bug.bug_id.clone(), (path, bug)))
+        .collect();
+    let mut written = HashSet::new();
+    for (bug_id, (file_name, bug)) in &mut bugs {
+        let path = match existing.get(bug_id) {
+            Some((path, local)) => {
+                if mode == ImportMode::Merge {
+                    keep_local_review(bug, local);
+                }
+                path.clone()
+            }
+            None => bugs_dir.join(file_name),
+        };
+        bug_store::save_bug(&path, bug).await?;
+        written.insert(path);
+    }
+
+    if mode == ImportMode::Replace {
+        let incoming: HashSet<&str> = patterns
+            .iter()
+            .map(|(document, _, _)| document.id.as_str())
+            .collect();
+        for entry in local_patterns {
+            if !incoming.contains(entry.id.as_str()) {
+                pattern_catalog::delete_pattern(&patterns_dir, &entry.id)?;
+                vector_store::unindex_pattern(&entry.id).await?;
+                summary.removed_patterns += 1;
+            }
+        }
+        for (bug_id, (path, _)) in &existing {
+            if !bugs.contains_key(bug_id) && !written.contains(path) {
+                std::fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                summary.removed_bugs += 1;
+            }
+        }
+    }
+
+    // Embedding cache, then the indexes already built in this process
+    let cache_path = &Config::settings().embedding.index_cache;
+    let cached = match mode {
+        ImportMode::Merge if cache_path.is_file() => match IndexSnapshot::read(cache_path) {
+            Ok(mut cached) if cached.check_compatible(&summary.model, dimension).is_ok() => {
+                cached.merge(snapshot);
+                cached
+            }
+            _ => snapshot,
+        },
+        _ => snapshot,
+    };
+    cached.write(cache_path)?;
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+
+    if let Some(store) = vector_store::loaded() {
+        let mut store = store.write().await;
+        for (document, _, embedding) in patterns {
+            store.add_embedded(document, embedding)?;
+        }
+    }
+    bug_index::refresh().await?;
+
+    info!(
+        "📦 Imported {} patterns and {} bugs from {} ({:?})",
+        summary.patterns,
+        summary.bugs,
+        path.display(),
+        mode
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bug_store::{BugStatus, StatusChange};
+
+    fn snapshot(pattern_id: &str, content: &str, bug_id: &str) -> IndexSnapshot {
+        let document = BugPatternDocument {
+            id: pattern_id.to_string(),
+            category: "VALIDATION".to_string(),
+            severity: 6,
+            content: content.to_string(),
+            ..Default::default()
+        };
+        IndexSnapshot {
+            version: SNAPSHOT_VERSION,
+            model: "AllMiniLML6V2".to_string(),
+            dimension: 2,
+            exported_at: Utc::now().to_rfc3339(),
+            patterns: vec![SnapshotPattern {
+                document: serde_json::to_string(&document).unwrap(),
+                entry: None,
+                embedding: vec![0.6, 0.8],
+            }],
+            bugs: vec![SnapshotBug {
+                bug_id: bug_id.to_string(),
+                file_name: format!("AUTO_BUG_{}.json", bug_id),
+                record: serde_json::to_string(&StoredBug {
+                    bug_id: bug_id.to_string(),
+                    ..Default::default()
+                })
+                .unwrap(),
+                text: "Price parsed through f64".to_string(),
+                embedding: vec![1.0, 0.0],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_round_trip_and_compatibility() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/index.bin");
+        snapshot("p1", "Lot size ignored", "b1")
+            .write(&path)
+            .unwrap();
+
+        let read = IndexSnapshot::read(&path).unwrap();
+        assert_eq!((read.patterns.len(), read.bugs.len()), (1, 1));
+        read.check_compatible("AllMiniLML6V2", 2).unwrap();
+        let err = read
+            .check_compatible("BGESmallENV15", 384)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("AllMiniLML6V2 (2 dimensions)"), "{}", err);
+
+        let mut bad = read.clone();
+        bad.bugs[0].embedding.push(0.0);
+        assert!(bad.check_compatible("AllMiniLML6V2", 2).is_err());
+
+        std::fs::write(&path, b"not bincode").unwrap();
+        assert!(IndexSnapshot::read(&path).is_err());
+    }
+
+    #[test]
+    fn test_cache_reuses_unchanged_text_only() {
+        let mut cached = snapshot("p1", "Lot size ignored", "b1");
+        cached.merge(snapshot("p1", "Lot size not rounded", "b2"));
+        assert_eq!((cached.patterns.len(), cached.bugs.len()), (1, 2));
+
+        let cache = EmbeddingCache::from_snapshot(cached);
+        let mut pattern = BugPatternDocument {
+            id: "p1".to_string(),
+            content: "Lot size not rounded".to_string(),
+            ..Default::default()
+        };
+        let model = FastembedModel::AllMiniLML6V2;
+        assert_eq!(
+            cache
+                .pattern_embedding(&model, &pattern)
+                .unwrap()
+                .first()
+                .vec,
+            vec![0.6, 0.8]
+        );
+        // Vectors of one model are never handed to a store built with another
+        assert!(cache
+            .pattern_embedding(&FastembedModel::BGESmallENV15, &pattern)
+            .is_none());
+        pattern.content = "Lot size ignored".to_string();
+        assert!(cache.pattern_embedding(&model, &pattern).is_none());
+
+        let document = BugDocument {
+            bug_id: "b2".to_string(),
+            text: "Price parsed through f64".to_string(),
+        };
+        assert!(cache.bug_embedding(&model, &document).is_some());
+        assert!(cache
+            .bug_embedding(&FastembedModel::BGESmallENV15, &document)
+            .is_none());
+    }
+
+    #[test]
+    fn test_safe_file_name() {
+        assert!(safe_file_name("AUTO_BUG_x.json").is_some());
+        assert!(safe_file_name("../AUTO_BUG_x.json").is_none());
+        assert!(safe_file_name("notes.json").is_none());
+    }
+
+    #[test]
+    fn test_merge_keeps_local_review() {
+        let mut local = StoredBug {
+            bug_id: "b1".to_string(),
+            status: BugStatus::Fixed,
+            status_history: vec![StatusChange {
+                status: BugStatus::Fixed,
+                timestamp: Utc::now().to_rfc3339(),
+                commit_hash: Some("abc123".to_string()),
+                note: None,
+            }],
+            ..Default::default()
+        };
+        local.mark_confirmed("triage");
+        let mut incoming = StoredBug {
+            bug_id: "b1".to_string(),
+            description: "Price parsed through f64".to_string(),
+            ..Default::default()
+        };
+        keep_local_review(&mut incoming, &local);
+        assert_eq!(incoming.status, BugStatus::Fixed);
+        assert_eq!(incoming.status_history, local.status_history);
+        assert!(incoming.is_confirmed());
+        assert_eq!(incoming.description, "Price parsed through f64");
+    }
+
+    #[test]
+    fn test_path_within() {
+        let dir = Path::new("/srv/rig/index");
+        assert_eq!(
+            path_within(dir, "team/index.bin").unwrap(),
+            dir.join("team/index.bin")
+        );
+        assert!(path_within(dir, "/etc/passwd").is_err());
+        assert!(path_within(dir, "../bugs/index.bin").is_err());
+        assert!(path_within(dir, "team/../../x.bin").is_err());
+        assert_eq!(path_within(dir, "./x.bin").unwrap(), dir.join("./x.bin"));
+        assert!(path_within(dir, " ").is_err());
+    }
+}
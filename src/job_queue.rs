@@ -0,0 +1,713 @@
+//! Durable job queue for analysis work items
+//!
+//! Every file, or line range of a file, analyzed in a run is an apalis job stored
+//! in `jobs/analysis_queue.sqlite`, so a crash mid-sweep loses at most the job in
+//! flight. Status, attempts and errors live in apalis' `Jobs` table; per-job
+//! results are kept in `AnalysisResults` until the last job of a run finishes
+//! and the run is finalized into an `analysis_summary_*.json`.
+
+use anyhow::{anyhow, Result};
+use apalis::prelude::*;
+use apalis::sqlite::SqliteStorage;
+use apalis::utils::TokioExecutor;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, OnceCell};
+use tracing::{debug, error, info, warn};
+
+use crate::bug_index;
+use crate::bug_store::{AnalyzedFile, LifecycleSummary, LifecycleTracker};
+use crate::config::Config;
+use crate::repo_paths;
+use crate::UnifiedServerState;
+
+/// SQLite database holding the queue, inside the jobs directory
+pub const QUEUE_DATABASE: &str = "analysis_queue.sqlite";
+
+/// Jobs that have not reached a final outcome yet
+///
+/// apalis keeps fetching a `Failed` job until its attempts reach `max_attempts`,
+/// so a failure only becomes final then.
+const UNFINISHED_JOBS: &str =
+    "(status IN ('Pending', 'Running') OR (status = 'Failed' AND attempts < max_attempts))";
+
+/// Jobs that ended without a result: cancelled, or failed with no attempts left
+const ABANDONED_JOBS: &str =
+    "(status = 'Killed' OR (status = 'Failed' AND attempts >= max_attempts))";
+
+/// Jobs that are not running and will still be picked up
+const CANCELLABLE_JOBS: &str =
+    "(status = 'Pending' OR (status = 'Failed' AND attempts < max_attempts))";
+
+/// Interval at which waiting callers and the worker poll for run completion
+const RUN_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Result statuses of an analysis that should be retried
+const FAILED_ANALYSIS: [&str; 2] = ["error", "analysis_failed"];
+
+/// Analysis of one source file, or an inclusive 1-based line range of it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnalysisJob {
+    /// Repository-relative source file
+    pub file_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<(usize, usize)>,
+    /// Run the job belongs to; ad-hoc jobs have none and write no summary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+}
+
+impl AnalysisJob {
+    /// Key identifying the job's work item within a run
+    pub fn key(&self) -> String {
+        match self.lines {
+            Some((start, end)) => format!("{}#L{}-L{}", self.file_path, start, end),
+            None => self.file_path.clone(),
+        }
+    }
+}
+
+impl Job for AnalysisJob {
+    const NAME: &'static str = "nautilus_trader_rig::analysis_job";
+}
+
+/// Queued job as reported by `list`
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedJob {
+    pub id: String,
+    pub file_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<(usize, usize)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub status: String,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub run_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub done_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Handle to the persistent analysis queue
+#[derive(Clone)]
+pub struct JobQueue {
+    pool: SqlitePool,
+    storage: SqliteStorage<AnalysisJob>,
+}
+
+impl JobQueue {
+    /// Path of the queue database
+    pub fn database_path() -> PathBuf {
+        Config::jobs_directory_path().join(QUEUE_DATABASE)
+    }
+
+    /// Open the queue database, creating its tables on first use
+    pub async fn open() -> Result<Self> {
+        Self::open_at(&Self::database_path()).await
+    }
+
+    /// Open the queue database at `path`
+    async fn open_at(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_with(options)
+            .await?;
+        SqliteStorage::setup(&pool).await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS AnalysisRuns (
+                run_id TEXT PRIMARY KEY,
+                total_jobs INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                finalized_at TEXT
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS AnalysisResults (
+                run_id TEXT NOT NULL,
+                job_key TEXT NOT NULL,
+                result TEXT NOT NULL,
+                analyzed_file TEXT,
+                PRIMARY KEY (run_id, job_key)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        debug!("Analysis queue opened at {}", path.display());
+        Ok(Self {
+            storage: SqliteStorage::new(pool.clone()),
+            pool,
+        })
+    }
+
+    /// Queue shared by the workers, scheduler and MCP tools of this process
+    pub async fn shared() -> Result<&'static JobQueue> {
+        static QUEUE: OnceCell<JobQueue> = OnceCell::const_new();
+        QUEUE.get_or_try_init(Self::open).await
+    }
+
+    /// Push a single job, returning its ID
+    pub async fn enqueue(&self, job: AnalysisJob) -> Result<String> {
+        let mut storage = self.storage.clone();
+        let id = storage.push(job).await?;
+        Ok(id.to_string())
+    }
+
+    /// Register a new run and queue one job per repository-relative file, returning the run ID
+    ///
+    /// Run IDs carry microseconds so runs started in the same second stay apart; an ID
+    /// that is taken all the same, e.g. by another process, is drawn again.
+    pub async fn enqueue_run(&self, files: &[String]) -> Result<String> {
+        let run_id = loop {
+            let now = chrono::Utc::now();
+            let run_id = now.format("%Y%m%d_%H%M%S_%6f").to_string();
+            let inserted = sqlx::query(
+                "INSERT INTO AnalysisRuns (run_id, total_jobs, created_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (run_id) DO NOTHING",
+            )
+            .bind(&run_id)
+            .bind(files.len() as i64)
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+            if inserted == 1 {
+                break run_id;
+            }
+            debug!("Run ID {} is taken, drawing another", run_id);
+        };
+
+        for file_path in files {
+            self.enqueue(AnalysisJob {
+                file_path: file_path.clone(),
+                lines: None,
+                run_id: Some(run_id.clone()),
+            })
+            .await?;
+        }
+        info!("📥 Queued {} analysis jobs for run {}", files.len(), run_id);
+        Ok(run_id)
+    }
+
+    /// List jobs, newest first, optionally filtered by apalis status (e.g. Pending, Done)
+    pub async fn list(&self, status: Option<&str>, limit: usize) -> Result<Vec<QueuedJob>> {
+        let rows = sqlx::query(
+            "SELECT id, job, status, attempts, max_attempts, run_at, done_at, last_error
+             FROM Jobs
+             WHERE job_type = ?1 AND (?2 IS NULL OR status = ?2 COLLATE NOCASE)
+             ORDER BY run_at DESC, rowid DESC
+             LIMIT ?3",
+        )
+        .bind(AnalysisJob::NAME)
+        .bind(status)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut jobs = Vec::with_capacity(rows.len());
+        for row in rows {
+            let job: AnalysisJob = serde_json::from_str(&row.try_get::<String, _>("job")?)?;
+            jobs.push(QueuedJob {
+                id: row.try_get("id")?,
+                file_path: job.file_path,
+                lines: job.lines,
+                run_id: job.run_id,
+                status: row.try_get("status")?,
+                attempts: row.try_get("attempts")?,
+                max_attempts: row.try_get("max_attempts")?,
+                run_at: row.try_get("run_at")?,
+                done_at: row.try_get("done_at")?,
+                last_error: row.try_get("last_error")?,
+            });
+        }
+        Ok(jobs)
+    }
+
+    /// Cancel a job that has not started yet or is waiting to be retried
+    ///
+    /// Returns false when no such job has that ID. Cancelling the last open job
+    /// of a run finalizes the run.
+    pub async fn cancel(&self, job_id: &str) -> Result<bool> {
+        // One row comes back per cancelled job, carrying the run it belonged to
+        let cancelled: Option<Option<String>> = sqlx::query_scalar(&format!(
+            "UPDATE Jobs SET status = 'Killed', done_at = strftime('%s', 'now'), last_error = 'Cancelled'
+             WHERE id = ?1 AND job_type = ?2 AND {}
+             RETURNING json_extract(job, '$.run_id')",
+            CANCELLABLE_JOBS
+        ))
+        .bind(job_id)
+        .bind(AnalysisJob::NAME)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(run_id) = cancelled else {
+            return Ok(false);
+        };
+
+        info!("🛑 Cancelled analysis job {}", job_id);
+        if let Some(run_id) = run_id {
+            self.finalize_if_complete(&run_id).await?;
+        }
+        Ok(true)
+    }
+
+    /// Requeue jobs left running by a crashed process and finalize runs that completed
+    pub async fn resume_interrupted(&self) -> Result<usize> {
+        let requeued = sqlx::query(
+            "UPDATE Jobs SET status = 'Pending', lock_by = NULL, lock_at = NULL
+             WHERE job_type = ?1 AND status = 'Running'",
+        )
+        .bind(AnalysisJob::NAME)
+        .execute(&self.pool)
+        .await?
+        .rows_affected() as usize;
+        if requeued > 0 {
+            warn!(
+                "⚠️ Requeued {} analysis jobs interrupted by a previous shutdown",
+                requeued
+            );
+        }
+
+        self.finalize_completed_runs().await?;
+        Ok(requeued)
+    }
+
+    /// Finalize every open run whose jobs are all done, returning how many were finalized
+    ///
+    /// Catches runs whose last job ran out of attempts, which no job result finalizes.
+    pub async fn finalize_completed_runs(&self) -> Result<usize> {
+        let open_runs: Vec<String> =
+            sqlx::query_scalar("SELECT run_id FROM AnalysisRuns WHERE finalized_at IS NULL")
+                .fetch_all(&self.pool)
+                .await?;
+        let mut finalized = 0;
+        for run_id in open_runs {
+            if self.finalize_if_complete(&run_id).await?.is_some() {
+                finalized += 1;
+            }
+        }
+        Ok(finalized)
+    }
+
+    /// Store the result of a job that belongs to a run
+    async fn record_result(
+        &self,
+        run_id: &str,
+        job: &AnalysisJob,
+        result: &Value,
+        analyzed: Option<&AnalyzedFile>,
+    ) -> Result<()> {
+        let mut result = result.clone();
+        if let Some(fields) = result.as_object_mut() {
+            fields.retain(|_, value| !value.is_null());
+        }
+        sqlx::query("INSERT OR REPLACE INTO AnalysisResults (run_id, job_key, result, analyzed_file) VALUES (?1, ?2, ?3, ?4)")
+            .bind(run_id)
+            .bind(job.key())
+            .bind(serde_json::to_string(&result)?)
+            .bind(analyzed.map(serde_json::to_string).transpose()?)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Whether every job of a run has a result, was cancelled or ran out of attempts
+    async fn run_is_complete(&self, run_id: &str) -> Result<bool> {
+        let total: Option<i64> =
+            sqlx::query_scalar("SELECT total_jobs FROM AnalysisRuns WHERE run_id = ?1")
+                .bind(run_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        let Some(total) = total else {
+            return Ok(false);
+        };
+        let results: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM AnalysisResults WHERE run_id = ?1")
+                .bind(run_id)
+                .fetch_one(&self.pool)
+                .await?;
+        let abandoned: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM Jobs
+             WHERE job_type = ?1 AND json_extract(job, '$.run_id') = ?2 AND {}",
+            ABANDONED_JOBS
+        ))
+        .bind(AnalysisJob::NAME)
+        .bind(run_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(results + abandoned >= total)
+    }
+
+    /// Write the run summary once all jobs of a run are done
+    ///
+    /// Returns the summary path when the run was finalized by this call.
+    pub async fn finalize_if_complete(&self, run_id: &str) -> Result<Option<PathBuf>> {
+        if !self.run_is_complete(run_id).await? {
+            return Ok(None);
+        }
+        let claimed = sqlx::query(
+            "UPDATE AnalysisRuns SET finalized_at = ?2 WHERE run_id = ?1 AND finalized_at IS NULL",
+        )
+        .bind(run_id)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        if claimed == 0 {
+            return Ok(None);
+        }
+
+        let mut results = Vec::new();
+        let mut analyzed_files = Vec::new();
+        let rows = sqlx::query(
+            "SELECT result, analyzed_file FROM AnalysisResults WHERE run_id = ?1 ORDER BY job_key",
+        )
+        .bind(run_id)
+        .fetch_all(&self.pool)
+        .await?;
+        for row in rows {
+            results.push(serde_json::from_str::<Value>(
+                &row.try_get::<String, _>("result")?,
+            )?);
+            if let Some(analyzed) = row.try_get::<Option<String>, _>("analyzed_file")? {
+                analyzed_files.push(serde_json::from_str::<AnalyzedFile>(&analyzed)?);
+            }
+        }
+
+        // Jobs that never produced a result still appear in the summary
+        let abandoned_query = format!(
+            "SELECT job, status, last_error FROM Jobs
+             WHERE job_type = ?1 AND json_extract(job, '$.run_id') = ?2 AND {}",
+            ABANDONED_JOBS
+        );
+        let abandoned = sqlx::query(&abandoned_query)
+            .bind(AnalysisJob::NAME)
+            .bind(run_id)
+            .fetch_all(&self.pool)
+            .await?;
+        for row in abandoned {
+            let job: AnalysisJob = serde_json::from_str(&row.try_get::<String, _>("job")?)?;
+            let status: String = row.try_get("status")?;
+            results.push(serde_json::json!({
+                "file_path": job.file_path,
+                "lines": job.lines.map(|(start, end)| format!("{}-{}", start, end)),
+                "status": if status == "Killed" { "cancelled" } else { "error" },
+                "error": row.try_get::<Option<String>, _>("last_error")?,
+            }));
+        }
+
+        // Rebuild the lifecycle breakdown from the job results, then close bugs
+        // that none of the jobs detected again
+        let mut lifecycle = LifecycleSummary::default();
+        let mut detected = HashSet::new();
+        for result in &results {
+            let Some(bug_id) = result["bug_id"].as_str() else {
+                continue;
+            };
+            detected.insert(bug_id.to_string());
+            match result["lifecycle"].as_str() {
+                Some("new") => lifecycle.new.push(bug_id.to_string()),
+                Some("regressed") => lifecycle.regressed.push(bug_id.to_string()),
+                Some("persisting") => lifecycle.persisting.push(bug_id.to_string()),
+                _ => {}
+            }
+        }
+        let mut tracker = LifecycleTracker::load(
+            &Config::bugs_directory_path(),
+            crate::get_git_commit_hash().await,
+        )
+        .await?;
+        tracker.mark_detected(&detected);
+        tracker.close_undetected(&analyzed_files).await?;
+        lifecycle.fixed.extend(tracker.summary.fixed);
+        lifecycle.persisting.extend(tracker.summary.persisting);
+
+        let total = results.len();
+        let summary = crate::write_run_summary(run_id, total, &results, &lifecycle).await?;
+        sqlx::query("DELETE FROM AnalysisResults WHERE run_id = ?1")
+            .bind(run_id)
+            .execute(&self.pool)
+            .await?;
+        info!("✅ Run {} finalized: {}", run_id, summary.display());
+        Ok(Some(summary))
+    }
+
+    /// Wait until a run has been finalized
+    pub async fn wait_for_run(&self, run_id: &str) -> Result<()> {
+        loop {
+            let finalized: Option<Option<String>> =
+                sqlx::query_scalar("SELECT finalized_at FROM AnalysisRuns WHERE run_id = ?1")
+                    .bind(run_id)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            match finalized {
+                None => return Err(anyhow!("Unknown analysis run '{}'", run_id)),
+                Some(Some(_)) => return Ok(()),
+                Some(None) => tokio::time::sleep(RUN_POLL_INTERVAL).await,
+            }
+        }
+    }
+
+    /// Number of jobs that have not reached a final state
+    pub async fn pending_count(&self) -> Result<i64> {
+        Ok(sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM Jobs WHERE job_type = ?1 AND {}",
+            UNFINISHED_JOBS
+        ))
+        .bind(AnalysisJob::NAME)
+        .fetch_one(&self.pool)
+        .await?)
+    }
+}
+
+#[derive(Clone)]
+struct WorkerContext {
+    app: Arc<UnifiedServerState>,
+    queue: JobQueue,
+    /// Stored bugs loaded once per batch of jobs, `None` between batches
+    batch: Arc<Mutex<Option<LifecycleTracker>>>,
+}
+
+impl WorkerContext {
+    /// Finalize completed runs and end the batch once a run finished or the queue drained
+    ///
+    /// Finalizing rewrites stored bugs, so the batch tracker is dropped rather than
+    /// kept with stale copies, and bug search picks up the batch's records.
+    async fn settle(&self) -> Result<()> {
+        let mut batch = self.batch.lock().await;
+        let finalized = self.queue.finalize_completed_runs().await?;
+        if batch.is_some() && (finalized > 0 || self.queue.pending_count().await? == 0) {
+            *batch = None;
+            refresh_bug_index().await;
+        }
+        Ok(())
+    }
+}
+
+/// Keep semantic bug search in step with new and updated records
+async fn refresh_bug_index() {
+    if let Err(e) = bug_index::refresh().await {
+        warn!("⚠️ Failed to refresh bug index: {}", e);
+    }
+}
+
+/// Error of an analysis result that should be retried, if any
+fn analysis_error(result: &Value) -> Option<String> {
+    let status = result["status"].as_str()?;
+    if !FAILED_ANALYSIS.contains(&status) {
+        return None;
+    }
+    Some(result["error"].as_str().unwrap_or(status).to_string())
+}
+
+async fn run_analysis_job(job: AnalysisJob, ctx: Data<WorkerContext>) -> Result<(), Error> {
+    process_job(&job, &ctx).await.map_err(|e| {
+        error!("❌ Analysis job for {} failed: {}", job.key(), e);
+        Error::Failed(e.into())
+    })
+}
+
+async fn process_job(job: &AnalysisJob, ctx: &WorkerContext) -> Result<()> {
+    println!("   📄 Analyzing {}", job.key());
+    let file_path = repo_paths::resolve(&job.file_path);
+    let mut batch = ctx.batch.lock().await;
+    let mut tracker = match batch.take() {
+        Some(tracker) => tracker,
+        None => {
+            LifecycleTracker::load(
+                &Config::bugs_directory_path(),
+                crate::get_git_commit_hash().await,
+            )
+            .await?
+        }
+    };
+    let analysis = crate::analyze_file(
+        &ctx.app,
+        &mut tracker,
+        &file_path.to_string_lossy(),
+        job.lines,
+    )
+    .await;
+
+    // Failed analyses are left to apalis to retry; the run counts them once attempts run out
+    if let Some(error) = analysis_error(&analysis.result) {
+        *batch = Some(tracker);
+        return Err(anyhow!(error));
+    }
+
+    match &job.run_id {
+        Some(run_id) => {
+            ctx.queue
+                .record_result(run_id, job, &analysis.result, analysis.analyzed.as_ref())
+                .await?;
+            if ctx.queue.finalize_if_complete(run_id).await?.is_some() {
+                refresh_bug_index().await;
+                return Ok(());
+            }
+        }
+        None => {
+            // Ad-hoc jobs update the lifecycle of their own file right away
+            if let Some(analyzed) = analysis.analyzed {
+                tracker.close_undetected(&[analyzed]).await?;
+            }
+        }
+    }
+    *batch = Some(tracker);
+    Ok(())
+}
+
+/// Run the analysis worker until the process is stopped
+///
+/// Jobs interrupted by a previous shutdown are requeued first, so an interrupted
+/// run resumes where it stopped.
+pub async fn run_worker(app: Arc<UnifiedServerState>) -> Result<()> {
+    let queue = JobQueue::shared().await?.clone();
+    queue.resume_interrupted().await?;
+    info!(
+        "🧵 Analysis worker started with {} unfinished jobs",
+        queue.pending_count().await?
+    );
+
+    let ctx = WorkerContext {
+        app,
+        queue: queue.clone(),
+        batch: Arc::new(Mutex::new(None)),
+    };
+    let worker = WorkerBuilder::new("rig-analysis")
+        .data(ctx.clone())
+        .with_storage(queue.storage.clone())
+        .build_fn(run_analysis_job);
+    let settle = async {
+        loop {
+            tokio::time::sleep(RUN_POLL_INTERVAL).await;
+            if let Err(e) = ctx.settle().await {
+                warn!("⚠️ Failed to settle analysis batch: {}", e);
+            }
+        }
+    };
+    tokio::select! {
+        result = Monitor::<TokioExecutor>::new().register(worker).run() => result?,
+        _ = settle => {}
+    }
+    ctx.settle().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_key_and_roundtrip() {
+        let job = AnalysisJob {
+            file_path: "crates/adapters/okx/src/parse.rs".to_string(),
+            lines: Some((10, 80)),
+            run_id: Some("20250825_183509".to_string()),
+        };
+        assert_eq!(job.key(), "crates/adapters/okx/src/parse.rs#L10-L80");
+
+        let json = serde_json::to_string(&job).unwrap();
+        assert_eq!(serde_json::from_str::<AnalysisJob>(&json).unwrap(), job);
+
+        let adhoc: AnalysisJob =
+            serde_json::from_str(r#"{"file_path":"crates/adapters/okx/src/lib.rs"}"#).unwrap();
+        assert_eq!(adhoc.key(), "crates/adapters/okx/src/lib.rs");
+        assert!(adhoc.run_id.is_none());
+    }
+
+    #[test]
+    fn test_analysis_error() {
+        let failed = serde_json::json!({"status": "analysis_failed", "error": "timeout"});
+        assert_eq!(analysis_error(&failed).as_deref(), Some("timeout"));
+        let unreadable = serde_json::json!({"status": "error"});
+        assert_eq!(analysis_error(&unreadable).as_deref(), Some("error"));
+        assert_eq!(
+            analysis_error(&serde_json::json!({"status": "clean"})),
+            None
+        );
+        assert_eq!(
+            analysis_error(&serde_json::json!({"status": "bug_found_but_storage_failed"})),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_keeps_run_open_until_attempts_run_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = JobQueue::open_at(&dir.path().join(QUEUE_DATABASE))
+            .await
+            .unwrap();
+        let run_id = queue
+            .enqueue_run(&["crates/adapters/okx/src/parse.rs".to_string()])
+            .await
+            .unwrap();
+        let run_id = run_id.as_str();
+
+        // What apalis records when a job returns an error and will be retried
+        let fail = |attempts: &'static str| {
+            format!("UPDATE Jobs SET status = 'Failed', attempts = {}, last_error = 'boom', lock_by = NULL", attempts)
+        };
+        sqlx::query(&fail("1")).execute(&queue.pool).await.unwrap();
+        assert!(!queue.run_is_complete(run_id).await.unwrap());
+        assert_eq!(queue.finalize_if_complete(run_id).await.unwrap(), None);
+        assert_eq!(queue.pending_count().await.unwrap(), 1);
+
+        sqlx::query(&fail("max_attempts"))
+            .execute(&queue.pool)
+            .await
+            .unwrap();
+        assert!(queue.run_is_complete(run_id).await.unwrap());
+        assert_eq!(queue.pending_count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_runs_started_together_get_distinct_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = JobQueue::open_at(&dir.path().join(QUEUE_DATABASE))
+            .await
+            .unwrap();
+        let files = ["crates/adapters/okx/src/lib.rs".to_string()];
+        let (a, b) = tokio::join!(queue.enqueue_run(&files), queue.enqueue_run(&files));
+        let (a, b) = (a.unwrap(), b.unwrap());
+        assert_ne!(a, b);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_job_waiting_for_retry() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = JobQueue::open_at(&dir.path().join(QUEUE_DATABASE))
+            .await
+            .unwrap();
+        let id = queue
+            .enqueue(AnalysisJob {
+                file_path: "crates/adapters/okx/src/lib.rs".to_string(),
+                lines: None,
+                run_id: None,
+            })
+            .await
+            .unwrap();
+        sqlx::query("UPDATE Jobs SET status = 'Failed', attempts = 1")
+            .execute(&queue.pool)
+            .await
+            .unwrap();
+
+        assert!(queue.cancel(&id).await.unwrap());
+        assert!(!queue.cancel(&id).await.unwrap());
+        assert_eq!(queue.list(Some("Killed"), 10).await.unwrap().len(), 1);
+    }
+}
@@ -218,8 +218,11 @@ pub fn init_default_logging() -> Result<()> {
 pub fn init_dev_logging() -> Result<()> {
     // Create logs directory if it doesn't exist
     if !Config::logs_directory_exists() {
-        std::fs::create_dir_all(Config::logs_directory())?;
-        println!("📁 Created logs directory: {:?}", Config::logs_directory());
+        std::fs::create_dir_all(Config::logs_directory_path())?;
+        println!(
+            "📁 Created logs directory: {:?}",
+            Config::logs_directory_path()
+        );
     }
 
     // Generate timestamped log file path
@@ -3,24 +3,51 @@
 // This implementation uses rig-sqlite for vector similarity search
 
 use anyhow::Result;
-use std::time::Duration;
+use std::collections::HashSet;
+use std::sync::Arc;
 use tracing::{debug, error, info, trace, warn};
 
+mod batch_embed;
+mod bug_index;
+mod bug_store;
+mod cli;
+mod clustering;
+mod code_index;
 mod config;
+mod dashboard;
 mod deepseek;
+mod doctor;
 mod fastembed;
+mod health;
+mod html_report;
+mod hybrid;
+mod index_snapshot;
+mod job_queue;
 mod logging;
 mod mcp;
+mod models;
+mod pattern_catalog;
+mod pattern_learning;
+mod repo_paths;
+mod retrieval_eval;
+mod risk;
+mod run_diff;
+mod sarif;
+mod scheduler;
+mod themes;
 mod vector_store;
 
+use bug_store::{AnalyzedFile, FindingMatch, FindingSite, LifecycleTracker};
 use config::Config;
 use deepseek::DeepSeekClient;
 use logging::{init_dev_logging, log_directory_op, log_file_processing, log_status};
 use mcp::run_mcp_server;
+use tokio::sync::RwLock;
 use vector_store::VectorStoreManager;
 
 pub struct UnifiedServerState {
-    pub vector_store: Option<VectorStoreManager>,
+    /// Process-wide pattern index, also used by every MCP session
+    pub vector_store: Option<&'static RwLock<VectorStoreManager>>,
     pub deepseek_client: Option<DeepSeekClient>,
 }
 
@@ -31,7 +58,7 @@ impl UnifiedServerState {
 
         // Initialize vector store with FastEmbed (no API key required)
         trace!("Attempting to initialize vector store with FastEmbed local embeddings");
-        let vector_store = match VectorStoreManager::new().await {
+        let vector_store = match vector_store::shared().await {
             Ok(store) => {
                 info!("✅ Vector store initialized with FastEmbed local embeddings");
                 debug!("Vector store ready for similarity search operations");
@@ -178,10 +205,12 @@ async fn test_vector_search(state: &UnifiedServerState) -> Result<()> {
         debug!("Vector store available, proceeding with similarity search tests");
         println!("🔍 Testing vector similarity search with rig-sqlite...");
 
-        let test_queries = ["authentication bypass vulnerability",
+        let test_queries = [
+            "authentication bypass vulnerability",
             "websocket security issues",
             "rate limiting problems",
-            "memory leak"];
+            "memory leak",
+        ];
 
         debug!("Testing {} different search queries", test_queries.len());
 
@@ -194,7 +223,12 @@ async fn test_vector_search(state: &UnifiedServerState) -> Result<()> {
                 query
             );
 
-            match vector_store.similarity_search(query, 3).await {
+            match vector_store
+                .read()
+                .await
+                .similarity_search(query, &Default::default(), 3)
+                .await
+            {
                 Ok(results) => {
                     debug!(
                         "Similarity search completed, found {} results",
@@ -231,14 +265,12 @@ async fn test_vector_search(state: &UnifiedServerState) -> Result<()> {
     Ok(())
 }
 
-// Function to discover Rust files in the adapters directory
-async fn discover_rust_files(adapters_path: &str) -> Result<Vec<String>> {
+// Function to discover Rust files in the configured adapter roots
+async fn discover_rust_files(adapter_roots: &[std::path::PathBuf]) -> Result<Vec<String>> {
     let mut rust_files = Vec::new();
 
     // Use the same logic as the main function for file discovery
-    let rust_dirs = vec![std::path::Path::new(adapters_path)];
-
-    for rust_dir in rust_dirs {
+    for rust_dir in adapter_roots {
         if let Ok(entries) = std::fs::read_dir(rust_dir) {
             for entry in entries.flatten() {
                 if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
@@ -262,19 +294,28 @@ async fn discover_rust_files(adapters_path: &str) -> Result<Vec<String>> {
     Ok(rust_files)
 }
 
-// Function to analyze adapter files for bugs and store results with enhanced location tracking
-async fn analyze_adapter_files_for_bugs(state: &UnifiedServerState) -> Result<()> {
+/// Analyze adapter files for bugs, restricted to `only` (repository-relative paths) when given
+///
+/// Every file becomes a job on the analysis queue; this returns the run ID once the
+/// run has been finalized into an analysis summary, or `None` when no file matched.
+async fn analyze_adapter_files_for_bugs(
+    queue: &job_queue::JobQueue,
+    only: Option<&HashSet<String>>,
+) -> Result<Option<String>> {
     println!("\n🔍 Starting automated bug analysis on adapter files...");
     info!("Beginning automated bug analysis workflow");
 
     // Get list of adapter files
-    let adapters_path = config::Config::core_adapters_directory_abs();
-    let rust_files = discover_rust_files(&adapters_path.to_string_lossy()).await?;
+    let mut rust_files = discover_rust_files(Config::adapter_roots()).await?;
+    if let Some(only) = only {
+        rust_files.retain(|file| only.contains(&repo_paths::to_repo_relative(file)));
+        debug!("Restricted analysis to {} selected files", rust_files.len());
+    }
 
     if rust_files.is_empty() {
         println!("⚠️ No Rust files found for analysis");
         warn!("Bug analysis skipped - no files discovered");
-        return Ok(());
+        return Ok(None);
     }
 
     println!("📁 Found {} files to analyze", rust_files.len());
@@ -283,206 +324,356 @@ async fn analyze_adapter_files_for_bugs(state: &UnifiedServerState) -> Result<()
         rust_files.len()
     );
 
-    let mut bugs_found = 0;
-    let mut files_analyzed = 0;
-    let mut analysis_results = Vec::new();
-
-    // Analyze each file
-    for (i, file_path) in rust_files.iter().enumerate() {
-        println!(
-            "   📄 Analyzing file {}/{}: {}",
-            i + 1,
-            rust_files.len(),
-            file_path
-        );
-        debug!("Starting analysis of file: {}", file_path);
+    // Re-embed adapter files that changed since the code index was built
+    if let Err(e) = code_index::refresh().await {
+        warn!("⚠️ Failed to refresh code index: {}", e);
+    }
 
-        // Read file content
-        let content = match tokio::fs::read_to_string(file_path).await {
-            Ok(content) => content,
-            Err(e) => {
-                println!("   ❌ Failed to read file: {}", e);
-                warn!("Skipping file due to read error: {} - {}", file_path, e);
-                analysis_results.push(serde_json::json!({
-                    "file_path": file_path,
+    let files: Vec<String> = rust_files
+        .iter()
+        .map(|f| repo_paths::to_repo_relative(f))
+        .collect();
+    let run_id = queue.enqueue_run(&files).await?;
+    println!("📥 Queued {} analysis jobs for run {}", files.len(), run_id);
+
+    queue.wait_for_run(&run_id).await?;
+    Ok(Some(run_id))
+}
+
+/// Outcome of analyzing one file or line range
+struct FileAnalysis {
+    /// Entry for the `file_results` section of the run summary
+    result: serde_json::Value,
+    /// Set when the whole file was analyzed, so undetected bugs in it can be closed
+    analyzed: Option<AnalyzedFile>,
+}
+
+/// Analyze one file, or an inclusive 1-based line range of it, and record any finding
+async fn analyze_file(
+    state: &UnifiedServerState,
+    tracker: &mut LifecycleTracker,
+    file_path: &str,
+    lines: Option<(usize, usize)>,
+) -> FileAnalysis {
+    debug!("Starting analysis of file: {}", file_path);
+    let relative_file_path = repo_paths::to_repo_relative(file_path);
+    let line_range = lines.map(|(start, end)| format!("{}-{}", start, end));
+
+    // Read file content
+    let file_content = match tokio::fs::read_to_string(file_path).await {
+        Ok(content) => content,
+        Err(e) => {
+            println!("   ❌ Failed to read file: {}", e);
+            warn!("Skipping file due to read error: {} - {}", file_path, e);
+            return FileAnalysis {
+                result: serde_json::json!({
+                    "file_path": relative_file_path,
+                    "lines": line_range,
                     "status": "error",
                     "error": format!("Failed to read file: {}", e),
                     "timestamp": chrono::Utc::now().to_rfc3339()
-                }));
-                continue;
+                }),
+                analyzed: None,
+            };
+        }
+    };
+    let content = match lines {
+        Some((start, end)) => file_content
+            .lines()
+            .skip(start.saturating_sub(1))
+            .take(end.saturating_sub(start) + 1)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None => file_content.clone(),
+    };
+
+    // Get file metadata for enhanced reporting
+    let last_modified = tokio::fs::metadata(file_path)
+        .await
+        .ok()
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    let file_metadata = match tokio::fs::metadata(file_path).await {
+        Ok(metadata) => Some(serde_json::json!({
+            "size_bytes": metadata.len(),
+            "lines_of_code": file_content.lines().count(),
+            "last_modified": last_modified
+        })),
+        Err(_) => None,
+    };
+    let analyzed_file = AnalyzedFile {
+        file_path: file_path.to_string(),
+        content_hash: bug_store::content_hash(&file_content),
+        last_modified,
+    };
+
+    // Analyze with DeepSeek
+    let Some(deepseek_client) = &state.deepseek_client else {
+        println!("   ⚠️ DeepSeek client not available");
+        warn!("Skipping analysis - DeepSeek client not initialized");
+
+        return FileAnalysis {
+            result: serde_json::json!({
+                "file_path": relative_file_path,
+                "lines": line_range,
+                "status": "skipped_no_client",
+                "file_metadata": file_metadata,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+            analyzed: None,
+        };
+    };
+
+    // Retrieve related code so the model sees how the same problem is handled elsewhere
+    let related_code =
+        match code_index::related_context(&relative_file_path, &file_content, &content).await {
+            Ok(Some(context)) => format!(
+                "{}\nThe code above is context only; report issues in the file under analysis.\n\n",
+                context
+            ),
+            Ok(None) => String::new(),
+            Err(e) => {
+                warn!(
+                    "Analyzing {} without code context: {}",
+                    relative_file_path, e
+                );
+                String::new()
             }
         };
 
-        files_analyzed += 1;
-
-        // Get file metadata for enhanced reporting
-        let file_metadata = match tokio::fs::metadata(file_path).await {
-            Ok(metadata) => Some(serde_json::json!({
-                "size_bytes": metadata.len(),
-                "lines_of_code": content.lines().count(),
-                "last_modified": metadata.modified()
-                    .ok()
-                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                    .map(|d| d.as_secs())
-            })),
-            Err(_) => None,
+    let analysis_prompt = format!(
+        "Analyze this Rust code for critical bugs, security vulnerabilities, and potential issues:\n\n\
+         File: {}{}\n\
+         Lines of code: {}\n\n\
+         ```rust\n{}\n```\n\n\
+         {}\
+         Focus on:\n\
+         - Security vulnerabilities (buffer overflows, injection attacks, etc.)\n\
+         - Memory safety issues\n\
+         - Logic errors that could cause financial losses\n\
+         - Performance bottlenecks\n\
+         - Error handling problems\n\
+         - Precision loss in financial calculations (especially with f64 conversions)\n\
+         - Type safety issues\n\n\
+         If you find critical issues, respond with:\n\
+         BUG_FOUND: yes\n\
+         SEVERITY: [CRITICAL|HIGH|MEDIUM|LOW]\n\
+         DESCRIPTION: [detailed description]\n\
+         CODE_SAMPLE: [relevant code snippet with function/line context]\n\
+         FIX_SUGGESTION: [how to fix it]\n\
+         AFFECTED_FUNCTIONS: [list of function names affected]\n\n\
+         If no critical issues found, respond with:\n\
+         BUG_FOUND: no\n\
+         ANALYSIS: [brief analysis summary]",
+        relative_file_path,
+        line_range.as_ref().map(|r| format!(" (lines {})", r)).unwrap_or_default(),
+        content.lines().count(),
+        content,
+        related_code
+    );
+
+    let analysis_result = match deepseek_client.analyze_code(&analysis_prompt).await {
+        Ok(analysis_result) => analysis_result,
+        Err(e) => {
+            println!("   ❌ Analysis failed: {}", e);
+            error!("DeepSeek analysis failed for {}: {}", file_path, e);
+
+            return FileAnalysis {
+                result: serde_json::json!({
+                    "file_path": relative_file_path,
+                    "lines": line_range,
+                    "status": "analysis_failed",
+                    "error": format!("{}", e),
+                    "file_metadata": file_metadata,
+                    "timestamp": chrono::Utc::now().to_rfc3339()
+                }),
+                analyzed: None,
+            };
+        }
+    };
+    debug!(
+        "Analysis completed for {}, response length: {}",
+        file_path,
+        analysis_result.len()
+    );
+
+    // Only a whole-file analysis can show that a bug is gone
+    let analyzed = lines.is_none().then(|| analyzed_file.clone());
+
+    // Check if bug was found
+    if !analysis_result.contains("BUG_FOUND: yes") {
+        println!("   ✅ No critical issues found");
+        debug!("File analysis clean: {}", file_path);
+
+        return FileAnalysis {
+            result: serde_json::json!({
+                "file_path": relative_file_path,
+                "lines": line_range,
+                "status": "clean",
+                "file_metadata": file_metadata,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+            analyzed,
         };
+    }
 
-        // Analyze with DeepSeek
-        if let Some(deepseek_client) = &state.deepseek_client {
-            let analysis_prompt = format!(
-                "Analyze this Rust code for critical bugs, security vulnerabilities, and potential issues:\n\n\
-                 File: {}\n\
-                 Lines of code: {}\n\n\
-                 ```rust\n{}\n```\n\n\
-                 Focus on:\n\
-                 - Security vulnerabilities (buffer overflows, injection attacks, etc.)\n\
-                 - Memory safety issues\n\
-                 - Logic errors that could cause financial losses\n\
-                 - Performance bottlenecks\n\
-                 - Error handling problems\n\
-                 - Precision loss in financial calculations (especially with f64 conversions)\n\
-                 - Type safety issues\n\n\
-                 If you find critical issues, respond with:\n\
-                 BUG_FOUND: yes\n\
-                 SEVERITY: [CRITICAL|HIGH|MEDIUM|LOW]\n\
-                 DESCRIPTION: [detailed description]\n\
-                 CODE_SAMPLE: [relevant code snippet with function/line context]\n\
-                 FIX_SUGGESTION: [how to fix it]\n\
-                 AFFECTED_FUNCTIONS: [list of function names affected]\n\n\
-                 If no critical issues found, respond with:\n\
-                 BUG_FOUND: no\n\
-                 ANALYSIS: [brief analysis summary]",
-                file_path, content.lines().count(), content
-            );
+    // Extract bug details with enhanced parsing
+    let finding = BugFinding {
+        severity: extract_field(&analysis_result, "SEVERITY").unwrap_or("MEDIUM".to_string()),
+        description: extract_field(&analysis_result, "DESCRIPTION")
+            .unwrap_or("Bug detected by automated analysis".to_string()),
+        code_sample: extract_field(&analysis_result, "CODE_SAMPLE")
+            .unwrap_or("See file content".to_string()),
+        fix_suggestion: extract_field(&analysis_result, "FIX_SUGGESTION")
+            .unwrap_or("Manual review required".to_string()),
+        affected_functions: extract_field(&analysis_result, "AFFECTED_FUNCTIONS")
+            .unwrap_or("Unknown".to_string()),
+    };
+    let fingerprint = bug_store::compute_fingerprint(file_path, &finding.affected_functions);
+    let site = FindingSite {
+        file_path: file_path.to_string(),
+        affected_functions: finding.affected_functions.clone(),
+        line_number: find_code_in_file(&file_content, &finding.code_sample).map(|line| line as u64),
+        content_hash: analyzed_file.content_hash.clone(),
+        last_modified,
+    };
+
+    // Match against stored bugs before creating a new record
+    let known_bug = match tracker.match_finding(&fingerprint, &site).await {
+        Ok(FindingMatch::New) => None,
+        Ok(FindingMatch::Persisting { bug_id, bug_file }) => {
+            println!("   🐛 Known bug still present: {}", bug_id);
+            Some(("persisting", bug_id, bug_file))
+        }
+        Ok(FindingMatch::Regressed { bug_id, bug_file }) => {
+            println!("   🔁 Previously fixed bug regressed: {}", bug_id);
+            Some(("regressed", bug_id, bug_file))
+        }
+        Err(e) => {
+            warn!("Failed to match finding against stored bugs: {}", e);
+            None
+        }
+    };
+
+    if let Some((lifecycle, bug_id, bug_file)) = known_bug {
+        return FileAnalysis {
+            result: serde_json::json!({
+                "file_path": relative_file_path,
+                "lines": line_range,
+                "status": "bug_found",
+                "lifecycle": lifecycle,
+                "bug_id": bug_id,
+                "severity": finding.severity,
+                "affected_functions": finding.affected_functions,
+                "bug_file": bug_file.file_name().map(|n| n.to_string_lossy().to_string()),
+                "file_metadata": file_metadata,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+            analyzed,
+        };
+    }
 
-            match deepseek_client.analyze_code(&analysis_prompt).await {
-                Ok(analysis_result) => {
-                    debug!(
-                        "Analysis completed for {}, response length: {}",
-                        file_path,
-                        analysis_result.len()
-                    );
+    println!("   🐛 Bug detected! Storing analysis...");
+
+    // Generate enhanced bug ID with more context
+    let file_name = std::path::Path::new(file_path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown");
+    let bug_id = format!(
+        "AUTO_BUG_{}_{}_{}",
+        file_name,
+        &fingerprint[..8],
+        chrono::Utc::now().format("%H%M%S")
+    );
 
-                    // Check if bug was found
-                    if analysis_result.contains("BUG_FOUND: yes") {
-                        bugs_found += 1;
-                        println!("   🐛 Bug detected! Storing analysis...");
-
-                        // Extract bug details with enhanced parsing
-                        let severity = extract_field(&analysis_result, "SEVERITY")
-                            .unwrap_or("MEDIUM".to_string());
-                        let description = extract_field(&analysis_result, "DESCRIPTION")
-                            .unwrap_or("Bug detected by automated analysis".to_string());
-                        let code_sample = extract_field(&analysis_result, "CODE_SAMPLE")
-                            .unwrap_or("See file content".to_string());
-                        let fix_suggestion = extract_field(&analysis_result, "FIX_SUGGESTION")
-                            .unwrap_or("Manual review required".to_string());
-                        let affected_functions = extract_field(&analysis_result, "AFFECTED_FUNCTIONS")
-                            .unwrap_or("Unknown".to_string());
-
-                        // Generate enhanced bug ID with more context
-                        let file_name = std::path::Path::new(file_path)
-                            .file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("unknown");
-                        let bug_id = format!(
-                            "AUTO_BUG_{}_{}_{}",
-                            file_name,
-                            bugs_found,
-                            chrono::Utc::now().format("%H%M%S")
-                        );
-
-                        // Store bug using enhanced internal function
-                        match store_bug_internal(
-                            &bug_id,
-                            &severity,
-                            &description,
-                            Some(file_name.to_string()),
-                            &code_sample,
-                            &fix_suggestion,
-                            Some(file_path.to_string()),
-                        )
-                        .await
-                        {
-                            Ok(filename) => {
-                                println!("   ✅ Bug stored: {}", filename);
-                                info!("Bug {} stored successfully: {}", bug_id, filename);
-                                
-                                analysis_results.push(serde_json::json!({
-                                    "file_path": file_path,
-                                    "status": "bug_found",
-                                    "bug_id": bug_id,
-                                    "severity": severity,
-                                    "affected_functions": affected_functions,
-                                    "bug_file": filename,
-                                    "file_metadata": file_metadata,
-                                    "timestamp": chrono::Utc::now().to_rfc3339()
-                                }));
-                            }
-                            Err(e) => {
-                                println!("   ❌ Failed to store bug: {}", e);
-                                error!("Failed to store bug {}: {}", bug_id, e);
-                                
-                                analysis_results.push(serde_json::json!({
-                                    "file_path": file_path,
-                                    "status": "bug_found_but_storage_failed",
-                                    "bug_id": bug_id,
-                                    "storage_error": format!("{}", e),
-                                    "timestamp": chrono::Utc::now().to_rfc3339()
-                                }));
-                            }
-                        }
-                    } else {
-                        println!("   ✅ No critical issues found");
-                        debug!("File analysis clean: {}", file_path);
-                        
-                        analysis_results.push(serde_json::json!({
-                            "file_path": file_path,
-                            "status": "clean",
-                            "file_metadata": file_metadata,
-                            "timestamp": chrono::Utc::now().to_rfc3339()
-                        }));
-                    }
-                }
-                Err(e) => {
-                    println!("   ❌ Analysis failed: {}", e);
-                    error!("DeepSeek analysis failed for {}: {}", file_path, e);
-                    
-                    analysis_results.push(serde_json::json!({
-                        "file_path": file_path,
-                        "status": "analysis_failed",
-                        "error": format!("{}", e),
-                        "file_metadata": file_metadata,
-                        "timestamp": chrono::Utc::now().to_rfc3339()
-                    }));
-                }
-            }
-        } else {
-            println!("   ⚠️ DeepSeek client not available");
-            warn!("Skipping analysis - DeepSeek client not initialized");
-            
-            analysis_results.push(serde_json::json!({
-                "file_path": file_path,
-                "status": "skipped_no_client",
+    // Store bug using enhanced internal function
+    let result = match store_bug_internal(
+        &bug_id,
+        Some(file_name.to_string()),
+        &finding,
+        Some(file_path.to_string()),
+        &fingerprint,
+    )
+    .await
+    {
+        Ok(filename) => {
+            println!("   ✅ Bug stored: {}", filename);
+            info!("Bug {} stored successfully: {}", bug_id, filename);
+            tracker.record_new(&bug_id);
+
+            serde_json::json!({
+                "file_path": relative_file_path,
+                "lines": line_range,
+                "status": "bug_found",
+                "lifecycle": "new",
+                "bug_id": bug_id,
+                "severity": finding.severity,
+                "affected_functions": finding.affected_functions,
+                "bug_file": std::path::Path::new(&filename)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string()),
                 "file_metadata": file_metadata,
                 "timestamp": chrono::Utc::now().to_rfc3339()
-            }));
+            })
+        }
+        Err(e) => {
+            println!("   ❌ Failed to store bug: {}", e);
+            error!("Failed to store bug {}: {}", bug_id, e);
+
+            serde_json::json!({
+                "file_path": relative_file_path,
+                "lines": line_range,
+                "status": "bug_found_but_storage_failed",
+                "bug_id": bug_id,
+                "storage_error": format!("{}", e),
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            })
         }
+    };
 
-        // Add small delay to avoid rate limiting
-        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-    }
+    FileAnalysis { result, analyzed }
+}
+
+/// Write the summary of a finished run along with its HTML report and adapter risk scores
+async fn write_run_summary(
+    run_id: &str,
+    total_files: usize,
+    analysis_results: &[serde_json::Value],
+    lifecycle: &bug_store::LifecycleSummary,
+) -> Result<std::path::PathBuf> {
+    let bugs_dir = config::Config::bugs_directory_path();
+    let adapters_path = config::Config::core_adapters_directory_abs();
+    let files_analyzed = analysis_results
+        .iter()
+        .filter(|r| r["status"] != "error" && r["status"] != "cancelled")
+        .count();
+    let bugs_found = analysis_results
+        .iter()
+        .filter(|r| {
+            r["status"]
+                .as_str()
+                .is_some_and(|s| s.starts_with("bug_found"))
+        })
+        .count();
 
     // Store comprehensive analysis summary
     let summary_report = serde_json::json!({
         "analysis_summary": {
-            "total_files_discovered": rust_files.len(),
+            "run_id": run_id,
+            "total_files_discovered": total_files,
             "files_analyzed": files_analyzed,
             "bugs_found": bugs_found,
+            "lifecycle": lifecycle.to_json(),
             "analysis_timestamp": chrono::Utc::now().to_rfc3339(),
             "workspace_info": {
-                "adapters_path": adapters_path.display().to_string(),
+                "adapters_path": repo_paths::to_repo_relative(&adapters_path.to_string_lossy()),
+                "adapter_roots": Config::adapter_roots()
+                    .iter()
+                    .map(|root| repo_paths::to_repo_relative(&root.to_string_lossy()))
+                    .collect::<Vec<_>>(),
+                "path_base": repo_paths::PATH_BASE,
                 "repository": "nautilus_trader",
                 "branch": get_git_branch().await.unwrap_or_else(|| "unknown".to_string())
             }
@@ -491,45 +682,66 @@ async fn analyze_adapter_files_for_bugs(state: &UnifiedServerState) -> Result<()
     });
 
     // Store summary report
-    let bugs_dir = config::Config::bugs_directory_path();
-    let summary_filename = bugs_dir.join(format!(
-        "analysis_summary_{}.json",
-        chrono::Utc::now().format("%Y%m%d_%H%M%S")
-    ));
-    
-    if let Err(e) = tokio::fs::write(&summary_filename, serde_json::to_string_pretty(&summary_report)?).await {
-        error!("Failed to write analysis summary: {}", e);
-    } else {
-        println!("📊 Analysis summary saved to: {}", summary_filename.display());
-        info!("Analysis summary saved: {}", summary_filename.display());
+    let summary_filename = bugs_dir.join(format!("analysis_summary_{}.json", run_id));
+    tokio::fs::write(
+        &summary_filename,
+        serde_json::to_string_pretty(&summary_report)?,
+    )
+    .await?;
+    println!(
+        "📊 Analysis summary saved to: {}",
+        summary_filename.display()
+    );
+    info!("Analysis summary saved: {}", summary_filename.display());
+
+    match html_report::generate_report(&bugs_dir, &summary_filename.to_string_lossy(), None, None)
+        .await
+    {
+        Ok(report) => println!("📄 HTML report saved to: {}", report.display()),
+        Err(e) => error!("Failed to write HTML report: {}", e),
+    }
+
+    match risk::generate(&bugs_dir, run_id).await {
+        Ok(report) => println!("⚠️ Adapter risk scores saved for run {}", report.run_id),
+        Err(e) => error!("Failed to compute adapter risk: {}", e),
     }
 
     println!("\n📊 Bug analysis completed:");
     println!("   📄 Files analyzed: {}", files_analyzed);
     println!("   🐛 Bugs found: {}", bugs_found);
+    println!(
+        "   🔄 Lifecycle: {} new, {} fixed, {} regressed, {} persisting",
+        lifecycle.new.len(),
+        lifecycle.fixed.len(),
+        lifecycle.regressed.len(),
+        lifecycle.persisting.len()
+    );
     println!("   📋 Summary report: {}", summary_filename.display());
 
     // Print file-by-file results
     if bugs_found > 0 {
         println!("\n🔍 Files with bugs detected:");
-        for result in &analysis_results {
+        for result in analysis_results {
             if result["status"] == "bug_found" {
                 println!(
                     "   • {} (Bug ID: {}, Severity: {})",
-                    result["file_path"],
-                    result["bug_id"],
-                    result["severity"]
+                    result["file_path"], result["bug_id"], result["severity"]
                 );
             }
         }
     }
 
     info!(
-        "Bug analysis workflow completed: {} files analyzed, {} bugs found",
-        files_analyzed, bugs_found
+        "Bug analysis workflow completed: {} files analyzed, {} bugs found ({} new, {} fixed, {} regressed, {} persisting)",
+        files_analyzed,
+        bugs_found,
+        lifecycle.new.len(),
+        lifecycle.fixed.len(),
+        lifecycle.regressed.len(),
+        lifecycle.persisting.len()
     );
 
-    Ok(())
+    Ok(summary_filename)
 }
 
 // Helper function to extract fields from analysis response
@@ -546,16 +758,30 @@ fn extract_field(text: &str, field_name: &str) -> Option<String> {
     None
 }
 
+// Bug details extracted from an analysis response
+struct BugFinding {
+    severity: String,
+    description: String,
+    code_sample: String,
+    fix_suggestion: String,
+    affected_functions: String,
+}
+
 // Internal function to store bugs with enhanced file location tracking
 async fn store_bug_internal(
     bug_id: &str,
-    severity: &str,
-    description: &str,
     adapter_name: Option<String>,
-    code_sample: &str,
-    fix_suggestion: &str,
+    finding: &BugFinding,
     file_path: Option<String>,
+    fingerprint: &str,
 ) -> Result<String> {
+    let BugFinding {
+        severity,
+        description,
+        code_sample,
+        fix_suggestion,
+        affected_functions,
+    } = finding;
     let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
     let adapter_suffix = adapter_name
         .as_ref()
@@ -567,38 +793,55 @@ async fn store_bug_internal(
     // Enhanced file location information
     let mut file_details = serde_json::Map::new();
     if let Some(path) = &file_path {
-        file_details.insert("absolute_path".to_string(), serde_json::Value::String(path.clone()));
-        
-        // Extract relative path from workspace root
-        if let Ok(workspace_root) = std::env::current_dir() {
-            if let Ok(relative_path) = std::path::Path::new(path).strip_prefix(&workspace_root) {
-                file_details.insert("relative_path".to_string(), 
-                    serde_json::Value::String(relative_path.display().to_string()));
-            }
-        }
-        
+        // Store paths relative to the nautilus_trader repo root so records are portable
+        let relative_path = repo_paths::to_repo_relative(path);
+        file_details.insert(
+            "relative_path".to_string(),
+            serde_json::Value::String(relative_path.clone()),
+        );
+        file_details.insert(
+            "path_base".to_string(),
+            serde_json::Value::String(repo_paths::PATH_BASE.to_string()),
+        );
+
         // Extract filename and directory information
-        let path_obj = std::path::Path::new(path);
+        let path_obj = std::path::Path::new(&relative_path);
         if let Some(filename) = path_obj.file_name() {
-            file_details.insert("filename".to_string(), 
-                serde_json::Value::String(filename.to_string_lossy().to_string()));
+            file_details.insert(
+                "filename".to_string(),
+                serde_json::Value::String(filename.to_string_lossy().to_string()),
+            );
         }
         if let Some(parent) = path_obj.parent() {
-            file_details.insert("directory".to_string(), 
-                serde_json::Value::String(parent.display().to_string()));
+            file_details.insert(
+                "directory".to_string(),
+                serde_json::Value::String(parent.display().to_string()),
+            );
         }
-        
+
         // Check if file exists and get metadata
         if let Ok(metadata) = tokio::fs::metadata(path).await {
-            file_details.insert("file_size_bytes".to_string(), 
-                serde_json::Value::Number(serde_json::Number::from(metadata.len())));
+            file_details.insert(
+                "file_size_bytes".to_string(),
+                serde_json::Value::Number(serde_json::Number::from(metadata.len())),
+            );
             if let Ok(modified) = metadata.modified() {
                 if let Ok(duration) = modified.duration_since(std::time::UNIX_EPOCH) {
-                    file_details.insert("last_modified_timestamp".to_string(), 
-                        serde_json::Value::Number(serde_json::Number::from(duration.as_secs())));
+                    file_details.insert(
+                        "last_modified_timestamp".to_string(),
+                        serde_json::Value::Number(serde_json::Number::from(duration.as_secs())),
+                    );
                 }
             }
         }
+
+        // Record the source hash so later runs can tell whether the file changed
+        if let Ok(file_content) = tokio::fs::read_to_string(path).await {
+            file_details.insert(
+                "source_sha256".to_string(),
+                serde_json::Value::String(bug_store::content_hash(&file_content)),
+            );
+        }
     }
 
     // Try to extract line numbers from code sample if available
@@ -608,15 +851,22 @@ async fn store_bug_internal(
             if let Ok(file_content) = tokio::fs::read_to_string(path).await {
                 // Find the approximate line number where the code sample appears
                 if let Some(line_number) = find_code_in_file(&file_content, code_sample) {
-                    location_details.insert("approximate_line_number".to_string(), 
-                        serde_json::Value::Number(serde_json::Number::from(line_number)));
-                    location_details.insert("context_extraction_method".to_string(), 
-                        serde_json::Value::String("fuzzy_string_match".to_string()));
+                    location_details.insert(
+                        "approximate_line_number".to_string(),
+                        serde_json::Value::Number(serde_json::Number::from(line_number)),
+                    );
+                    location_details.insert(
+                        "context_extraction_method".to_string(),
+                        serde_json::Value::String("fuzzy_string_match".to_string()),
+                    );
                 }
             }
         }
     }
 
+    let commit_hash = get_git_commit_hash()
+        .await
+        .unwrap_or_else(|| "unknown".to_string());
     let bug_data = serde_json::json!({
         "bug_id": bug_id,
         "severity": severity,
@@ -624,7 +874,15 @@ async fn store_bug_internal(
         "adapter_name": adapter_name,
         "code_sample": code_sample,
         "fix_suggestion": fix_suggestion,
+        "affected_functions": affected_functions,
         "timestamp": timestamp,
+        "status": bug_store::BugStatus::Open,
+        "fingerprint": fingerprint,
+        "status_history": [{
+            "status": bug_store::BugStatus::Open,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "commit_hash": commit_hash
+        }],
         "analysis_context": "Automated detection via Nautilus Trader Rig",
         "file_location": {
             "details": file_details,
@@ -633,7 +891,7 @@ async fn store_bug_internal(
         "workspace_info": {
             "repository": "nautilus_trader",
             "branch": get_git_branch().await.unwrap_or_else(|| "unknown".to_string()),
-            "commit_hash": get_git_commit_hash().await.unwrap_or_else(|| "unknown".to_string())
+            "commit_hash": commit_hash
         }
     });
 
@@ -654,11 +912,11 @@ fn find_code_in_file(file_content: &str, code_sample: &str) -> Option<usize> {
         .filter(|line| !line.is_empty() && !line.starts_with("//"))
         .collect::<Vec<_>>()
         .join(" ");
-    
+
     if cleaned_sample.is_empty() {
         return None;
     }
-    
+
     // Look for the first substantial line from the code sample
     for (line_num, line) in file_content.lines().enumerate() {
         let cleaned_line = line.trim();
@@ -666,7 +924,7 @@ fn find_code_in_file(file_content: &str, code_sample: &str) -> Option<usize> {
             return Some(line_num + 1); // 1-indexed line numbers
         }
     }
-    
+
     None
 }
 
@@ -677,7 +935,7 @@ async fn get_git_branch() -> Option<String> {
         .output()
         .await
         .ok()?;
-    
+
     if output.status.success() {
         String::from_utf8(output.stdout)
             .ok()
@@ -694,7 +952,7 @@ async fn get_git_commit_hash() -> Option<String> {
         .output()
         .await
         .ok()?;
-    
+
     if output.status.success() {
         String::from_utf8(output.stdout)
             .ok()
@@ -714,7 +972,7 @@ async fn run_rig_sqlite_application() -> Result<()> {
 
     // Initialize state
     trace!("Beginning unified server state initialization");
-    let state = UnifiedServerState::new().await?;
+    let state = Arc::new(UnifiedServerState::new().await?);
     info!("✅ Server state initialization completed");
 
     // Test DeepSeek client functionality
@@ -728,88 +986,246 @@ async fn run_rig_sqlite_application() -> Result<()> {
     println!("\n✅ FastEmbed + DeepSeek integration test completed successfully!");
     info!("🎉 All integration tests passed successfully");
 
-    // Run automated bug analysis on discovered files
-    debug!("Starting automated bug analysis on adapter files");
-    analyze_adapter_files_for_bugs(&state).await?;
+    // Probe dependencies in the background instead of pinging the LLM
+    debug!("Starting health probes every {:?}", health::PROBE_INTERVAL);
+    tokio::spawn(health::run_monitor());
 
-    // Keep running for monitoring
-    let mut interval = tokio::time::interval(Duration::from_secs(30));
+    // Start the analysis worker on the durable job queue
+    let queue = job_queue::JobQueue::shared().await?;
+    debug!(
+        "Starting analysis worker on {}",
+        job_queue::JobQueue::database_path().display()
+    );
+    tokio::spawn(async move {
+        if let Err(e) = job_queue::run_worker(state).await {
+            error!("❌ Analysis worker failed: {}", e);
+        }
+    });
 
-    println!("\n🔄 Running periodic monitoring (Ctrl+C to stop)...");
-    info!("Starting periodic health monitoring with 30-second intervals");
-    debug!("Monitoring will check vector store and AI client health");
+    // Hand analysis over to the cron scheduler
+    println!("\n📅 Starting scheduled analysis jobs (Ctrl+C to stop)...");
+    debug!("Starting scheduler for full sweeps, diff sweeps and re-verification");
+    scheduler::run(queue).await
+}
 
-    loop {
-        interval.tick().await;
-        trace!("Executing periodic health check");
+// Command to diff two analysis runs: report diff [<base> <head>] [--format json|markdown|text]
+async fn run_diff_command(args: &[String]) -> Result<()> {
+    let mut format = "text".to_string();
+    let mut runs = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--format" {
+            format = iter
+                .next()
+                .cloned()
+                .ok_or_else(|| cli::usage_error("--format requires a value"))?;
+        } else {
+            runs.push(arg.clone());
+        }
+    }
 
-        println!("\n⏰ Periodic check - FastEmbed and DeepSeek systems operational");
+    let (base, head) = match runs.as_slice() {
+        [] => ("previous".to_string(), "latest".to_string()),
+        [base, head] => (base.clone(), head.clone()),
+        _ => {
+            return Err(cli::usage_error(
+                "Usage: report diff [<base> <head>] [--format json|markdown|text]",
+            ))
+        }
+    };
 
-        // Test vector search
-        if let Some(vector_store) = &state.vector_store {
-            let query = "security vulnerability";
-            trace!("Testing vector store with query: '{}'", query);
-            match vector_store.similarity_search(query, 1).await {
-                Ok(results) => {
-                    println!(
-                        "   🔍 Vector search for '{}': {} results",
-                        query,
-                        results.len()
-                    );
-                    debug!(
-                        "Vector store health check successful, {} results returned",
-                        results.len()
-                    );
-                }
-                Err(e) => {
-                    println!("   ❌ Vector search error: {}", e);
-                    error!("Vector store health check failed: {}", e);
-                }
+    let diff = run_diff::diff_runs(&Config::bugs_directory_path(), &base, &head).await?;
+    println!("{}", run_diff::render(&diff, &format)?);
+    Ok(())
+}
+
+// Command to rewrite stored bug and summary records to repository-relative paths
+async fn migrate_paths_command() -> Result<()> {
+    let bugs_dir = Config::bugs_directory_path();
+    match repo_paths::repo_root() {
+        Some(root) => println!("📂 nautilus_trader repository root: {}", root.display()),
+        None => {
+            println!("⚠️ Repository root not detected; paths are cut at their 'crates/' segment")
+        }
+    }
+    let migrated = repo_paths::migrate_bugs_directory(&bugs_dir).await?;
+    println!("✅ Migrated {} records in {}", migrated, bugs_dir.display());
+    Ok(())
+}
+
+// Command to export stored bugs as SARIF: report sarif [--output <file>] [--include-fixed]
+async fn export_sarif_command(args: &[String]) -> Result<()> {
+    let mut output = None;
+    let mut include_fixed = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--output" | "-o" => {
+                output = Some(
+                    iter.next()
+                        .cloned()
+                        .ok_or_else(|| cli::usage_error("--output requires a value"))?,
+                )
+            }
+            "--include-fixed" => include_fixed = true,
+            other => {
+                return Err(cli::usage_error(format!(
+                    "Unknown report sarif argument: {}",
+                    other
+                )))
             }
-        } else {
-            debug!("Vector store not available during health check");
         }
+    }
 
-        // Test DeepSeek client
-        if let Some(client) = &state.deepseek_client {
-            trace!("Testing DeepSeek client health with simple prompt");
-            match client
-                .prompt("Respond with just 'OK' to confirm you're working.")
-                .await
-            {
-                Ok(response) => {
-                    println!("   🤖 DeepSeek client: {}", response.trim());
-                    debug!(
-                        "DeepSeek health check successful, response: '{}'",
-                        response.trim()
-                    );
-                }
-                Err(e) => {
-                    println!("   ❌ DeepSeek client error: {}", e);
-                    error!("DeepSeek health check failed: {}", e);
-                }
+    let log = sarif::export_bugs(&Config::bugs_directory_path(), include_fixed).await?;
+    let rendered = serde_json::to_string_pretty(&log)?;
+    match output {
+        Some(path) => {
+            tokio::fs::write(&path, rendered).await?;
+            let count = log["runs"][0]["results"]
+                .as_array()
+                .map(|r| r.len())
+                .unwrap_or(0);
+            println!("✅ Exported {} results to {}", count, path);
+        }
+        None => println!("{}", rendered),
+    }
+    Ok(())
+}
+
+// Command to render a run as HTML: report html [run] [--output <file>] [--source-base-url <url>]
+async fn html_report_command(args: &[String]) -> Result<()> {
+    let mut run = "latest".to_string();
+    let mut output = None;
+    let mut source_base_url = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--output" | "-o" => {
+                output =
+                    Some(std::path::PathBuf::from(iter.next().ok_or_else(|| {
+                        cli::usage_error("--output requires a value")
+                    })?))
             }
-        } else {
-            debug!("DeepSeek client not available during health check");
+            "--source-base-url" => {
+                source_base_url = Some(
+                    iter.next()
+                        .cloned()
+                        .ok_or_else(|| cli::usage_error("--source-base-url requires a value"))?,
+                )
+            }
+            other => run = other.to_string(),
         }
     }
+
+    let report = html_report::generate_report(
+        &Config::bugs_directory_path(),
+        &run,
+        output,
+        source_base_url.as_deref(),
+    )
+    .await?;
+    println!("✅ HTML report written to {}", report.display());
+    Ok(())
+}
+
+// Command to print per-adapter risk scores: report risk [run] [--json]
+async fn risk_report_command(args: &[String]) -> Result<()> {
+    let mut run = "latest".to_string();
+    let mut as_json = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => as_json = true,
+            other => run = other.to_string(),
+        }
+    }
+
+    let report = risk::load_or_compute(&Config::bugs_directory_path(), &run).await?;
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.to_table());
+    }
+    Ok(())
+}
+
+// Command to serve MCP, the dashboard, health endpoints and queued jobs, without sweeps: serve
+async fn serve_command() -> Result<cli::Outcome> {
+    init_dev_logging()?;
+    run_mcp_server().await?;
+    tokio::spawn(health::run_monitor());
+
+    // Jobs queued over MCP need a worker in this process
+    let state = Arc::new(UnifiedServerState::new().await?);
+    if state.deepseek_client.is_some() {
+        tokio::spawn(async move {
+            if let Err(e) = job_queue::run_worker(state).await {
+                error!("❌ Analysis worker failed: {}", e);
+            }
+        });
+    } else {
+        warn!(
+            "⚠️ DeepSeek client unavailable; queued analysis jobs wait until {} is set",
+            Config::settings().provider.api_key_env
+        );
+    }
+    println!(
+        "🌐 Serving MCP, dashboard and health checks on http://{} (Ctrl+C to stop)",
+        Config::settings().mcp.bind
+    );
+    tokio::signal::ctrl_c().await?;
+    info!("✅ MCP server stopped");
+    Ok(cli::Outcome::Success)
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load environment variables from .env file FIRST (before any client initialization)
     // Try absolute path first, then relative paths
-    let env_paths = [
-        Config::manifest_dir().join(".env"),
-        std::path::Path::new(".env").to_path_buf(),
-        std::path::Path::new("nautilus-trader-rig/.env").to_path_buf(),
-    ];
+    if let Some(env_path) = Config::env_file_in_use() {
+        dotenvy::from_path(env_path).ok();
+    }
 
-    for env_path in &env_paths {
-        if env_path.exists() {
-            dotenvy::from_path(env_path).ok();
-            break;
+    // Load layered settings; global flags may appear anywhere on the command line
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let settings = match config::CliOverrides::extract(&mut args).and_then(|cli| Config::init(&cli))
+    {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("❌ {:#}", e);
+            std::process::exit(cli::EXIT_USAGE);
+        }
+    };
+
+    // Handle one-shot commands before starting the long-running services
+    let done = |result: Result<()>| result.map(|_| cli::Outcome::Success);
+    let outcome = match args.first().map(String::as_str) {
+        None | Some("run") => None,
+        Some("serve") => Some(serve_command().await),
+        Some("analyze") => Some(cli::analyze_command(&args[1..]).await),
+        Some("bugs") => Some(cli::bugs_command(&args[1..]).await),
+        Some("search") => Some(cli::search_command(&args[1..]).await),
+        Some("patterns") => Some(cli::patterns_command(&args[1..]).await),
+        Some("doctor") => Some(cli::doctor_command(&args[1..]).await),
+        Some("models") => Some(cli::models_command(&args[1..]).await),
+        Some("report") => Some(cli::report_command(&args[1..]).await),
+        Some("eval") => Some(cli::eval_command(&args[1..]).await),
+        Some("index") => Some(cli::index_command(&args[1..]).await),
+        Some("migrate-paths") => Some(done(migrate_paths_command().await)),
+        Some("help" | "--help" | "-h") => {
+            print!("{}", cli::USAGE);
+            return Ok(());
         }
+        Some(other) => Some(Err(cli::usage_error(format!("Unknown command: {}", other)))),
+    };
+    if let Some(outcome) = outcome {
+        if let Err(e) = &outcome {
+            eprintln!("❌ {:#}", e);
+            if e.downcast_ref::<cli::UsageError>().is_some() {
+                eprint!("\n{}", cli::USAGE);
+            }
+        }
+        std::process::exit(cli::exit_code(&outcome));
     }
 
     // Initialize centralized logging system
@@ -818,12 +1234,21 @@ async fn main() -> Result<()> {
     // Log environment file status
     debug!("Environment file path: {}", Config::ENV_FILE_PATH);
     debug!("Environment file exists: {}", Config::env_file_exists());
-    if let Ok(api_key) = std::env::var("DEEPSEEK_API_KEY") {
-        debug!("DEEPSEEK_API_KEY loaded (length: {} chars)", api_key.len());
+    let api_key_env = &settings.provider.api_key_env;
+    if let Ok(api_key) = std::env::var(api_key_env) {
+        debug!("{} loaded (length: {} chars)", api_key_env, api_key.len());
     } else {
-        warn!("DEEPSEEK_API_KEY not found in environment");
+        warn!("{} not found in environment", api_key_env);
     }
 
+    info!(
+        "⚙️ Configuration: {} adapter root(s), bugs in {}, model {}, MCP on {}",
+        settings.paths.roots.len(),
+        settings.paths.bugs_dir.display(),
+        settings.provider.model,
+        settings.mcp.bind
+    );
+
     info!("🚀 Starting Nautilus Trader Rig with MCP server...");
     debug!("Application entry point - initializing concurrent services");
 
@@ -1,15 +1,21 @@
 //! MCP (Model Context Protocol) Server for Nautilus Trader Rig
-//! 
+//!
 //! This module implements an MCP server using rmcp with DeepSeek and FastEmbed integration
 //! for trading system analysis and vector similarity search.
 
-use std::sync::Arc;
+use crate::bug_index;
+use crate::bug_store::{self, BugFilter};
+use crate::code_index;
+use crate::config::Config;
+use crate::dashboard;
+use crate::health;
+use crate::index_snapshot::{self, ImportMode};
+use crate::logging::{log_directory_op, log_file_processing, log_mcp_op};
+use rmcp::ServiceExt;
 use std::future::Future;
 use std::path::Path;
+use std::sync::Arc;
 use tokio::fs as async_fs;
-use rmcp::ServiceExt;
-use crate::config::Config;
-use crate::logging::{log_file_processing, log_directory_op, log_mcp_op};
 
 use rig::{
     client::{CompletionClient, ProviderClient},
@@ -17,12 +23,11 @@ use rig::{
     providers::deepseek,
 };
 use rmcp::{
-    RoleServer, ServerHandler,
     handler::server::{router::tool::ToolRouter, tool::Parameters},
     model::*,
     schemars,
     service::RequestContext,
-    tool, tool_handler, tool_router,
+    tool, tool_handler, tool_router, RoleServer, ServerHandler,
 };
 use serde_json::json;
 use tokio::sync::Mutex;
@@ -33,17 +38,40 @@ use hyper_util::{
     service::TowerToHyperService,
 };
 use rmcp::transport::streamable_http_server::{
-    StreamableHttpService, session::local::LocalSessionManager,
+    session::local::LocalSessionManager, StreamableHttpService,
 };
 
-use crate::vector_store::VectorStoreManager;
 use crate::deepseek::DeepSeekClient;
+use crate::job_queue;
+use crate::pattern_catalog::{self, PatternChanges, PatternEntry};
+use crate::pattern_learning;
+use crate::repo_paths;
+use crate::risk;
+use crate::run_diff;
+use crate::sarif;
+use crate::scheduler;
+use crate::themes::{self, ThemeOptions};
+use crate::vector_store::{self, PatternFilter};
 use anyhow::Result;
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct SimilaritySearchRequest {
     pub query: String,
     pub limit: Option<usize>,
+    /// Pattern category (case-insensitive), e.g. WEBSOCKET
+    pub category: Option<String>,
+    /// Lowest severity to include (patterns use 1-10)
+    pub min_severity: Option<u8>,
+    /// Adapter name (case-insensitive substring); built-in patterns have none
+    pub adapter: Option<String>,
+    /// Pattern status, e.g. active
+    pub status: Option<String>,
+    /// First creation day to include, YYYY-MM-DD or RFC 3339
+    pub since: Option<String>,
+    /// Last creation day to include, YYYY-MM-DD or RFC 3339
+    pub until: Option<String>,
+    /// Minimum cosine similarity of every returned hit, keyword matches included
+    pub min_score: Option<f64>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -56,6 +84,8 @@ pub struct CodeAnalysisRequest {
 pub struct BugConfirmationRequest {
     pub bug_description: String,
     pub code_sample: String,
+    /// Stored bug to mark confirmed when the analysis confirms it is critical
+    pub bug_id: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -80,6 +110,8 @@ pub struct BugStoreRequest {
 pub struct BugListRequest {
     pub severity_filter: Option<String>,
     pub adapter_filter: Option<String>,
+    /// Lifecycle status filter: open, fixed or regressed
+    pub status_filter: Option<String>,
     pub include_file_details: Option<bool>,
 }
 
@@ -109,9 +141,175 @@ pub struct AdapterFileRequest {
     pub adapter_name: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct RunDiffRequest {
+    /// Base run: run ID, summary file path, or "previous"
+    pub base: Option<String>,
+    /// Head run: run ID, summary file path, or "latest"
+    pub head: Option<String>,
+    /// Output format: json, markdown or text (default: json)
+    pub format: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct AdapterRiskRequest {
+    /// Run to score: run ID, summary file path, or "latest" (default)
+    pub run: Option<String>,
+    /// Output format: json or text (default: text)
+    pub format: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ScheduledJobsRequest {
+    /// Only show runs of this job: full_sweep, diff_sweep or reverify
+    pub kind: Option<String>,
+    /// Maximum number of runs to return, newest first (default: 20)
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct EnqueueAnalysisRequest {
+    /// Adapter source file, relative to the nautilus_trader repository root
+    pub file_path: String,
+    /// First line of the range to analyze (1-based, optional)
+    pub start_line: Option<usize>,
+    /// Last line of the range to analyze (inclusive, optional)
+    pub end_line: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct AnalysisJobsRequest {
+    /// Only show jobs with this status: Pending, Running, Done, Failed or Killed
+    pub status: Option<String>,
+    /// Maximum number of jobs to return, newest first (default: 50)
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CancelAnalysisJobRequest {
+    /// ID of the job to cancel, as shown by list_analysis_jobs
+    pub job_id: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct BugSearchRequest {
+    /// What to look for, e.g. "order book sequence gap handling"
+    pub query: String,
+    /// Maximum number of bugs to return (default: embedding.search_limit)
+    pub limit: Option<usize>,
+    /// Severity label (case-insensitive), e.g. HIGH
+    pub severity: Option<String>,
+    /// Adapter name (case-insensitive substring), e.g. okx
+    pub adapter: Option<String>,
+    /// Lifecycle status: open, fixed, regressed, or active for open and regressed
+    pub status: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CodeSearchRequest {
+    /// Code or description to look for, e.g. "reconnect websocket and resubscribe"
+    pub query: String,
+    /// Only search this adapter, e.g. okx
+    pub adapter: Option<String>,
+    /// Maximum number of chunks to return (default: embedding.search_limit)
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PatternAddRequest {
+    /// Unique ID, also the catalog file name, e.g. okx_lot_size_001
+    pub id: String,
+    /// Category, e.g. VALIDATION
+    pub category: String,
+    /// 1 (cosmetic) to 10 (critical)
+    pub severity: u8,
+    pub description: String,
+    /// Short code or behaviour snippets showing the pattern
+    pub examples: Option<Vec<String>>,
+    /// CWE entries, advisories or issues describing the pattern
+    pub references: Option<Vec<String>>,
+    /// Adapter the pattern was seen in; omit for patterns that apply everywhere
+    pub adapter: Option<String>,
+    /// Lifecycle status, e.g. active
+    pub status: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PatternUpdateRequest {
+    /// ID of the pattern to change
+    pub id: String,
+    pub category: Option<String>,
+    pub severity: Option<u8>,
+    pub description: Option<String>,
+    /// Replaces the examples
+    pub examples: Option<Vec<String>>,
+    /// Replaces the references
+    pub references: Option<Vec<String>>,
+    /// Empty string clears the adapter
+    pub adapter: Option<String>,
+    /// Empty string clears the status
+    pub status: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PatternIdRequest {
+    pub id: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PatternLearnRequest {
+    /// Smallest cluster of confirmed bugs to generalize (default 2)
+    pub min_cluster_size: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct IndexExportRequest {
+    /// File to write the index to, relative to the directory of embedding.index_cache
+    pub path: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct IndexImportRequest {
+    /// Exported index file, relative to the directory of embedding.index_cache
+    pub path: String,
+    /// Drop catalog patterns and bug records missing from the file (default false: merge)
+    pub replace: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ThemeRequest {
+    /// Only cluster bugs of this adapter (case-insensitive substring)
+    pub adapter: Option<String>,
+    /// Lifecycle status: open, fixed, regressed, or active (default)
+    pub status: Option<String>,
+    /// Average cosine similarity at which bugs share a theme (default 0.7)
+    pub threshold: Option<f64>,
+    /// Smallest number of bugs reported as a theme (default 2)
+    pub min_size: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PatternReviewRequest {
+    /// ID of the proposal
+    pub id: String,
+    /// true adds the pattern to the catalog, false rejects it
+    pub accept: bool,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PatternListRequest {
+    /// Only list this category (case-insensitive)
+    pub category: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SarifExportRequest {
+    /// Include bugs already marked fixed (default: false)
+    pub include_fixed: Option<bool>,
+}
+
 #[derive(Clone)]
 pub struct NautilusMcpServer {
-    pub vector_store: Arc<Mutex<Option<VectorStoreManager>>>,
     pub deepseek_client: Arc<Mutex<Option<DeepSeekClient>>>,
     tool_router: ToolRouter<NautilusMcpServer>,
 }
@@ -126,20 +324,16 @@ impl Default for NautilusMcpServer {
 impl NautilusMcpServer {
     pub fn new() -> Self {
         Self {
-            vector_store: Arc::new(Mutex::new(None)),
             deepseek_client: Arc::new(Mutex::new(None)),
             tool_router: Self::tool_router(),
         }
     }
 
     pub async fn initialize_services(&self) -> Result<()> {
-        // Initialize vector store with FastEmbed
-        if let Ok(vector_store) = VectorStoreManager::new().await {
-            let mut vs = self.vector_store.lock().await;
-            *vs = Some(vector_store);
-            tracing::info!("✅ Vector store initialized with FastEmbed");
-        } else {
-            tracing::warn!("⚠️ Failed to initialize vector store");
+        // The pattern index is shared by all sessions; only the first one builds it
+        match vector_store::shared().await {
+            Ok(_) => tracing::info!("✅ Shared vector store ready"),
+            Err(e) => tracing::warn!("⚠️ Failed to initialize vector store: {}", e),
         }
 
         // Initialize DeepSeek client
@@ -158,38 +352,78 @@ impl NautilusMcpServer {
         RawResource::new(uri, name.to_string()).no_annotation()
     }
 
-    #[tool(description = "Search for similar bug patterns using FastEmbed vector embeddings")]
+    #[tool(
+        description = "Search bug patterns by fused FastEmbed vector and keyword rank, filtered by category, minimum severity, adapter, status, creation date and minimum score; results include per-component scores"
+    )]
     async fn similarity_search(
         &self,
-        Parameters(SimilaritySearchRequest { query, limit }): Parameters<SimilaritySearchRequest>,
+        Parameters(request): Parameters<SimilaritySearchRequest>,
     ) -> Result<CallToolResult, ErrorData> {
-        let search_limit = limit.unwrap_or(5);
-        
-        let vector_store = self.vector_store.lock().await;
-        if let Some(vs) = vector_store.as_ref() {
-            match vs.similarity_search(&query, search_limit).await {
+        let SimilaritySearchRequest {
+            query,
+            limit,
+            category,
+            min_severity,
+            adapter,
+            status,
+            since,
+            until,
+            min_score,
+        } = request;
+        let search_limit = limit.unwrap_or(Config::settings().embedding.search_limit);
+        let parse_day = |value: Option<String>| {
+            value
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| vector_store::parse_filter_date(&v))
+                .transpose()
+        };
+        let filter = match (parse_day(since), parse_day(until)) {
+            (Ok(since), Ok(until)) => PatternFilter {
+                category,
+                min_severity,
+                adapter,
+                status,
+                since,
+                until,
+                min_score,
+            },
+            (Err(e), _) | (_, Err(e)) => {
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "❌ {}",
+                    e
+                ))]));
+            }
+        };
+
+        if let Some(vs) = vector_store::loaded() {
+            match vs
+                .read()
+                .await
+                .similarity_search(&query, &filter, search_limit)
+                .await
+            {
                 Ok(results) => {
                     let response = if results.is_empty() {
                         format!("No similar bug patterns found for query: '{}'", query)
                     } else {
-                        format!("Found {} similar bug patterns for query '{}':\n{}", 
-                            results.len(), 
+                        format!(
+                            "Found {} similar bug patterns for query '{}':\n{}",
+                            results.len(),
                             query,
                             serde_json::to_string_pretty(&results).unwrap_or_default()
                         )
                     };
-                    
+
                     Ok(CallToolResult::success(vec![Content::text(response)]))
                 }
-                Err(e) => {
-                    Ok(CallToolResult::success(vec![Content::text(
-                        format!("Error performing vector similarity search: {}", e)
-                    )]))
-                }
+                Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                    "Error performing vector similarity search: {}",
+                    e
+                ))])),
             }
         } else {
             Ok(CallToolResult::success(vec![Content::text(
-                "Vector store not available".to_string()
+                "Vector store not available".to_string(),
             )]))
         }
     }
@@ -206,22 +440,20 @@ impl NautilusMcpServer {
                 "Analyze this {} code for security vulnerabilities, performance issues, and potential bugs:\n\n```{}\n{}\n```\n\nProvide a detailed analysis including:\n1. Security vulnerabilities\n2. Performance issues\n3. Code quality problems\n4. Recommended fixes",
                 lang, lang, code
             );
-            
+
             match client.analyze_code(&prompt).await {
-                Ok(analysis) => {
-                    Ok(CallToolResult::success(vec![Content::text(
-                        format!("🔍 DeepSeek Code Analysis:\n\n{}", analysis)
-                    )]))
-                }
-                Err(e) => {
-                    Ok(CallToolResult::success(vec![Content::text(
-                        format!("❌ Code analysis failed: {}", e)
-                    )]))
-                }
+                Ok(analysis) => Ok(CallToolResult::success(vec![Content::text(format!(
+                    "🔍 DeepSeek Code Analysis:\n\n{}",
+                    analysis
+                ))])),
+                Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                    "❌ Code analysis failed: {}",
+                    e
+                ))])),
             }
         } else {
             Ok(CallToolResult::success(vec![Content::text(
-                "⚠️ DeepSeek client not available (API key not set)".to_string()
+                "⚠️ DeepSeek client not available (API key not set)".to_string(),
             )]))
         }
     }
@@ -229,49 +461,91 @@ impl NautilusMcpServer {
     #[tool(description = "Confirm if a bug is critical using DeepSeek AI analysis")]
     async fn confirm_critical_bug(
         &self,
-        Parameters(BugConfirmationRequest { bug_description, code_sample }): Parameters<BugConfirmationRequest>,
+        Parameters(BugConfirmationRequest {
+            bug_description,
+            code_sample,
+            bug_id,
+        }): Parameters<BugConfirmationRequest>,
     ) -> Result<CallToolResult, ErrorData> {
         let deepseek_client = self.deepseek_client.lock().await;
         if let Some(client) = deepseek_client.as_ref() {
-            match client.confirm_critical_bug(&bug_description, &code_sample).await {
+            match client
+                .confirm_critical_bug(&bug_description, &code_sample)
+                .await
+            {
                 Ok(analysis) => {
-                    Ok(CallToolResult::success(vec![Content::text(
-                        format!("🤖 DeepSeek Critical Bug Analysis:\n\n{}", analysis)
-                    )]))
-                }
-                Err(e) => {
-                    Ok(CallToolResult::success(vec![Content::text(
-                        format!("❌ Bug confirmation failed: {}", e)
-                    )]))
+                    let recorded = match bug_id.filter(|id| !id.trim().is_empty()) {
+                        Some(bug_id) if DeepSeekClient::confirms_critical(&analysis) => {
+                            match bug_store::confirm_bug(
+                                &Config::bugs_directory_path(),
+                                bug_id.trim(),
+                                "confirm_critical_bug",
+                            )
+                            .await
+                            {
+                                Ok(_) => format!("\n\n✅ Marked {} as confirmed", bug_id.trim()),
+                                Err(e) => format!(
+                                    "\n\n❌ Failed to mark {} as confirmed: {}",
+                                    bug_id.trim(),
+                                    e
+                                ),
+                            }
+                        }
+                        Some(bug_id) => format!(
+                            "\n\nℹ️ {} was not confirmed as critical and stays unconfirmed",
+                            bug_id.trim()
+                        ),
+                        None => String::new(),
+                    };
+                    Ok(CallToolResult::success(vec![Content::text(format!(
+                        "🤖 DeepSeek Critical Bug Analysis:\n\n{}{}",
+                        analysis, recorded
+                    ))]))
                 }
+                Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                    "❌ Bug confirmation failed: {}",
+                    e
+                ))])),
             }
         } else {
             Ok(CallToolResult::success(vec![Content::text(
-                "⚠️ DeepSeek client not available (API key not set)".to_string()
+                "⚠️ DeepSeek client not available (API key not set)".to_string(),
             )]))
         }
     }
 
     #[tool(description = "Get server status and available capabilities")]
     async fn get_status(&self) -> Result<CallToolResult, ErrorData> {
-        let vector_store_status = {
-            let vs = self.vector_store.lock().await;
-            if vs.is_some() { "✅ Available (FastEmbed)" } else { "❌ Not available" }
+        let vector_store_status = if vector_store::loaded().is_some() {
+            "✅ Available (FastEmbed)"
+        } else {
+            "❌ Not available"
         };
-        
+
         let deepseek_status = {
             let ds = self.deepseek_client.lock().await;
-            if ds.is_some() { "✅ Available" } else { "❌ Not available" }
+            if ds.is_some() {
+                "✅ Available"
+            } else {
+                "❌ Not available"
+            }
         };
-        
+
+        // Reuse the background probe report; probe now only if none has run yet
+        let health_report = match health::latest() {
+            Some(report) => report,
+            None => health::run_probes().await,
+        };
+        let health_text = health_report.to_text();
+
         let status = format!(
             "🔧 Nautilus Trader Rig MCP Server Status:\n\
              📊 Vector Store: {}\n\
              🤖 DeepSeek Client: {}\n\
              🛠️ Available Tools:\n\
-             - similarity_search: Search bug patterns with FastEmbed\n\
+             - similarity_search: Search bug patterns with FastEmbed and metadata filters\n\
              - analyze_code: Analyze code with DeepSeek AI\n\
-             - confirm_critical_bug: Validate critical bugs\n\
+             - confirm_critical_bug: Validate critical bugs, marking a stored bug confirmed\n\
              - get_status: Get server status\n\
              - read_file: Read file contents\n\
              - write_file: Write content to file\n\
@@ -279,10 +553,27 @@ impl NautilusMcpServer {
              - read_adapter: Read adapter source files\n\
              - store_bug: Store bug analysis to JSON file\n\
              - list_bugs: List all stored bugs with file locations and metadata\n\
-             - get_bug_details: Get detailed information about a specific bug",
-            vector_store_status, deepseek_status
+             - get_bug_details: Get detailed information about a specific bug\n\
+             - search_bugs: Hybrid keyword + semantic search over stored bugs with severity/adapter/status filters\n\
+             - search_code: Semantic search over adapter source chunks\n\
+             - list_patterns: List the bug pattern catalog\n\
+             - add_pattern / update_pattern / delete_pattern: Edit the pattern catalog\n\
+             - learn_patterns / list_pattern_proposals / review_pattern_proposal: Learn patterns from confirmed bugs\n\
+             - discover_themes: Cluster bugs into systemic themes across adapters\n\
+             - export_index / import_index: Share the embedded index without re-embedding\n\
+             - diff_runs: Compare two analysis runs\n\
+             - export_sarif: Export stored bugs as SARIF 2.1.0\n\
+             - adapter_risk_report: Rank adapters by risk score\n\
+             - list_scheduled_jobs: Show scheduled job history\n\
+             - enqueue_analysis: Queue a file for re-analysis\n\
+             - list_analysis_jobs: List queued analysis jobs\n\
+             - cancel_analysis_job: Cancel a pending analysis job\n\
+             🌐 Dashboard: http://{bind}/dashboard (JSON under /api)\n\
+             🩺 Health: http://{bind}/healthz and /readyz\n\n{}",
+            vector_store_status, deepseek_status, health_text,
+            bind = Config::settings().mcp.bind
         );
-        
+
         Ok(CallToolResult::success(vec![Content::text(status)]))
     }
 
@@ -298,26 +589,27 @@ impl NautilusMcpServer {
             "examples/",
             "crates/adapters/",
         ];
-        
-        let is_allowed = allowed_paths.iter().any(|allowed| file_path.starts_with(allowed));
-        
+
+        let is_allowed = allowed_paths
+            .iter()
+            .any(|allowed| file_path.starts_with(allowed));
+
         if !is_allowed {
-            return Ok(CallToolResult::success(vec![Content::text(
-                format!("❌ Access denied: File path '{}' is not in allowed directories", file_path)
-            )]));
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Access denied: File path '{}' is not in allowed directories",
+                file_path
+            ))]));
         }
 
         match async_fs::read_to_string(&file_path).await {
-            Ok(content) => {
-                Ok(CallToolResult::success(vec![Content::text(
-                    format!("📄 File: {}\n\n```\n{}\n```", file_path, content)
-                )]))
-            }
-            Err(e) => {
-                Ok(CallToolResult::success(vec![Content::text(
-                    format!("❌ Failed to read file '{}': {}", file_path, e)
-                )]))
-            }
+            Ok(content) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "📄 File: {}\n\n```\n{}\n```",
+                file_path, content
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to read file '{}': {}",
+                file_path, e
+            ))])),
         }
     }
 
@@ -327,39 +619,46 @@ impl NautilusMcpServer {
         Parameters(FileWriteRequest { file_path, content }): Parameters<FileWriteRequest>,
     ) -> Result<CallToolResult, ErrorData> {
         // Security check: only allow writing to bugs directory and certain areas
-        let allowed_write_paths = [
-            Config::BUGS_DIRECTORY,
-            "nautilus-trader-rig/logs/",
-        ];
-        
-        let is_allowed = allowed_write_paths.iter().any(|allowed| file_path.starts_with(allowed));
-        
+        let allowed_write_paths = [Config::BUGS_DIRECTORY, "nautilus-trader-rig/logs/"];
+
+        let is_allowed = allowed_write_paths
+            .iter()
+            .any(|allowed| file_path.starts_with(allowed));
+
         if !is_allowed {
-            return Ok(CallToolResult::success(vec![Content::text(
-                format!("❌ Write access denied: File path '{}' is not in allowed write directories", file_path)
-            )]));
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Write access denied: File path '{}' is not in allowed write directories",
+                file_path
+            ))]));
         }
 
         // Ensure parent directory exists
         if let Some(parent) = Path::new(&file_path).parent() {
             if let Err(e) = async_fs::create_dir_all(parent).await {
-                return Ok(CallToolResult::success(vec![Content::text(
-                    format!("❌ Failed to create directory '{}': {}", parent.display(), e)
-                )]));
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "❌ Failed to create directory '{}': {}",
+                    parent.display(),
+                    e
+                ))]));
             }
         }
 
         match async_fs::write(&file_path, &content).await {
             Ok(_) => {
-                Ok(CallToolResult::success(vec![Content::text(
-                    format!("✅ Successfully wrote to file: {}", file_path)
-                )]))
-            }
-            Err(e) => {
-                Ok(CallToolResult::success(vec![Content::text(
-                    format!("❌ Failed to write file '{}': {}", file_path, e)
-                )]))
+                if bug_store::is_bug_file(Path::new(&file_path)) {
+                    if let Err(e) = bug_index::refresh().await {
+                        tracing::warn!("⚠️ Failed to refresh bug index: {}", e);
+                    }
+                }
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "✅ Successfully wrote to file: {}",
+                    file_path
+                ))]))
             }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to write file '{}': {}",
+                file_path, e
+            ))])),
         }
     }
 
@@ -375,50 +674,60 @@ impl NautilusMcpServer {
             "examples/",
             "crates/adapters/",
         ];
-        
-        let is_allowed = allowed_paths.iter().any(|allowed| directory_path.starts_with(allowed));
-        
+
+        let is_allowed = allowed_paths
+            .iter()
+            .any(|allowed| directory_path.starts_with(allowed));
+
         if !is_allowed {
-            return Ok(CallToolResult::success(vec![Content::text(
-                format!("❌ Access denied: Directory path '{}' is not in allowed directories", directory_path)
-            )]));
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Access denied: Directory path '{}' is not in allowed directories",
+                directory_path
+            ))]));
         }
 
         match async_fs::read_dir(&directory_path).await {
             Ok(mut entries) => {
                 let mut files = Vec::new();
                 let mut dirs = Vec::new();
-                
+
                 while let Ok(Some(entry)) = entries.next_entry().await {
                     let path = entry.path();
-                    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
-                    
+                    let name = path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string();
+
                     if path.is_dir() {
                         dirs.push(format!("📁 {}/", name));
                     } else {
                         files.push(format!("📄 {}", name));
                     }
                 }
-                
+
                 dirs.sort();
                 files.sort();
-                
+
                 let mut contents = dirs;
                 contents.extend(files);
-                
+
                 let result = if contents.is_empty() {
                     format!("📂 Directory '{}' is empty", directory_path)
                 } else {
-                    format!("📂 Directory '{}' contents:\n{}", directory_path, contents.join("\n"))
+                    format!(
+                        "📂 Directory '{}' contents:\n{}",
+                        directory_path,
+                        contents.join("\n")
+                    )
                 };
-                
+
                 Ok(CallToolResult::success(vec![Content::text(result)]))
             }
-            Err(e) => {
-                Ok(CallToolResult::success(vec![Content::text(
-                    format!("❌ Failed to list directory '{}': {}", directory_path, e)
-                )]))
-            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to list directory '{}': {}",
+                directory_path, e
+            ))])),
         }
     }
 
@@ -427,17 +736,21 @@ impl NautilusMcpServer {
         &self,
         Parameters(AdapterFileRequest { adapter_name }): Parameters<AdapterFileRequest>,
     ) -> Result<CallToolResult, ErrorData> {
-        log_mcp_op!(info, "read_adapter", format!("Processing request for adapter: {}", adapter_name));
-        
+        log_mcp_op!(
+            info,
+            "read_adapter",
+            format!("Processing request for adapter: {}", adapter_name)
+        );
+
         // Try multiple Rust adapter directories
-        let rust_directories = Config::all_rust_adapter_directories();
+        let rust_directories = Config::all_rust_adapter_directories_abs();
         let mut found_files = Vec::new();
         let mut processed_files = Vec::new();
-        
+
         for rust_dir in rust_directories {
             let adapter_path = rust_dir.join(adapter_name.to_lowercase());
             log_directory_op!(debug, "Checking adapter directory", adapter_path);
-            
+
             if adapter_path.exists() {
                 log_directory_op!(info, "Found adapter directory", adapter_path);
                 match async_fs::read_dir(&adapter_path).await {
@@ -446,7 +759,7 @@ impl NautilusMcpServer {
                             let path = entry.path();
                             let file_name = path.file_name().unwrap_or_default().to_string_lossy();
                             log_file_processing!(debug, "Examining file", file_name);
-                            
+
                             if let Some(ext) = path.extension() {
                                 // Use configured Rust extensions
                                 let ext_str = ext.to_string_lossy();
@@ -455,22 +768,32 @@ impl NautilusMcpServer {
                                 }) {
                                     tracing::info!("📄 Processing Rust file: {:?}", path);
                                     processed_files.push(path.display().to_string());
-                                    
+
                                     if let Ok(content) = async_fs::read_to_string(&path).await {
-                                        tracing::debug!("✅ Successfully read file: {} ({} bytes)", file_name, content.len());
+                                        tracing::debug!(
+                                            "✅ Successfully read file: {} ({} bytes)",
+                                            file_name,
+                                            content.len()
+                                        );
                                         found_files.push(format!(
                                             "📄 File: {}\n```rust\n{}\n```\n",
                                             path.display(),
                                             content
                                         ));
                                     } else {
-                                        tracing::warn!("❌ Failed to read file content: {}", file_name);
+                                        tracing::warn!(
+                                            "❌ Failed to read file content: {}",
+                                            file_name
+                                        );
                                     }
                                 } else {
                                     tracing::debug!("⏭️ Skipping non-Rust file: {}", file_name);
                                 }
                             } else {
-                                tracing::debug!("⏭️ Skipping file without extension: {}", file_name);
+                                tracing::debug!(
+                                    "⏭️ Skipping file without extension: {}",
+                                    file_name
+                                );
                             }
                         }
                     }
@@ -483,44 +806,52 @@ impl NautilusMcpServer {
                 tracing::debug!("❌ Adapter directory not found: {:?}", adapter_path);
             }
         }
-        
-        tracing::info!("📊 Summary: Found {} Rust files for adapter '{}'", found_files.len(), adapter_name);
+
+        tracing::info!(
+            "📊 Summary: Found {} Rust files for adapter '{}'",
+            found_files.len(),
+            adapter_name
+        );
         for file in &processed_files {
             tracing::info!("   📄 {}", file);
         }
-        
+
         if !found_files.is_empty() {
-            return Ok(CallToolResult::success(vec![Content::text(
-                format!("🦀 Rust Adapter '{}' source files:\n\n{}", adapter_name, found_files.join("\n"))
-            )]));
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "🦀 Rust Adapter '{}' source files:\n\n{}",
+                adapter_name,
+                found_files.join("\n")
+            ))]));
         }
-        
-        Ok(CallToolResult::success(vec![Content::text(
-            format!("❌ No Rust adapter files found for '{}'", adapter_name)
-        )]))
+
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "❌ No Rust adapter files found for '{}'",
+            adapter_name
+        ))]))
     }
 
     #[tool(description = "List all stored bugs with exact file locations and detailed metadata")]
     async fn list_bugs(
         &self,
-        Parameters(BugListRequest { 
-            severity_filter, 
-            adapter_filter, 
-            include_file_details 
+        Parameters(BugListRequest {
+            severity_filter,
+            adapter_filter,
+            status_filter,
+            include_file_details,
         }): Parameters<BugListRequest>,
     ) -> Result<CallToolResult, ErrorData> {
-        let bugs_dir = std::path::Path::new(Config::BUGS_DIRECTORY);
+        let bugs_dir = Config::bugs_directory_path();
         let include_details = include_file_details.unwrap_or(true);
-        
+
         if !bugs_dir.exists() {
             return Ok(CallToolResult::success(vec![Content::text(
-                "📁 No bugs directory found. No bugs have been stored yet.".to_string()
+                "📁 No bugs directory found. No bugs have been stored yet.".to_string(),
             )]));
         }
-        
+
         let mut bug_files = Vec::new();
         let mut summary_files = Vec::new();
-        
+
         // Read all JSON files in bugs directory
         if let Ok(entries) = async_fs::read_dir(bugs_dir).await {
             let mut entries = entries;
@@ -538,23 +869,23 @@ impl NautilusMcpServer {
                 }
             }
         }
-        
+
         if bug_files.is_empty() && summary_files.is_empty() {
             return Ok(CallToolResult::success(vec![Content::text(
-                "📭 No bug reports found in the bugs directory.".to_string()
+                "📭 No bug reports found in the bugs directory.".to_string(),
             )]));
         }
-        
+
         let mut response_parts = Vec::new();
         let mut matching_bugs = Vec::new();
-        
+
         // Process individual bug files
         for bug_file in &bug_files {
             if let Ok(content) = async_fs::read_to_string(bug_file).await {
                 if let Ok(bug_data) = serde_json::from_str::<serde_json::Value>(&content) {
                     // Apply filters
                     let mut include_bug = true;
-                    
+
                     if let Some(ref sev_filter) = severity_filter {
                         if let Some(severity) = bug_data.get("severity").and_then(|s| s.as_str()) {
                             if !severity.to_lowercase().contains(&sev_filter.to_lowercase()) {
@@ -562,107 +893,169 @@ impl NautilusMcpServer {
                             }
                         }
                     }
-                    
+
                     if let Some(ref adapter_filter) = adapter_filter {
-                        if let Some(adapter) = bug_data.get("adapter_name").and_then(|a| a.as_str()) {
-                            if !adapter.to_lowercase().contains(&adapter_filter.to_lowercase()) {
+                        if let Some(adapter) = bug_data.get("adapter_name").and_then(|a| a.as_str())
+                        {
+                            if !adapter
+                                .to_lowercase()
+                                .contains(&adapter_filter.to_lowercase())
+                            {
                                 include_bug = false;
                             }
                         }
                     }
-                    
+
+                    if let Some(ref status_filter) = status_filter {
+                        let status = bug_data
+                            .get("status")
+                            .and_then(|s| s.as_str())
+                            .unwrap_or("open");
+                        if !status.eq_ignore_ascii_case(status_filter) {
+                            include_bug = false;
+                        }
+                    }
+
                     if include_bug {
                         matching_bugs.push((bug_file.clone(), bug_data));
                     }
                 }
             }
         }
-        
+
         if matching_bugs.is_empty() {
             response_parts.push("🔍 No bugs match the specified filters.".to_string());
         } else {
             response_parts.push(format!("🐛 Found {} matching bugs:\n", matching_bugs.len()));
-            
+
             for (i, (bug_file, bug_data)) in matching_bugs.iter().enumerate() {
-                let bug_id = bug_data.get("bug_id").and_then(|s| s.as_str()).unwrap_or("unknown");
-                let severity = bug_data.get("severity").and_then(|s| s.as_str()).unwrap_or("unknown");
-                let description = bug_data.get("description").and_then(|s| s.as_str()).unwrap_or("No description");
-                let adapter = bug_data.get("adapter_name").and_then(|s| s.as_str()).unwrap_or("unknown");
-                let timestamp = bug_data.get("timestamp").and_then(|s| s.as_str()).unwrap_or("unknown");
-                
+                let bug_id = bug_data
+                    .get("bug_id")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("unknown");
+                let severity = bug_data
+                    .get("severity")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("unknown");
+                let description = bug_data
+                    .get("description")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("No description");
+                let adapter = bug_data
+                    .get("adapter_name")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("unknown");
+                let timestamp = bug_data
+                    .get("timestamp")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("unknown");
+                let status = bug_data
+                    .get("status")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("open");
+
                 response_parts.push(format!(
-                    "\n{}. 🆔 Bug ID: {}\n   ⚠️ Severity: {}\n   📦 Adapter: {}\n   ⏰ Timestamp: {}\n   📄 File: {}",
-                    i + 1, bug_id, severity, adapter, timestamp, bug_file.display()
+                    "\n{}. 🆔 Bug ID: {}\n   ⚠️ Severity: {}\n   🔄 Status: {}\n   📦 Adapter: {}\n   ⏰ Timestamp: {}\n   📄 File: {}",
+                    i + 1, bug_id, severity, status, adapter, timestamp, bug_file.display()
                 ));
-                
+
                 if include_details {
                     // Include file location details if available
                     if let Some(file_location) = bug_data.get("file_location") {
                         response_parts.push("   📍 File Location Details:".to_string());
-                        
+
                         if let Some(details) = file_location.get("details") {
-                            if let Some(abs_path) = details.get("absolute_path").and_then(|s| s.as_str()) {
-                                response_parts.push(format!("      📂 Absolute Path: {}", abs_path));
-                            }
-                            if let Some(rel_path) = details.get("relative_path").and_then(|s| s.as_str()) {
-                                response_parts.push(format!("      📁 Relative Path: {}", rel_path));
+                            if let Some(rel_path) =
+                                details.get("relative_path").and_then(|s| s.as_str())
+                            {
+                                response_parts
+                                    .push(format!("      📁 Relative Path: {}", rel_path));
+                                response_parts.push(format!(
+                                    "      📂 Absolute Path: {}",
+                                    repo_paths::resolve(rel_path).display()
+                                ));
+                            } else if let Some(abs_path) =
+                                details.get("absolute_path").and_then(|s| s.as_str())
+                            {
+                                response_parts
+                                    .push(format!("      📂 Absolute Path: {}", abs_path));
                             }
-                            if let Some(filename) = details.get("filename").and_then(|s| s.as_str()) {
+                            if let Some(filename) = details.get("filename").and_then(|s| s.as_str())
+                            {
                                 response_parts.push(format!("      📄 Filename: {}", filename));
                             }
-                            if let Some(size) = details.get("file_size_bytes").and_then(|s| s.as_u64()) {
+                            if let Some(size) =
+                                details.get("file_size_bytes").and_then(|s| s.as_u64())
+                            {
                                 response_parts.push(format!("      📏 File Size: {} bytes", size));
                             }
                         }
-                        
+
                         if let Some(source_loc) = file_location.get("source_location") {
-                            if let Some(line_num) = source_loc.get("approximate_line_number").and_then(|s| s.as_u64()) {
-                                response_parts.push(format!("      📍 Approximate Line: {}", line_num));
+                            if let Some(line_num) = source_loc
+                                .get("approximate_line_number")
+                                .and_then(|s| s.as_u64())
+                            {
+                                response_parts
+                                    .push(format!("      📍 Approximate Line: {}", line_num));
                             }
                         }
                     }
-                    
+
                     // Include workspace info if available
                     if let Some(workspace) = bug_data.get("workspace_info") {
                         if let Some(branch) = workspace.get("branch").and_then(|s| s.as_str()) {
                             response_parts.push(format!("      🌿 Git Branch: {}", branch));
                         }
-                        if let Some(commit) = workspace.get("commit_hash").and_then(|s| s.as_str()) {
+                        if let Some(commit) = workspace.get("commit_hash").and_then(|s| s.as_str())
+                        {
                             response_parts.push(format!("      🔗 Commit: {}", &commit[..8]));
                         }
                     }
                 }
-                
-                response_parts.push(format!("   📝 Description: {}", 
-                    if description.len() > 100 { 
-                        format!("{}...", &description[..100]) 
-                    } else { 
-                        description.to_string() 
+
+                response_parts.push(format!(
+                    "   📝 Description: {}",
+                    if description.len() > 100 {
+                        format!("{}...", &description[..100])
+                    } else {
+                        description.to_string()
                     }
                 ));
             }
         }
-        
+
         // Add summary information about analysis runs
         if !summary_files.is_empty() {
-            response_parts.push(format!("\n📊 Analysis Summary Files: {} found", summary_files.len()));
-            
+            response_parts.push(format!(
+                "\n📊 Analysis Summary Files: {} found",
+                summary_files.len()
+            ));
+
             // Show the most recent summary
             if let Some(latest_summary) = summary_files.last() {
                 if let Ok(content) = async_fs::read_to_string(latest_summary).await {
                     if let Ok(summary_data) = serde_json::from_str::<serde_json::Value>(&content) {
                         if let Some(summary) = summary_data.get("analysis_summary") {
                             response_parts.push("\n📋 Latest Analysis Summary:".to_string());
-                            if let Some(total) = summary.get("total_files_discovered").and_then(|s| s.as_u64()) {
-                                response_parts.push(format!("   📁 Total Files Discovered: {}", total));
+                            if let Some(total) = summary
+                                .get("total_files_discovered")
+                                .and_then(|s| s.as_u64())
+                            {
+                                response_parts
+                                    .push(format!("   📁 Total Files Discovered: {}", total));
                             }
-                            if let Some(analyzed) = summary.get("files_analyzed").and_then(|s| s.as_u64()) {
+                            if let Some(analyzed) =
+                                summary.get("files_analyzed").and_then(|s| s.as_u64())
+                            {
                                 response_parts.push(format!("   🔍 Files Analyzed: {}", analyzed));
                             }
                             if let Some(bugs) = summary.get("bugs_found").and_then(|s| s.as_u64()) {
                                 response_parts.push(format!("   🐛 Bugs Found: {}", bugs));
                             }
-                            if let Some(timestamp) = summary.get("analysis_timestamp").and_then(|s| s.as_str()) {
+                            if let Some(timestamp) =
+                                summary.get("analysis_timestamp").and_then(|s| s.as_str())
+                            {
                                 response_parts.push(format!("   ⏰ Analysis Time: {}", timestamp));
                             }
                         }
@@ -670,23 +1063,27 @@ impl NautilusMcpServer {
                 }
             }
         }
-        
-        Ok(CallToolResult::success(vec![Content::text(response_parts.join("\n"))]))
+
+        Ok(CallToolResult::success(vec![Content::text(
+            response_parts.join("\n"),
+        )]))
     }
 
-    #[tool(description = "Get detailed information about a specific bug including exact file location and fix suggestions")]
+    #[tool(
+        description = "Get detailed information about a specific bug including exact file location and fix suggestions"
+    )]
     async fn get_bug_details(
         &self,
         Parameters(BugDetailRequest { bug_id }): Parameters<BugDetailRequest>,
     ) -> Result<CallToolResult, ErrorData> {
-        let bugs_dir = std::path::Path::new(Config::BUGS_DIRECTORY);
-        
+        let bugs_dir = Config::bugs_directory_path();
+
         if !bugs_dir.exists() {
             return Ok(CallToolResult::success(vec![Content::text(
-                "📁 No bugs directory found.".to_string()
+                "📁 No bugs directory found.".to_string(),
             )]));
         }
-        
+
         // Search for bug file by ID
         let mut found_bug_file = None;
         if let Ok(entries) = async_fs::read_dir(bugs_dir).await {
@@ -704,72 +1101,105 @@ impl NautilusMcpServer {
                 }
             }
         }
-        
+
         let Some(bug_file) = found_bug_file else {
-            return Ok(CallToolResult::success(vec![Content::text(
-                format!("❌ Bug with ID '{}' not found.", bug_id)
-            )]));
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Bug with ID '{}' not found.",
+                bug_id
+            ))]));
         };
-        
+
         let content = match async_fs::read_to_string(&bug_file).await {
             Ok(content) => content,
             Err(e) => {
-                return Ok(CallToolResult::success(vec![Content::text(
-                    format!("❌ Failed to read bug file: {}", e)
-                )]));
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "❌ Failed to read bug file: {}",
+                    e
+                ))]));
             }
         };
-        
+
         let bug_data: serde_json::Value = match serde_json::from_str(&content) {
             Ok(data) => data,
             Err(e) => {
-                return Ok(CallToolResult::success(vec![Content::text(
-                    format!("❌ Failed to parse bug file: {}", e)
-                )]));
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "❌ Failed to parse bug file: {}",
+                    e
+                ))]));
             }
         };
-        
+
         let mut response_parts = Vec::new();
-        
+
         // Basic bug information
         response_parts.push(format!("🐛 Bug Details for ID: {}", bug_id));
-        response_parts.push("=" .repeat(50));
-        
+        response_parts.push("=".repeat(50));
+
         if let Some(severity) = bug_data.get("severity").and_then(|s| s.as_str()) {
             response_parts.push(format!("⚠️  Severity: {}", severity));
         }
-        
+
         if let Some(adapter) = bug_data.get("adapter_name").and_then(|s| s.as_str()) {
             response_parts.push(format!("📦 Adapter: {}", adapter));
         }
-        
+
+        let status = bug_data
+            .get("status")
+            .and_then(|s| s.as_str())
+            .unwrap_or("open");
+        response_parts.push(format!("🔄 Status: {}", status));
+        if let Some(history) = bug_data.get("status_history").and_then(|h| h.as_array()) {
+            for change in history {
+                let change_status = change
+                    .get("status")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("unknown");
+                let change_time = change
+                    .get("timestamp")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("unknown");
+                let commit = change
+                    .get("commit_hash")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("unknown");
+                response_parts.push(format!(
+                    "   ↳ {} at {} (commit {})",
+                    change_status, change_time, commit
+                ));
+            }
+        }
+
         if let Some(timestamp) = bug_data.get("timestamp").and_then(|s| s.as_str()) {
             response_parts.push(format!("⏰ Timestamp: {}", timestamp));
         }
-        
+
         if let Some(context) = bug_data.get("analysis_context").and_then(|s| s.as_str()) {
             response_parts.push(format!("🔍 Analysis Context: {}", context));
         }
-        
+
         response_parts.push("".to_string());
-        
+
         // Description
         if let Some(description) = bug_data.get("description").and_then(|s| s.as_str()) {
             response_parts.push("📝 Description:".to_string());
             response_parts.push(format!("   {}", description));
             response_parts.push("".to_string());
         }
-        
+
         // File location details
         if let Some(file_location) = bug_data.get("file_location") {
             response_parts.push("📍 File Location:".to_string());
-            
+
             if let Some(details) = file_location.get("details") {
-                if let Some(abs_path) = details.get("absolute_path").and_then(|s| s.as_str()) {
-                    response_parts.push(format!("   📂 Absolute Path: {}", abs_path));
-                }
                 if let Some(rel_path) = details.get("relative_path").and_then(|s| s.as_str()) {
                     response_parts.push(format!("   📁 Relative Path: {}", rel_path));
+                    response_parts.push(format!(
+                        "   📂 Absolute Path: {}",
+                        repo_paths::resolve(rel_path).display()
+                    ));
+                } else if let Some(abs_path) = details.get("absolute_path").and_then(|s| s.as_str())
+                {
+                    response_parts.push(format!("   📂 Absolute Path: {}", abs_path));
                 }
                 if let Some(filename) = details.get("filename").and_then(|s| s.as_str()) {
                     response_parts.push(format!("   📄 Filename: {}", filename));
@@ -780,23 +1210,35 @@ impl NautilusMcpServer {
                 if let Some(size) = details.get("file_size_bytes").and_then(|s| s.as_u64()) {
                     response_parts.push(format!("   📏 File Size: {} bytes", size));
                 }
-                if let Some(modified) = details.get("last_modified_timestamp").and_then(|s| s.as_u64()) {
-                    response_parts.push(format!("   🕒 Last Modified: {} (Unix timestamp)", modified));
+                if let Some(modified) = details
+                    .get("last_modified_timestamp")
+                    .and_then(|s| s.as_u64())
+                {
+                    response_parts.push(format!(
+                        "   🕒 Last Modified: {} (Unix timestamp)",
+                        modified
+                    ));
                 }
             }
-            
+
             if let Some(source_loc) = file_location.get("source_location") {
-                if let Some(line_num) = source_loc.get("approximate_line_number").and_then(|s| s.as_u64()) {
+                if let Some(line_num) = source_loc
+                    .get("approximate_line_number")
+                    .and_then(|s| s.as_u64())
+                {
                     response_parts.push(format!("   📍 Approximate Line Number: {}", line_num));
                 }
-                if let Some(method) = source_loc.get("context_extraction_method").and_then(|s| s.as_str()) {
+                if let Some(method) = source_loc
+                    .get("context_extraction_method")
+                    .and_then(|s| s.as_str())
+                {
                     response_parts.push(format!("   🔍 Location Method: {}", method));
                 }
             }
-            
+
             response_parts.push("".to_string());
         }
-        
+
         // Workspace information
         if let Some(workspace) = bug_data.get("workspace_info") {
             response_parts.push("🌿 Workspace Information:".to_string());
@@ -811,7 +1253,7 @@ impl NautilusMcpServer {
             }
             response_parts.push("".to_string());
         }
-        
+
         // Code sample
         if let Some(code_sample) = bug_data.get("code_sample").and_then(|s| s.as_str()) {
             if !code_sample.is_empty() && code_sample != "See file content" {
@@ -822,7 +1264,7 @@ impl NautilusMcpServer {
                 response_parts.push("".to_string());
             }
         }
-        
+
         // Fix suggestion
         if let Some(fix_suggestion) = bug_data.get("fix_suggestion").and_then(|s| s.as_str()) {
             if !fix_suggestion.is_empty() && fix_suggestion != "Manual review required" {
@@ -831,7 +1273,7 @@ impl NautilusMcpServer {
                 response_parts.push("".to_string());
             }
         }
-        
+
         // Additional metadata
         if let Some(metadata) = bug_data.get("mcp_metadata") {
             response_parts.push("🔧 Technical Metadata:".to_string());
@@ -842,60 +1284,77 @@ impl NautilusMcpServer {
                 response_parts.push(format!("   📤 Submission Method: {}", method));
             }
         }
-        
+
         response_parts.push("".to_string());
         response_parts.push(format!("📄 Bug file location: {}", bug_file.display()));
-        
-        Ok(CallToolResult::success(vec![Content::text(response_parts.join("\n"))]))
+
+        Ok(CallToolResult::success(vec![Content::text(
+            response_parts.join("\n"),
+        )]))
     }
 
-    #[tool(description = "Store bug analysis as JSON file in bugs directory with enhanced file location tracking")]
+    #[tool(
+        description = "Store bug analysis as JSON file in bugs directory with enhanced file location tracking"
+    )]
     async fn store_bug(
         &self,
-        Parameters(BugStoreRequest { 
-            bug_id, 
-            severity, 
-            description, 
-            adapter_name, 
-            code_sample, 
-            fix_suggestion 
+        Parameters(BugStoreRequest {
+            bug_id,
+            severity,
+            description,
+            adapter_name,
+            code_sample,
+            fix_suggestion,
         }): Parameters<BugStoreRequest>,
     ) -> Result<CallToolResult, ErrorData> {
         let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
-        let adapter_suffix = adapter_name.as_ref().map(|s| format!("_{}", s)).unwrap_or_default();
-        let filename = format!("{}/{}{}_{}.json", Config::BUGS_DIRECTORY, bug_id, adapter_suffix, timestamp);
-        
+        let adapter_suffix = adapter_name
+            .as_ref()
+            .map(|s| format!("_{}", s))
+            .unwrap_or_default();
+        let filename = Config::bugs_directory_path()
+            .join(format!("{}{}_{}.json", bug_id, adapter_suffix, timestamp))
+            .to_string_lossy()
+            .into_owned();
+
         // Enhanced file location tracking (similar to store_bug_internal)
         let mut workspace_info = serde_json::Map::new();
-        workspace_info.insert("repository".to_string(), serde_json::Value::String("nautilus_trader".to_string()));
-        
+        workspace_info.insert(
+            "repository".to_string(),
+            serde_json::Value::String("nautilus_trader".to_string()),
+        );
+
         // Try to get git information
         if let Ok(output) = tokio::process::Command::new("git")
             .args(["branch", "--show-current"])
             .output()
-            .await 
+            .await
         {
             if output.status.success() {
                 if let Ok(branch) = String::from_utf8(output.stdout) {
-                    workspace_info.insert("branch".to_string(), 
-                        serde_json::Value::String(branch.trim().to_string()));
+                    workspace_info.insert(
+                        "branch".to_string(),
+                        serde_json::Value::String(branch.trim().to_string()),
+                    );
                 }
             }
         }
-        
+
         if let Ok(output) = tokio::process::Command::new("git")
             .args(["rev-parse", "HEAD"])
             .output()
-            .await 
+            .await
         {
             if output.status.success() {
                 if let Ok(commit) = String::from_utf8(output.stdout) {
-                    workspace_info.insert("commit_hash".to_string(), 
-                        serde_json::Value::String(commit.trim().to_string()));
+                    workspace_info.insert(
+                        "commit_hash".to_string(),
+                        serde_json::Value::String(commit.trim().to_string()),
+                    );
                 }
             }
         }
-        
+
         let bug_data = serde_json::json!({
             "bug_id": bug_id,
             "severity": severity,
@@ -912,20 +1371,603 @@ impl NautilusMcpServer {
                 "enhanced_tracking": true
             }
         });
-        
+
         match async_fs::write(&filename, serde_json::to_string_pretty(&bug_data).unwrap()).await {
             Ok(_) => {
+                if let Err(e) = bug_index::refresh().await {
+                    tracing::warn!("⚠️ Failed to refresh bug index: {}", e);
+                }
                 let response_msg = format!(
                     "✅ Bug stored successfully: {}\n📊 Enhanced tracking enabled with workspace metadata\n🔍 Bug ID: {}\n⚠️ Severity: {}",
                     filename, bug_id, severity
                 );
                 Ok(CallToolResult::success(vec![Content::text(response_msg)]))
             }
-            Err(e) => {
-                Ok(CallToolResult::success(vec![Content::text(
-                    format!("❌ Failed to store bug: {}", e)
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to store bug: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Hybrid keyword + semantic search over stored bugs (description, code sample and fix), filtered by severity, adapter and status; hits include vector, keyword and re-rank scores"
+    )]
+    async fn search_bugs(
+        &self,
+        Parameters(BugSearchRequest {
+            query,
+            limit,
+            severity,
+            adapter,
+            status,
+        }): Parameters<BugSearchRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let limit = limit.unwrap_or(Config::settings().embedding.search_limit);
+        let filter = BugFilter {
+            severity,
+            adapter,
+            status,
+        };
+
+        match bug_index::search(&query, &filter, limit).await {
+            Ok(hits) if hits.is_empty() => Ok(CallToolResult::success(vec![Content::text(
+                format!("No stored bugs match '{}'", query),
+            )])),
+            Ok(hits) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Found {} stored bugs similar to '{}':\n{}",
+                hits.len(),
+                query,
+                serde_json::to_string_pretty(&hits).unwrap_or_default()
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Bug search failed: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Semantic search over adapter source code chunks (functions, types, impl blocks), optionally within one adapter"
+    )]
+    async fn search_code(
+        &self,
+        Parameters(CodeSearchRequest {
+            query,
+            adapter,
+            limit,
+        }): Parameters<CodeSearchRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let limit = limit.unwrap_or(Config::settings().embedding.search_limit);
+
+        match code_index::search(&query, adapter.as_deref(), limit).await {
+            Ok(hits) if hits.is_empty() => Ok(CallToolResult::success(vec![Content::text(
+                format!("No adapter code matches '{}'", query),
+            )])),
+            Ok(hits) => {
+                let mut text =
+                    format!("Found {} code chunks similar to '{}':\n", hits.len(), query);
+                for hit in &hits {
+                    text.push_str(&format!(
+                        "\n📍 score {:.3}\n```rust\n{}\n```\n",
+                        hit.score,
+                        hit.chunk.excerpt(60)
+                    ));
+                }
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Code search failed: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "List the bug pattern catalog with descriptions, examples and references, optionally one category"
+    )]
+    async fn list_patterns(
+        &self,
+        Parameters(PatternListRequest { category }): Parameters<PatternListRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let category = category.filter(|c| !c.trim().is_empty());
+        match pattern_catalog::load_catalog(&Config::patterns_directory_path()) {
+            Ok(entries) => {
+                let entries: Vec<PatternEntry> = entries
+                    .into_iter()
+                    .filter(|entry| {
+                        category
+                            .as_deref()
+                            .is_none_or(|c| entry.category.eq_ignore_ascii_case(c.trim()))
+                    })
+                    .collect();
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "📚 {} bug patterns:\n{}",
+                    entries.len(),
+                    serde_json::to_string_pretty(&entries).unwrap_or_default()
+                ))]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to load pattern catalog: {:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(description = "Add a bug pattern to the catalog file store and the similarity index")]
+    async fn add_pattern(
+        &self,
+        Parameters(request): Parameters<PatternAddRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let non_empty = |value: Option<String>| {
+            value
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let mut entry = PatternEntry::new(
+            &request.id,
+            &request.category,
+            request.severity,
+            &request.description,
+        );
+        entry.examples = request.examples.unwrap_or_default();
+        entry.references = request.references.unwrap_or_default();
+        entry.adapter = non_empty(request.adapter);
+        entry.status = non_empty(request.status);
+
+        let result = async {
+            pattern_catalog::create_pattern(&Config::patterns_directory_path(), &entry)?;
+            vector_store::index_pattern(entry.to_document()).await
+        }
+        .await;
+        match result {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "✅ Added pattern {} to {}",
+                entry.id,
+                pattern_catalog::pattern_path(&Config::patterns_directory_path(), &entry.id)
+                    .display()
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to add pattern: {:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(description = "Update fields of a catalog bug pattern; unset fields keep their value")]
+    async fn update_pattern(
+        &self,
+        Parameters(PatternUpdateRequest {
+            id,
+            category,
+            severity,
+            description,
+            examples,
+            references,
+            adapter,
+            status,
+        }): Parameters<PatternUpdateRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let changes = PatternChanges {
+            category,
+            severity,
+            description,
+            examples,
+            references,
+            adapter,
+            status,
+        };
+        let result = async {
+            let entry =
+                pattern_catalog::update_pattern(&Config::patterns_directory_path(), &id, changes)?;
+            vector_store::index_pattern(entry.to_document()).await?;
+            Ok::<_, anyhow::Error>(entry)
+        }
+        .await;
+        match result {
+            Ok(entry) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "✅ Updated pattern {}:\n{}",
+                entry.id,
+                serde_json::to_string_pretty(&entry).unwrap_or_default()
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to update pattern: {:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(description = "Delete a bug pattern from the catalog and the similarity index")]
+    async fn delete_pattern(
+        &self,
+        Parameters(PatternIdRequest { id }): Parameters<PatternIdRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let result = async {
+            let entry = pattern_catalog::delete_pattern(&Config::patterns_directory_path(), &id)?;
+            vector_store::unindex_pattern(&entry.id).await
+        }
+        .await;
+        match result {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "🗑️ Deleted pattern {}",
+                id
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to delete pattern: {:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Export the embedded patterns and bugs, with the model name and dimension, to a portable bincode file"
+    )]
+    async fn export_index(
+        &self,
+        Parameters(IndexExportRequest { path }): Parameters<IndexExportRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let result = async {
+            let file = index_snapshot::shared_index_path(&path)?;
+            let snapshot = index_snapshot::export().await?;
+            snapshot.write(&file)?;
+            Ok::<_, anyhow::Error>(snapshot)
+        }
+        .await;
+        match result {
+            Ok(snapshot) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "📦 Exported {} patterns and {} bugs embedded with {} ({} dimensions) to {}",
+                snapshot.patterns.len(),
+                snapshot.bugs.len(),
+                snapshot.model,
+                snapshot.dimension,
+                path
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to export index: {:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Import an exported index without re-embedding, merging into or replacing the catalog and stored bugs; the embedding model must match"
+    )]
+    async fn import_index(
+        &self,
+        Parameters(IndexImportRequest { path, replace }): Parameters<IndexImportRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let mode = if replace.unwrap_or(false) {
+            ImportMode::Replace
+        } else {
+            ImportMode::Merge
+        };
+        let imported = match index_snapshot::shared_index_path(&path) {
+            Ok(file) => index_snapshot::import(&file, mode).await,
+            Err(e) => Err(e),
+        };
+        match imported {
+            Ok(summary) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "📦 Imported index from {}:\n{}",
+                path,
+                serde_json::to_string_pretty(&summary).unwrap_or_default()
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to import index: {:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Cluster stored bugs into systemic themes, each with a DeepSeek-written label and the adapters it affects"
+    )]
+    async fn discover_themes(
+        &self,
+        Parameters(request): Parameters<ThemeRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let mut options = ThemeOptions::default();
+        if let Some(status) = request.status {
+            options.filter.status = Some(status);
+        }
+        options.filter.adapter = request.adapter;
+        if let Some(threshold) = request.threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "❌ threshold must be between 0 and 1, got {}",
+                    threshold
+                ))]));
+            }
+            options.threshold = threshold;
+        }
+        if let Some(min_size) = request.min_size {
+            options.min_size = min_size;
+        }
+
+        let deepseek_client = self.deepseek_client.lock().await;
+        match themes::discover_themes(deepseek_client.as_ref(), &options).await {
+            Ok(report) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "🧭 {} themes across {} bugs:\n{}",
+                report.themes.len(),
+                report.bugs,
+                serde_json::to_string_pretty(&report).unwrap_or_default()
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Theme discovery failed: {:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Cluster confirmed bugs not yet covered by a pattern and have DeepSeek propose a pattern for each cluster, for review"
+    )]
+    async fn learn_patterns(
+        &self,
+        Parameters(PatternLearnRequest { min_cluster_size }): Parameters<PatternLearnRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let deepseek_client = self.deepseek_client.lock().await;
+        let Some(client) = deepseek_client.as_ref() else {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "⚠️ DeepSeek client not available (API key not set)".to_string(),
+            )]));
+        };
+        let min_cluster_size = min_cluster_size.unwrap_or(pattern_learning::MIN_CLUSTER_SIZE);
+        match pattern_learning::learn_patterns(client, min_cluster_size).await {
+            Ok(summary) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "🧬 {} unlearned confirmed bugs in {} clusters; {} patterns proposed for review:\n{}",
+                summary.unlearned_bugs,
+                summary.clusters,
+                summary.proposed.len(),
+                serde_json::to_string_pretty(&summary).unwrap_or_default()
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(
+                format!("❌ Pattern learning failed: {:#}", e)
+            )])),
+        }
+    }
+
+    #[tool(
+        description = "List learned pattern proposals awaiting review and the ones already rejected"
+    )]
+    async fn list_pattern_proposals(&self) -> Result<CallToolResult, ErrorData> {
+        match pattern_learning::load_proposals(&Config::patterns_directory_path()) {
+            Ok(proposals) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "📝 {} pattern proposals:\n{}",
+                proposals.len(),
+                serde_json::to_string_pretty(&proposals).unwrap_or_default()
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to load pattern proposals: {:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Accept a learned pattern proposal into the catalog and index, or reject it"
+    )]
+    async fn review_pattern_proposal(
+        &self,
+        Parameters(PatternReviewRequest { id, accept }): Parameters<PatternReviewRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let result = if accept {
+            pattern_learning::accept_proposal(&id)
+                .await
+                .map(|_| format!("✅ Added pattern {} to the catalog", id))
+        } else {
+            pattern_learning::reject_proposal(&id).map(|_| format!("🚫 Rejected proposal {}", id))
+        };
+        match result {
+            Ok(message) => Ok(CallToolResult::success(vec![Content::text(message)])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to review proposal: {:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Diff two analysis runs: newly flagged files, files that went clean, severity changes and new failures"
+    )]
+    async fn diff_runs(
+        &self,
+        Parameters(RunDiffRequest { base, head, format }): Parameters<RunDiffRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let base = base.unwrap_or_else(|| "previous".to_string());
+        let head = head.unwrap_or_else(|| "latest".to_string());
+        let format = format.unwrap_or_else(|| "json".to_string());
+
+        let rendered = run_diff::diff_runs(&Config::bugs_directory_path(), &base, &head)
+            .await
+            .and_then(|diff| run_diff::render(&diff, &format));
+
+        match rendered {
+            Ok(output) => Ok(CallToolResult::success(vec![Content::text(output)])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to diff runs '{}' and '{}': {}",
+                base, head, e
+            ))])),
+        }
+    }
+
+    #[tool(description = "Export stored bugs as a SARIF 2.1.0 log for code-scanning viewers")]
+    async fn export_sarif(
+        &self,
+        Parameters(SarifExportRequest { include_fixed }): Parameters<SarifExportRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        match sarif::export_bugs(
+            &Config::bugs_directory_path(),
+            include_fixed.unwrap_or(false),
+        )
+        .await
+        {
+            Ok(log) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&log).unwrap_or_default(),
+            )])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to export SARIF: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Show scheduled analysis jobs: cron schedules, last successful runs and recent job history"
+    )]
+    async fn list_scheduled_jobs(
+        &self,
+        Parameters(ScheduledJobsRequest { kind, limit }): Parameters<ScheduledJobsRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let kind = match kind.as_deref() {
+            None | Some("") => None,
+            Some(name) => match scheduler::JobKind::ALL
+                .into_iter()
+                .find(|k| k.as_str() == name)
+            {
+                Some(kind) => Some(kind),
+                None => {
+                    return Ok(CallToolResult::success(vec![Content::text(format!(
+                        "❌ Unknown job kind '{}', expected full_sweep, diff_sweep or reverify",
+                        name
+                    ))]))
+                }
+            },
+        };
+
+        let state = scheduler::SchedulerState::load(&scheduler::state_path()).await;
+        let overview = state.overview(kind, limit.unwrap_or(20));
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&overview).unwrap_or_default(),
+        )]))
+    }
+
+    #[tool(
+        description = "Queue an adapter file, or a line range of it, for re-analysis on the durable job queue"
+    )]
+    async fn enqueue_analysis(
+        &self,
+        Parameters(EnqueueAnalysisRequest {
+            file_path,
+            start_line,
+            end_line,
+        }): Parameters<EnqueueAnalysisRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let relative = repo_paths::to_repo_relative(&file_path);
+        if !relative.starts_with("crates/adapters/") || !relative.ends_with(".rs") {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Only Rust files under crates/adapters can be analyzed, got '{}'",
+                file_path
+            ))]));
+        }
+        let Ok(content) = async_fs::read_to_string(repo_paths::resolve(&relative)).await else {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ File not found: {}",
+                relative
+            ))]));
+        };
+        let line_count = content.lines().count();
+        let lines = match (start_line, end_line) {
+            (None, None) => None,
+            (Some(start), Some(end)) if start >= 1 && start <= end => Some((start, end)),
+            (Some(start), None) if start >= 1 && start <= line_count => Some((start, line_count)),
+            (None, Some(end)) if end >= 1 => Some((1, end)),
+            _ => {
+                return Ok(CallToolResult::success(vec![Content::text(
+                    "❌ Invalid line range: start_line must be >= 1 and not after end_line"
+                        .to_string(),
                 )]))
             }
+        };
+
+        let job = job_queue::AnalysisJob {
+            file_path: relative,
+            lines,
+            run_id: None,
+        };
+        let key = job.key();
+        let queued = match job_queue::JobQueue::shared().await {
+            Ok(queue) => queue.enqueue(job).await,
+            Err(e) => Err(e),
+        };
+        match queued {
+            Ok(job_id) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "✅ Queued analysis of {} as job {}",
+                key, job_id
+            ))])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to queue analysis of {}: {}",
+                key, e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "List analysis jobs on the durable queue with their status, attempts and errors"
+    )]
+    async fn list_analysis_jobs(
+        &self,
+        Parameters(AnalysisJobsRequest { status, limit }): Parameters<AnalysisJobsRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let status = status.filter(|s| !s.trim().is_empty());
+        let listed = match job_queue::JobQueue::shared().await {
+            Ok(queue) => queue.list(status.as_deref(), limit.unwrap_or(50)).await,
+            Err(e) => Err(e),
+        };
+        match listed {
+            Ok(jobs) => Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string_pretty(&json!({ "count": jobs.len(), "jobs": jobs }))
+                    .unwrap_or_default(),
+            )])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to list analysis jobs: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(description = "Cancel a pending analysis job on the durable queue")]
+    async fn cancel_analysis_job(
+        &self,
+        Parameters(CancelAnalysisJobRequest { job_id }): Parameters<CancelAnalysisJobRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let cancelled = match job_queue::JobQueue::shared().await {
+            Ok(queue) => queue.cancel(&job_id).await,
+            Err(e) => Err(e),
+        };
+        let text = match cancelled {
+            Ok(true) => format!("✅ Cancelled analysis job {}", job_id),
+            Ok(false) => format!("❌ No pending analysis job with ID {}", job_id),
+            Err(e) => format!("❌ Failed to cancel analysis job {}: {}", job_id, e),
+        };
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        description = "Rank adapters by risk score from severity-weighted open bugs, confirmation, age, git churn and failed analyses"
+    )]
+    async fn adapter_risk_report(
+        &self,
+        Parameters(AdapterRiskRequest { run, format }): Parameters<AdapterRiskRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let run = run.unwrap_or_else(|| "latest".to_string());
+
+        let rendered = risk::load_or_compute(&Config::bugs_directory_path(), &run)
+            .await
+            .and_then(|report| match format.as_deref().unwrap_or("text") {
+                "json" => Ok(serde_json::to_string_pretty(&report)?),
+                "text" => Ok(report.to_table()),
+                other => Err(anyhow::anyhow!(
+                    "Unknown format '{}', expected json or text",
+                    other
+                )),
+            });
+
+        match rendered {
+            Ok(output) => Ok(CallToolResult::success(vec![Content::text(output)])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "❌ Failed to compute adapter risk for '{}': {}",
+                run, e
+            ))])),
         }
     }
 }
@@ -966,8 +2008,7 @@ impl ServerHandler for NautilusMcpServer {
     ) -> Result<ReadResourceResult, ErrorData> {
         match uri.as_str() {
             "nautilus://vector_store" => {
-                let vs = self.vector_store.lock().await;
-                let content = if vs.is_some() {
+                let content = if vector_store::loaded().is_some() {
                     "Vector Store Status: Active\nType: FastEmbed with SQLite\nCapabilities: Local embeddings, similarity search, bug pattern storage"
                 } else {
                     "Vector Store Status: Inactive"
@@ -1023,7 +2064,7 @@ impl ServerHandler for NautilusMcpServer {
             let initialize_uri = &http_request_part.uri;
             tracing::info!(?initialize_headers, %initialize_uri, "initialize from http server");
         }
-        
+
         // Initialize services asynchronously
         tokio::spawn({
             let server = self.clone();
@@ -1033,24 +2074,32 @@ impl ServerHandler for NautilusMcpServer {
                 }
             }
         });
-        
+
         Ok(self.get_info())
     }
 }
 
 pub async fn run_mcp_server() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
-    
+
     tracing::info!("🚀 Starting Nautilus Trader Rig MCP Server...");
 
-    let service = TowerToHyperService::new(StreamableHttpService::new(
+    let mcp_service = StreamableHttpService::new(
         || Ok(NautilusMcpServer::new()),
         LocalSessionManager::default().into(),
         Default::default(),
-    ));
-    let listener = tokio::net::TcpListener::bind("localhost:8080").await?;
-    
-    tracing::info!("🌐 MCP Server listening on http://localhost:8080");
+    );
+    // Dashboard and health routes are matched first; everything else is the MCP endpoint
+    let service = TowerToHyperService::new(
+        dashboard::router()
+            .merge(health::router())
+            .fallback_service(mcp_service),
+    );
+    let bind = &Config::settings().mcp.bind;
+    let listener = tokio::net::TcpListener::bind(bind.as_str()).await?;
+
+    tracing::info!("🌐 MCP Server listening on http://{}", bind);
+    tracing::info!("📊 Dashboard available at http://{}/dashboard", bind);
 
     tokio::spawn({
         let service = service.clone();
@@ -1092,8 +2141,10 @@ pub async fn run_mcp_server() -> anyhow::Result<()> {
 
 #[allow(dead_code)]
 pub async fn test_mcp_client() -> anyhow::Result<()> {
-    let transport =
-        rmcp::transport::StreamableHttpClientTransport::from_uri("http://localhost:8080");
+    let transport = rmcp::transport::StreamableHttpClientTransport::from_uri(format!(
+        "http://{}",
+        Config::settings().mcp.bind
+    ));
 
     let client_info = ClientInfo {
         protocol_version: Default::default(),
@@ -1114,16 +2165,21 @@ pub async fn test_mcp_client() -> anyhow::Result<()> {
 
     // List tools
     let tools: Vec<Tool> = client.list_tools(Default::default()).await?.tools;
-    tracing::info!("Available tools: {:?}", tools.iter().map(|t| &t.name).collect::<Vec<_>>());
+    tracing::info!(
+        "Available tools: {:?}",
+        tools.iter().map(|t| &t.name).collect::<Vec<_>>()
+    );
 
     // Test with DeepSeek if available
     let deepseek_client = deepseek::Client::from_env();
     let agent = deepseek_client
-        .agent(deepseek::DEEPSEEK_CHAT)
+        .agent(&Config::settings().provider.model)
         .preamble("You are a helpful assistant with access to Nautilus Trader Rig MCP tools for trading system analysis.")
         .build();
 
-    let res = agent.prompt("Search for authentication vulnerabilities and analyze them").await?;
+    let res = agent
+        .prompt("Search for authentication vulnerabilities and analyze them")
+        .await?;
 
     println!("DeepSeek response: {res}");
 
@@ -0,0 +1,354 @@
+//! Local FastEmbed model bundles
+//!
+//! A bundle is a directory holding a model's ONNX file, its tokenizer files and a
+//! `bundle.json` manifest recording which FastEmbed model it contains and the
+//! SHA-256 of every file. `models import` builds a bundle from a downloaded
+//! snapshot and `models verify` re-checks it. The embedding model is loaded from
+//! the configured bundle; it is only downloaded when offline mode is off.
+
+use anyhow::{anyhow, bail, Context, Result};
+use fastembed::{
+    EmbeddingModel as FastembedModel, InitOptions, InitOptionsUserDefined, ModelInfo, Pooling,
+    TextEmbedding as FastembedTextEmbedding, TokenizerFiles, UserDefinedEmbeddingModel,
+};
+use rig_fastembed::EmbeddingModel;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::OnceCell;
+use tracing::info;
+
+use crate::config::Config;
+
+/// Files FastEmbed needs to load a model without downloading it
+pub const MODEL_FILES: [&str; 5] = [
+    "model.onnx",
+    "tokenizer.json",
+    "config.json",
+    "special_tokens_map.json",
+    "tokenizer_config.json",
+];
+
+/// Manifest written next to the model files
+pub const MANIFEST_FILE: &str = "bundle.json";
+
+/// What a bundle contains and the checksums of its files
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundleManifest {
+    /// FastEmbed model name, e.g. `AllMiniLML6V2`
+    pub model: String,
+    pub model_code: String,
+    pub dimension: usize,
+    pub imported_at: String,
+    /// SHA-256 of each file, keyed by file name
+    pub files: BTreeMap<String, String>,
+}
+
+/// Name of a FastEmbed model as accepted in `embedding.model`
+pub fn model_name(model: &FastembedModel) -> String {
+    format!("{:?}", model)
+}
+
+/// Every model FastEmbed can load
+pub fn supported_models() -> Vec<ModelInfo<FastembedModel>> {
+    FastembedTextEmbedding::list_supported_models()
+}
+
+/// Find a supported model by name (`AllMiniLML6V2`) or code (`Qdrant/all-MiniLM-L6-v2-onnx`)
+pub fn resolve_model(name: &str) -> Result<FastembedModel> {
+    let name = name.trim();
+    supported_models()
+        .into_iter()
+        .find(|info| {
+            model_name(&info.model).eq_ignore_ascii_case(name)
+                || info.model_code.eq_ignore_ascii_case(name)
+        })
+        .map(|info| info.model)
+        .ok_or_else(|| {
+            anyhow!(
+                "unknown FastEmbed model '{}'; `models list` shows the supported ones",
+                name
+            )
+        })
+}
+
+/// Embedding dimension of a model, from FastEmbed's model info
+pub fn model_dimension(model: &FastembedModel) -> Result<usize> {
+    Ok(FastembedTextEmbedding::get_model_info(model)?.dim)
+}
+
+/// Model configured in `embedding.model`
+pub fn configured_model() -> Result<FastembedModel> {
+    resolve_model(&Config::settings().embedding.model)
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Location of `file` in a downloaded snapshot, which may keep the ONNX file in a subdirectory
+fn find_source_file(
+    source: &Path,
+    file: &str,
+    info: &ModelInfo<FastembedModel>,
+) -> Option<PathBuf> {
+    let mut candidates = vec![source.join(file)];
+    if file == "model.onnx" {
+        candidates.push(source.join(&info.model_file));
+    }
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+/// Copy the model files from `source` into `dest` and record their checksums
+///
+/// `source` and `dest` may be the same directory, which only writes the manifest.
+pub fn import_bundle(source: &Path, dest: &Path, model: &FastembedModel) -> Result<BundleManifest> {
+    let info = FastembedTextEmbedding::get_model_info(model)?;
+    let sources: Vec<(&str, Option<PathBuf>)> = MODEL_FILES
+        .iter()
+        .map(|file| (*file, find_source_file(source, file, info)))
+        .collect();
+    let missing: Vec<&str> = sources
+        .iter()
+        .filter(|(_, path)| path.is_none())
+        .map(|(file, _)| *file)
+        .collect();
+    if !missing.is_empty() {
+        bail!("{} is missing {}", source.display(), missing.join(", "));
+    }
+
+    std::fs::create_dir_all(dest)
+        .with_context(|| format!("Failed to create {}", dest.display()))?;
+    let mut files = BTreeMap::new();
+    for (file, path) in sources {
+        let bytes = std::fs::read(path.expect("checked above"))?;
+        let target = dest.join(file);
+        std::fs::write(&target, &bytes)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        files.insert(file.to_string(), format!("{:x}", Sha256::digest(&bytes)));
+    }
+
+    let manifest = BundleManifest {
+        model: model_name(model),
+        model_code: info.model_code.clone(),
+        dimension: info.dim,
+        imported_at: chrono::Utc::now().to_rfc3339(),
+        files,
+    };
+    std::fs::write(
+        dest.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+    Ok(manifest)
+}
+
+/// Read the manifest of a bundle, if it has one
+pub fn read_manifest(dir: &Path) -> Result<Option<BundleManifest>> {
+    let path = dir.join(MANIFEST_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path)?;
+    let manifest = serde_json::from_str(&text)
+        .with_context(|| format!("Invalid bundle manifest {}", path.display()))?;
+    Ok(Some(manifest))
+}
+
+/// Check that a bundle holds `model` and that every file matches its checksum
+pub fn verify_bundle(dir: &Path, model: &FastembedModel) -> Result<BundleManifest> {
+    let manifest = read_manifest(dir)?.ok_or_else(|| {
+        anyhow!(
+            "{} has no {}; run `models import {}` to record checksums",
+            dir.display(),
+            MANIFEST_FILE,
+            dir.display()
+        )
+    })?;
+
+    let mut problems = Vec::new();
+    if manifest.model != model_name(model) {
+        problems.push(format!(
+            "bundle holds {} but embedding.model is {}",
+            manifest.model,
+            model_name(model)
+        ));
+    }
+    for file in MODEL_FILES {
+        let path = dir.join(file);
+        match (manifest.files.get(file), path.is_file()) {
+            (None, _) => problems.push(format!("{} has no recorded checksum", file)),
+            (Some(_), false) => problems.push(format!("{} is missing", file)),
+            (Some(expected), true) => {
+                if &sha256_file(&path)? != expected {
+                    problems.push(format!("{} does not match its checksum", file));
+                }
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(manifest)
+    } else {
+        bail!(
+            "Bundle {} failed verification:\n  - {}",
+            dir.display(),
+            problems.join("\n  - ")
+        )
+    }
+}
+
+/// Load `model` from the files in a bundle directory
+fn load_bundle(dir: &Path, model: &FastembedModel) -> Result<EmbeddingModel> {
+    if let Some(manifest) = read_manifest(dir)? {
+        if manifest.model != model_name(model) {
+            bail!(
+                "Model bundle {} holds {} but embedding.model is {}; import a matching bundle or point embedding.model_dir elsewhere",
+                dir.display(),
+                manifest.model,
+                model_name(model)
+            );
+        }
+    }
+    let info = FastembedTextEmbedding::get_model_info(model)?;
+    let read = |file: &str| {
+        let path = dir.join(file);
+        std::fs::read(&path)
+            .with_context(|| format!("Failed to read model file {}", path.display()))
+    };
+
+    let tokenizer_files = TokenizerFiles {
+        tokenizer_file: read("tokenizer.json")?,
+        config_file: read("config.json")?,
+        special_tokens_map_file: read("special_tokens_map.json")?,
+        tokenizer_config_file: read("tokenizer_config.json")?,
+    };
+    let pooling =
+        FastembedTextEmbedding::get_default_pooling_method(model).unwrap_or(Pooling::Mean);
+    let user_defined_model =
+        UserDefinedEmbeddingModel::new(read("model.onnx")?, tokenizer_files).with_pooling(pooling);
+
+    // rig-fastembed unwraps the session it builds, so build one first to turn a
+    // corrupt bundle into an error instead of a panic
+    FastembedTextEmbedding::try_new_from_user_defined(
+        user_defined_model.clone(),
+        InitOptionsUserDefined::default(),
+    )
+    .with_context(|| format!("Model bundle {} cannot be loaded", dir.display()))?;
+    Ok(EmbeddingModel::new_from_user_defined(
+        user_defined_model,
+        info.dim,
+        info,
+    ))
+}
+
+/// Load the configured embedding model from its bundle, downloading it only when allowed
+pub fn load_embedding_model() -> Result<EmbeddingModel> {
+    load_model(
+        &configured_model()?,
+        Some(&Config::settings().embedding.model_dir),
+    )
+}
+
+/// Load `model` from the bundle in `dir`, downloading it only when allowed
+///
+/// Without a bundle directory the model is always downloaded, unless offline.
+pub fn load_model(model: &FastembedModel, dir: Option<&Path>) -> Result<EmbeddingModel> {
+    let dimension = model_dimension(model)?;
+
+    if let Some(dir) = dir.filter(|dir| dir.exists()) {
+        info!(
+            "Loading FastEmbed model {} ({} dimensions) from {:?}",
+            model_name(model),
+            dimension,
+            dir
+        );
+        return load_bundle(dir, model);
+    }
+    if Config::settings().embedding.offline {
+        match dir {
+            Some(dir) => bail!(
+                "Embedding model {} is not available offline: {} does not exist. Import a downloaded snapshot with \
+                 `models import <snapshot-dir>`, point embedding.model_dir / RIG_EMBEDDING_MODEL_DIR at a bundle, \
+                 or disable embedding.offline to allow downloading",
+                model_name(model),
+                dir.display()
+            ),
+            None => bail!(
+                "Embedding model {} is not available offline: no bundle directory was given for it",
+                model_name(model)
+            ),
+        }
+    }
+
+    info!(
+        "Local model not found, downloading FastEmbed model {}",
+        model_name(model)
+    );
+    // Download and load once here so a failure is an error; rig-fastembed then loads from the cache
+    FastembedTextEmbedding::try_new(
+        InitOptions::new(model.clone()).with_show_download_progress(true),
+    )
+    .with_context(|| format!("Failed to download FastEmbed model {}", model_name(model)))?;
+    Ok(EmbeddingModel::new(model, dimension))
+}
+
+/// Embedding model shared by every vector store in this process, loaded once off the async runtime
+pub async fn shared_embedding_model() -> Result<EmbeddingModel> {
+    static MODEL: OnceCell<EmbeddingModel> = OnceCell::const_new();
+    MODEL
+        .get_or_try_init(|| async { tokio::task::spawn_blocking(load_embedding_model).await? })
+        .await
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_model_by_name_or_code() {
+        let by_name = resolve_model("allminilml6v2").unwrap();
+        let by_code = resolve_model("Qdrant/all-MiniLM-L6-v2-onnx").unwrap();
+        assert_eq!(model_name(&by_name), model_name(&by_code));
+        assert_eq!(model_dimension(&by_name).unwrap(), 384);
+        assert!(resolve_model("word2vec").is_err());
+    }
+
+    #[test]
+    fn test_import_then_verify_detects_tampering() {
+        let model = FastembedModel::AllMiniLML6V2;
+        let snapshot = tempfile::tempdir().unwrap();
+        for file in MODEL_FILES {
+            std::fs::write(snapshot.path().join(file), file.as_bytes()).unwrap();
+        }
+        let bundle = snapshot.path().join("bundle");
+
+        let manifest = import_bundle(snapshot.path(), &bundle, &model).unwrap();
+        assert_eq!(manifest.dimension, 384);
+        assert_eq!(manifest.files.len(), MODEL_FILES.len());
+        assert_eq!(verify_bundle(&bundle, &model).unwrap(), manifest);
+
+        std::fs::write(bundle.join("tokenizer.json"), b"changed").unwrap();
+        let err = verify_bundle(&bundle, &model).unwrap_err().to_string();
+        assert!(err.contains("tokenizer.json does not match"), "{}", err);
+        assert!(!err.contains("config.json"), "{}", err);
+    }
+
+    #[test]
+    fn test_import_reports_missing_files() {
+        let snapshot = tempfile::tempdir().unwrap();
+        std::fs::write(snapshot.path().join("model.onnx"), b"onnx").unwrap();
+        let err = import_bundle(
+            snapshot.path(),
+            &snapshot.path().join("bundle"),
+            &FastembedModel::AllMiniLML6V2,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("tokenizer.json"), "{}", err);
+        assert!(!snapshot.path().join("bundle").exists());
+    }
+}
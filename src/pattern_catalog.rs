@@ -0,0 +1,387 @@
+//! Bug pattern catalog
+//!
+//! Patterns live in `paths.patterns_dir`, one `<id>.json` file per pattern, so the
+//! catalog is reviewed and versioned like code. Each file records the schema
+//! `version` it was written with. The catalog is loaded into the pattern index at
+//! startup, and the MCP pattern tools write their changes back to these files.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::vector_store::BugPatternDocument;
+
+/// Schema version written to new and updated pattern files
+pub const CATALOG_VERSION: u32 = 1;
+
+/// One pattern file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PatternEntry {
+    pub version: u32,
+    /// Unique ID, also the file name; letters, digits, `_` and `-`
+    pub id: String,
+    pub category: String,
+    /// 1 (cosmetic) to 10 (critical)
+    pub severity: u8,
+    pub description: String,
+    /// Short code or behaviour snippets showing the pattern
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+    /// CWE entries, advisories or issues describing the pattern
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    /// Adapter the pattern was seen in; unset for patterns that apply everywhere
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adapter: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Confirmed bugs the pattern was learned from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_bugs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Fields an update may change; unset fields keep their value
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PatternChanges {
+    pub category: Option<String>,
+    pub severity: Option<u8>,
+    pub description: Option<String>,
+    pub examples: Option<Vec<String>>,
+    pub references: Option<Vec<String>>,
+    pub adapter: Option<String>,
+    pub status: Option<String>,
+}
+
+impl PatternEntry {
+    /// New entry at the current schema version, created now
+    pub fn new(id: &str, category: &str, severity: u8, description: &str) -> Self {
+        Self {
+            version: CATALOG_VERSION,
+            id: id.trim().to_string(),
+            category: category.trim().to_uppercase(),
+            severity,
+            description: description.trim().to_string(),
+            examples: Vec::new(),
+            references: Vec::new(),
+            adapter: None,
+            status: None,
+            source_bugs: Vec::new(),
+            created_at: Some(Utc::now()),
+            updated_at: None,
+        }
+    }
+
+    /// Problems that keep this entry out of the catalog
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.version == 0 || self.version > CATALOG_VERSION {
+            problems.push(format!(
+                "version must be between 1 and {}, got {}",
+                CATALOG_VERSION, self.version
+            ));
+        }
+        if !is_valid_id(&self.id) {
+            problems.push(format!(
+                "id '{}' must be non-empty letters, digits, '_' or '-'",
+                self.id
+            ));
+        }
+        if self.category.trim().is_empty() {
+            problems.push("category must not be empty".to_string());
+        }
+        if !(1..=10).contains(&self.severity) {
+            problems.push(format!(
+                "severity must be between 1 and 10, got {}",
+                self.severity
+            ));
+        }
+        if self.description.trim().is_empty() {
+            problems.push("description must not be empty".to_string());
+        }
+        problems
+    }
+
+    fn validate(&self) -> Result<()> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            bail!("Invalid pattern '{}': {}", self.id, problems.join("; "))
+        }
+    }
+
+    /// Apply `changes` and stamp the update at the current schema version
+    pub fn apply(&mut self, changes: PatternChanges) {
+        let non_empty = |value: Option<String>| {
+            value
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        if let Some(category) = non_empty(changes.category) {
+            self.category = category.to_uppercase();
+        }
+        if let Some(severity) = changes.severity {
+            self.severity = severity;
+        }
+        if let Some(description) = non_empty(changes.description) {
+            self.description = description;
+        }
+        if let Some(examples) = changes.examples {
+            self.examples = examples;
+        }
+        if let Some(references) = changes.references {
+            self.references = references;
+        }
+        if let Some(adapter) = changes.adapter {
+            self.adapter = non_empty(Some(adapter));
+        }
+        if let Some(status) = changes.status {
+            self.status = non_empty(Some(status));
+        }
+        self.version = CATALOG_VERSION;
+        self.updated_at = Some(Utc::now());
+    }
+
+    /// Document embedded in the pattern index: the description followed by the examples
+    pub fn to_document(&self) -> BugPatternDocument {
+        let mut content = self.description.clone();
+        if !self.examples.is_empty() {
+            content.push_str("\nExamples:");
+            for example in &self.examples {
+                content.push_str("\n- ");
+                content.push_str(example);
+            }
+        }
+        BugPatternDocument {
+            id: self.id.clone(),
+            category: self.category.clone(),
+            severity: self.severity,
+            content,
+            adapter: self.adapter.clone(),
+            status: self.status.clone(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// Whether `id` is usable as a pattern ID and file name
+pub fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// File holding pattern `id`
+pub fn pattern_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+/// Every pattern in `dir`, sorted by ID; a missing directory is an empty catalog
+pub fn load_catalog(dir: &Path) -> Result<Vec<PatternEntry>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read pattern catalog {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    let mut entries: Vec<PatternEntry> = Vec::new();
+    let mut problems = Vec::new();
+    for path in files {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Ok(serde_json::from_str::<PatternEntry>(&text)?));
+        match parsed {
+            Ok(entry) => {
+                let mut issues = entry.problems();
+                if path
+                    .file_stem()
+                    .is_some_and(|stem| stem != entry.id.as_str())
+                {
+                    issues.push(format!("file name does not match id '{}'", entry.id));
+                }
+                if entries.iter().any(|e| e.id == entry.id) {
+                    issues.push(format!("duplicate id '{}'", entry.id));
+                }
+                if issues.is_empty() {
+                    entries.push(entry);
+                } else {
+                    problems.push(format!("{}: {}", name, issues.join("; ")));
+                }
+            }
+            Err(e) => problems.push(format!("{}: {:#}", name, e)),
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!(
+            "Invalid pattern catalog {}:\n  - {}",
+            dir.display(),
+            problems.join("\n  - ")
+        );
+    }
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(entries)
+}
+
+/// Read pattern `id`, if the catalog has it
+pub fn find_pattern(dir: &Path, id: &str) -> Result<Option<PatternEntry>> {
+    if !is_valid_id(id) {
+        return Ok(None);
+    }
+    let path = pattern_path(dir, id);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path)?;
+    let entry = serde_json::from_str(&text)
+        .with_context(|| format!("Invalid pattern file {}", path.display()))?;
+    Ok(Some(entry))
+}
+
+fn write_pattern(dir: &Path, entry: &PatternEntry) -> Result<()> {
+    entry.validate()?;
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = pattern_path(dir, &entry.id);
+    std::fs::write(&path, format!("{}\n", serde_json::to_string_pretty(entry)?))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Add a new pattern to the catalog
+pub fn create_pattern(dir: &Path, entry: &PatternEntry) -> Result<()> {
+    if find_pattern(dir, &entry.id)?.is_some() {
+        bail!(
+            "Pattern '{}' already exists; use update_pattern to change it",
+            entry.id
+        );
+    }
+    write_pattern(dir, entry)
+}
+
+/// Write a pattern as given, adding it or replacing the stored one
+pub fn save_pattern(dir: &Path, entry: &PatternEntry) -> Result<()> {
+    write_pattern(dir, entry)
+}
+
+/// Change an existing pattern and return it as written
+pub fn update_pattern(dir: &Path, id: &str, changes: PatternChanges) -> Result<PatternEntry> {
+    let Some(mut entry) = find_pattern(dir, id)? else {
+        bail!("Pattern '{}' not found", id);
+    };
+    entry.apply(changes);
+    write_pattern(dir, &entry)?;
+    Ok(entry)
+}
+
+/// Remove a pattern from the catalog and return what it held
+pub fn delete_pattern(dir: &Path, id: &str) -> Result<PatternEntry> {
+    let Some(entry) = find_pattern(dir, id)? else {
+        bail!("Pattern '{}' not found", id);
+    };
+    std::fs::remove_file(pattern_path(dir, id))?;
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_shipped_catalog_is_valid() {
+        let catalog = load_catalog(&Config::manifest_dir().join("patterns")).unwrap();
+        assert_eq!(catalog.len(), 10);
+        assert!(catalog
+            .iter()
+            .all(|entry| entry.version == CATALOG_VERSION && !entry.references.is_empty()));
+    }
+
+    #[test]
+    fn test_crud_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut entry = PatternEntry::new(
+            "okx_lot_size_001",
+            "validation",
+            6,
+            "Order size not rounded to the lot size",
+        );
+        entry.examples = vec!["qty.to_string() sent without rounding".to_string()];
+        create_pattern(dir.path(), &entry).unwrap();
+        assert!(create_pattern(dir.path(), &entry)
+            .unwrap_err()
+            .to_string()
+            .contains("already exists"));
+
+        let changes = PatternChanges {
+            severity: Some(8),
+            adapter: Some("okx".to_string()),
+            ..Default::default()
+        };
+        let updated = update_pattern(dir.path(), "okx_lot_size_001", changes).unwrap();
+        assert_eq!(
+            (updated.severity, updated.category.as_str()),
+            (8, "VALIDATION")
+        );
+        assert!(updated.updated_at.is_some());
+        assert_eq!(load_catalog(dir.path()).unwrap(), vec![updated.clone()]);
+        assert!(updated
+            .to_document()
+            .content
+            .ends_with("Examples:\n- qty.to_string() sent without rounding"));
+
+        let invalid = PatternChanges {
+            severity: Some(11),
+            ..Default::default()
+        };
+        assert!(update_pattern(dir.path(), "okx_lot_size_001", invalid).is_err());
+        assert_eq!(
+            find_pattern(dir.path(), "okx_lot_size_001")
+                .unwrap()
+                .unwrap()
+                .severity,
+            8
+        );
+
+        delete_pattern(dir.path(), "okx_lot_size_001").unwrap();
+        assert!(load_catalog(dir.path()).unwrap().is_empty());
+        assert!(delete_pattern(dir.path(), "../bugs/x").is_err());
+    }
+
+    #[test]
+    fn test_load_reports_every_bad_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = PatternEntry::new("ok_001", "MEMORY", 5, "Leak");
+        std::fs::write(
+            dir.path().join("renamed.json"),
+            serde_json::to_string(&entry).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.json"), "{").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let err = load_catalog(dir.path()).unwrap_err().to_string();
+        assert!(err.contains("broken.json"), "{}", err);
+        assert!(
+            err.contains("renamed.json: file name does not match"),
+            "{}",
+            err
+        );
+        assert!(!err.contains("notes.txt"), "{}", err);
+    }
+}
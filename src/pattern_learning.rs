@@ -0,0 +1,382 @@
+//! Learning bug patterns from confirmed bugs
+//!
+//! Confirmed bugs (see `bugs confirm` and `confirm_critical_bug`) are clustered by
+//! embedding similarity, and the LLM generalizes each cluster into a pattern. The
+//! pattern is written to `<patterns_dir>/proposed/` for review. Accepted proposals
+//! move into the catalog and the pattern index. Rejected ones stay in the proposals
+//! directory so their bugs are not proposed again.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::bug_index;
+use crate::bug_store::{BugFilter, StoredBug, CATEGORY_KEYWORDS};
+use crate::clustering;
+use crate::config::Config;
+use crate::deepseek::{extract_json_object, DeepSeekClient};
+use crate::pattern_catalog::{self, PatternChanges, PatternEntry};
+use crate::vector_store;
+
+/// Subdirectory of the catalog holding proposals awaiting review
+pub const PROPOSALS_DIRECTORY: &str = "proposed";
+
+/// Average cosine similarity at which confirmed bugs count as the same mistake
+pub const CLUSTER_THRESHOLD: f64 = 0.75;
+
+/// Smallest cluster generalized by default; a single bug is too specific to be a pattern
+pub const MIN_CLUSTER_SIZE: usize = 2;
+
+/// Status of a proposal awaiting review
+pub const PROPOSED: &str = "proposed";
+
+/// Status of a proposal a reviewer turned down
+pub const REJECTED: &str = "rejected";
+
+/// System prompt of the pattern generalizer
+const GENERALIZER_PREAMBLE: &str =
+    "You are an expert reviewer of trading system adapters who turns concrete, confirmed bugs \
+     into reusable bug patterns. A pattern describes the class of mistake so that it can be \
+     recognized in other adapters; it never mentions a single file or bug ID.";
+
+/// Lines of each code sample included in the generalization prompt
+const PROMPT_CODE_LINES: usize = 30;
+
+/// Pattern fields the LLM is asked for
+#[derive(Debug, Deserialize)]
+struct GeneralizedPattern {
+    id: String,
+    category: String,
+    severity: u8,
+    description: String,
+    #[serde(default)]
+    examples: Vec<String>,
+    #[serde(default)]
+    references: Vec<String>,
+}
+
+/// What a learning run did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LearnSummary {
+    /// Confirmed bugs not yet covered by a pattern or proposal
+    pub unlearned_bugs: usize,
+    pub clusters: usize,
+    pub proposed: Vec<PatternEntry>,
+    /// Clusters the LLM could not generalize, with the reason
+    pub failed: Vec<String>,
+}
+
+/// Directory of proposals for the catalog in `patterns_dir`
+pub fn proposals_dir(patterns_dir: &Path) -> PathBuf {
+    patterns_dir.join(PROPOSALS_DIRECTORY)
+}
+
+/// Proposals in `patterns_dir`, pending and rejected
+pub fn load_proposals(patterns_dir: &Path) -> Result<Vec<PatternEntry>> {
+    pattern_catalog::load_catalog(&proposals_dir(patterns_dir))
+}
+
+/// Bugs already generalized into a catalog pattern or a proposal
+fn learned_bug_ids(patterns_dir: &Path) -> Result<HashSet<String>> {
+    let mut learned = HashSet::new();
+    for entry in pattern_catalog::load_catalog(patterns_dir)?
+        .into_iter()
+        .chain(load_proposals(patterns_dir)?)
+    {
+        learned.extend(entry.source_bugs);
+    }
+    Ok(learned)
+}
+
+/// Prompt asking the LLM to generalize `bugs` into one pattern
+pub fn generalization_prompt(bugs: &[StoredBug]) -> String {
+    let categories: Vec<&str> = CATEGORY_KEYWORDS
+        .iter()
+        .map(|(category, _)| *category)
+        .collect();
+    let mut prompt = format!(
+        "These {} confirmed bugs were found in Nautilus Trader exchange adapters and look like the same \
+         kind of mistake. Generalize them into one bug pattern that would help recognize the mistake in \
+         other adapters.\n",
+        bugs.len()
+    );
+    for (i, bug) in bugs.iter().enumerate() {
+        prompt.push_str(&format!(
+            "\n## Bug {} ({}, adapter {}, severity {})\n{}\n",
+            i + 1,
+            bug.bug_id,
+            bug.adapter(),
+            bug.severity,
+            bug.description.trim()
+        ));
+        if let Some(code) = bug.code_sample.as_deref().filter(|c| !c.trim().is_empty()) {
+            let excerpt: Vec<&str> = code.lines().take(PROMPT_CODE_LINES).collect();
+            prompt.push_str(&format!("```rust\n{}\n```\n", excerpt.join("\n")));
+        }
+        if let Some(fix) = bug
+            .fix_suggestion
+            .as_deref()
+            .filter(|f| !f.trim().is_empty())
+        {
+            prompt.push_str(&format!("Fix: {}\n", fix.trim()));
+        }
+    }
+    prompt.push_str(&format!(
+        "\nReply with a JSON object with these fields:\n\
+         - id: short snake_case name ending in _001, e.g. \"price_f64_precision_001\"\n\
+         - category: one of {}\n\
+         - severity: 1 (cosmetic) to 10 (critical)\n\
+         - description: one paragraph naming the mistake, why it matters for trading and the symptoms to look for\n\
+         - examples: 1-3 short code or behaviour snippets showing the mistake\n\
+         - references: CWE URLs or other public references, may be empty\n",
+        categories.join(", ")
+    ));
+    prompt
+}
+
+/// Lower-case ID made of letters, digits and `_`
+fn sanitize_id(id: &str) -> String {
+    let id: String = id
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let id = id.trim_matches('_').to_string();
+    if id.is_empty() {
+        "learned_pattern_001".to_string()
+    } else {
+        id
+    }
+}
+
+/// Build a proposal from the LLM reply for `bugs`
+///
+/// The reply may wrap the JSON object in prose or a code fence. The ID is made
+/// unique against `taken`.
+pub fn parse_generalization(
+    reply: &str,
+    bugs: &[StoredBug],
+    taken: &HashSet<String>,
+) -> Result<PatternEntry> {
+    let generalized: GeneralizedPattern = serde_json::from_str(extract_json_object(reply)?)?;
+
+    let base = sanitize_id(&generalized.id);
+    let mut id = base.clone();
+    let mut suffix = 2;
+    while taken.contains(&id) {
+        id = format!("{}_{}", base, suffix);
+        suffix += 1;
+    }
+
+    let mut entry = PatternEntry::new(
+        &id,
+        &generalized.category,
+        generalized.severity.clamp(1, 10),
+        &generalized.description,
+    );
+    entry.examples = generalized
+        .examples
+        .into_iter()
+        .filter(|e| !e.trim().is_empty())
+        .collect();
+    entry.references = generalized
+        .references
+        .into_iter()
+        .filter(|r| !r.trim().is_empty())
+        .collect();
+    entry.status = Some(PROPOSED.to_string());
+    entry.source_bugs = bugs.iter().map(|bug| bug.bug_id.clone()).collect();
+    let adapters: HashSet<String> = bugs.iter().map(StoredBug::adapter).collect();
+    if adapters.len() == 1 {
+        entry.adapter = adapters.into_iter().next();
+    }
+    Ok(entry)
+}
+
+/// Cluster confirmed bugs no pattern covers yet and propose a pattern for each cluster
+pub async fn learn_patterns(
+    client: &DeepSeekClient,
+    min_cluster_size: usize,
+) -> Result<LearnSummary> {
+    let patterns_dir = Config::patterns_directory_path();
+    let learned = learned_bug_ids(&patterns_dir)?;
+
+    let index = bug_index::shared().await?;
+    index
+        .write()
+        .await
+        .sync(&Config::bugs_directory_path())
+        .await?;
+    let (bugs, vectors): (Vec<StoredBug>, Vec<Vec<f64>>) = index
+        .read()
+        .await
+        .vectors(&BugFilter::default())
+        .into_iter()
+        .filter(|(bug, _)| bug.is_confirmed() && !learned.contains(&bug.bug_id))
+        .unzip();
+
+    let clusters: Vec<Vec<usize>> = clustering::agglomerative(&vectors, CLUSTER_THRESHOLD)
+        .into_iter()
+        .filter(|cluster| cluster.len() >= min_cluster_size.max(1))
+        .collect();
+    let mut summary = LearnSummary {
+        unlearned_bugs: bugs.len(),
+        clusters: clusters.len(),
+        ..Default::default()
+    };
+
+    let mut taken: HashSet<String> = pattern_catalog::load_catalog(&patterns_dir)?
+        .into_iter()
+        .chain(load_proposals(&patterns_dir)?)
+        .map(|entry| entry.id)
+        .collect();
+    for cluster in clusters {
+        let members: Vec<StoredBug> = cluster.iter().map(|&i| bugs[i].clone()).collect();
+        let ids: Vec<&str> = members.iter().map(|bug| bug.bug_id.as_str()).collect();
+        let proposal = async {
+            let reply = client
+                .complete_json(GENERALIZER_PREAMBLE, &generalization_prompt(&members))
+                .await?;
+            let entry = parse_generalization(&reply, &members, &taken)?;
+            pattern_catalog::create_pattern(&proposals_dir(&patterns_dir), &entry)?;
+            Ok::<_, anyhow::Error>(entry)
+        }
+        .await;
+        match proposal {
+            Ok(entry) => {
+                info!("🧬 Proposed pattern {} from {}", entry.id, ids.join(", "));
+                taken.insert(entry.id.clone());
+                summary.proposed.push(entry);
+            }
+            Err(e) => {
+                warn!("Failed to generalize {}: {:#}", ids.join(", "), e);
+                summary.failed.push(format!("{}: {:#}", ids.join(", "), e));
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Move a pending proposal into the catalog and the pattern index
+pub async fn accept_proposal(id: &str) -> Result<PatternEntry> {
+    let patterns_dir = Config::patterns_directory_path();
+    let proposals = proposals_dir(&patterns_dir);
+    let Some(mut entry) = pattern_catalog::find_pattern(&proposals, id)? else {
+        bail!("Proposal '{}' not found", id);
+    };
+    if entry.status.as_deref() != Some(PROPOSED) {
+        bail!(
+            "Proposal '{}' is {}, not {}",
+            id,
+            entry.status.as_deref().unwrap_or("unset"),
+            PROPOSED
+        );
+    }
+
+    entry.apply(PatternChanges {
+        status: Some("active".to_string()),
+        ..Default::default()
+    });
+    pattern_catalog::create_pattern(&patterns_dir, &entry)?;
+    pattern_catalog::delete_pattern(&proposals, id)?;
+    vector_store::index_pattern(entry.to_document()).await?;
+    Ok(entry)
+}
+
+/// Mark a pending proposal rejected; its bugs are not proposed again
+pub fn reject_proposal(id: &str) -> Result<PatternEntry> {
+    let proposals = proposals_dir(&Config::patterns_directory_path());
+    match pattern_catalog::find_pattern(&proposals, id)? {
+        Some(entry) if entry.status.as_deref() == Some(PROPOSED) => {}
+        Some(_) => bail!("Proposal '{}' was already reviewed", id),
+        None => bail!("Proposal '{}' not found", id),
+    }
+    let changes = PatternChanges {
+        status: Some(REJECTED.to_string()),
+        ..Default::default()
+    };
+    pattern_catalog::update_pattern(&proposals, id, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bug_store::test_bug;
+
+    /// Bugs reporting the same f64 price parsing, one per `(bug_id, adapter)`
+    fn precision_bugs(ids: &[(&str, &str)]) -> Vec<StoredBug> {
+        ids.iter()
+            .map(|(bug_id, adapter)| {
+                test_bug(serde_json::json!({
+                    "bug_id": bug_id,
+                    "severity": "HIGH",
+                    "description": "Price parsed through f64 loses precision",
+                    "adapter_name": adapter,
+                    "code_sample": "let px: f64 = raw.parse()?;"
+                }))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_prompt_lists_every_bug() {
+        let prompt = generalization_prompt(&precision_bugs(&[("B1", "okx"), ("B2", "bybit")]));
+        assert!(prompt.contains("## Bug 2 (B2, adapter bybit, severity HIGH)"));
+        assert!(prompt.contains("let px: f64"));
+        assert!(prompt.contains("PRECISION"));
+    }
+
+    #[test]
+    fn test_parse_generalization() {
+        let reply = "Here it is:\n```json\n{\"id\": \"Price F64 precision 001\", \"category\": \"precision\", \
+                     \"severity\": 12, \"description\": \"Prices parsed into f64\", \"examples\": [\"raw.parse::<f64>()\", \" \"]}\n```";
+        let taken: HashSet<String> = ["price_f64_precision_001".to_string()].into();
+
+        let same_adapter = precision_bugs(&[("B1", "okx"), ("B2", "okx")]);
+        let entry = parse_generalization(reply, &same_adapter, &taken).unwrap();
+        assert_eq!(entry.id, "price_f64_precision_001_2");
+        assert_eq!((entry.category.as_str(), entry.severity), ("PRECISION", 10));
+        assert_eq!(entry.examples, vec!["raw.parse::<f64>()"]);
+        assert_eq!(entry.source_bugs, vec!["B1", "B2"]);
+        assert_eq!(entry.adapter.as_deref(), Some("okx"));
+        assert_eq!(entry.status.as_deref(), Some(PROPOSED));
+        assert!(entry.problems().is_empty());
+
+        let mixed = parse_generalization(
+            reply,
+            &precision_bugs(&[("B1", "okx"), ("B3", "bybit")]),
+            &HashSet::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            (mixed.id.as_str(), mixed.adapter),
+            ("price_f64_precision_001", None)
+        );
+        assert!(parse_generalization("no pattern here", &same_adapter, &taken).is_err());
+    }
+
+    #[test]
+    fn test_learned_bugs_include_rejected_proposals() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut accepted = PatternEntry::new("accepted_001", "MEMORY", 5, "Leak");
+        accepted.source_bugs = vec!["B1".to_string()];
+        pattern_catalog::create_pattern(dir.path(), &accepted).unwrap();
+        let mut rejected = PatternEntry::new("rejected_001", "MEMORY", 5, "Leak");
+        rejected.source_bugs = vec!["B2".to_string()];
+        rejected.status = Some(REJECTED.to_string());
+        pattern_catalog::create_pattern(&proposals_dir(dir.path()), &rejected).unwrap();
+
+        let learned = learned_bug_ids(dir.path()).unwrap();
+        assert_eq!(learned, ["B1".to_string(), "B2".to_string()].into());
+        assert_eq!(pattern_catalog::load_catalog(dir.path()).unwrap().len(), 1);
+    }
+}
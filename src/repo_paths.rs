@@ -0,0 +1,390 @@
+//! Repository-relative path handling for Nautilus Trader Rig
+//!
+//! Bug and summary records store source paths relative to the nautilus_trader
+//! repository root so they stay valid on any machine or CI host. The repository
+//! root is detected at runtime and used to resolve stored paths when reading.
+
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{debug, info, warn};
+
+/// Environment variable that overrides repository root detection
+pub const REPO_ROOT_ENV: &str = "NAUTILUS_TRADER_ROOT";
+
+/// Marker written into migrated records
+pub const PATH_BASE: &str = "repo_root";
+
+/// Resolve `.` and `..` segments without touching the filesystem
+pub fn lexical_normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            Component::CurDir => {}
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Whether a directory looks like the nautilus_trader repository root
+fn is_repo_root(dir: &Path) -> bool {
+    dir.join("crates").join("adapters").is_dir()
+}
+
+/// Detect the nautilus_trader repository root
+///
+/// Uses `NAUTILUS_TRADER_ROOT` when set, otherwise walks up from the current
+/// directory and this crate's directory looking for `crates/adapters`.
+pub fn detect_repo_root() -> Option<PathBuf> {
+    if let Ok(root) = std::env::var(REPO_ROOT_ENV) {
+        let root = PathBuf::from(root);
+        if is_repo_root(&root) {
+            return Some(lexical_normalize(&root));
+        }
+        warn!(
+            "{} is set but {} has no crates/adapters directory",
+            REPO_ROOT_ENV,
+            root.display()
+        );
+    }
+
+    let starts = [
+        std::env::current_dir().ok(),
+        Some(crate::config::Config::manifest_dir().to_path_buf()),
+    ];
+    starts.into_iter().flatten().find_map(|start| {
+        start
+            .ancestors()
+            .find(|dir| is_repo_root(dir))
+            .map(lexical_normalize)
+    })
+}
+
+/// Repository root, detected once per process
+pub fn repo_root() -> Option<&'static Path> {
+    static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+    ROOT.get_or_init(|| {
+        let root = detect_repo_root();
+        match &root {
+            Some(root) => debug!(
+                "Detected nautilus_trader repository root: {}",
+                root.display()
+            ),
+            None => debug!("nautilus_trader repository root not detected"),
+        }
+        root
+    })
+    .as_deref()
+}
+
+/// Convert a path to a forward-slash string
+fn to_slash_string(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .filter(|c| c != "/")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Express a path relative to the given repository root
+///
+/// Paths outside the root (for example records written on another machine) are
+/// cut at their last `crates/` segment.
+pub fn relative_to(root: Option<&Path>, path: &str) -> String {
+    let normalized = lexical_normalize(Path::new(path));
+
+    if let Some(relative) = root.and_then(|root| normalized.strip_prefix(root).ok()) {
+        return to_slash_string(relative);
+    }
+
+    let parts: Vec<String> = normalized
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    match parts.iter().rposition(|p| p == "crates") {
+        Some(start) => parts[start..].join("/"),
+        None => to_slash_string(&normalized),
+    }
+}
+
+/// Express a path relative to the detected repository root
+pub fn to_repo_relative(path: &str) -> String {
+    relative_to(repo_root(), path)
+}
+
+/// Resolve a stored repository-relative path to an absolute path
+pub fn resolve(relative: &str) -> PathBuf {
+    let path = Path::new(relative);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    match repo_root() {
+        Some(root) => root.join(path),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Rewrite absolute paths in a bug record to repository-relative ones
+///
+/// Returns true when the record was changed.
+pub fn migrate_bug_record(bug: &mut serde_json::Value) -> bool {
+    let Some(details) = bug
+        .get_mut("file_location")
+        .and_then(|l| l.get_mut("details"))
+        .and_then(|d| d.as_object_mut())
+    else {
+        return false;
+    };
+    let Some(absolute) = details
+        .shift_remove("absolute_path")
+        .and_then(|p| p.as_str().map(|s| s.to_string()))
+    else {
+        return false;
+    };
+
+    let relative = to_repo_relative(&absolute);
+    let directory = Path::new(&relative)
+        .parent()
+        .map(to_slash_string)
+        .unwrap_or_default();
+    details.insert(
+        "relative_path".to_string(),
+        serde_json::Value::String(relative),
+    );
+    details.insert(
+        "directory".to_string(),
+        serde_json::Value::String(directory),
+    );
+    details.insert(
+        "path_base".to_string(),
+        serde_json::Value::String(PATH_BASE.to_string()),
+    );
+    true
+}
+
+/// Rewrite absolute paths in an analysis summary to repository-relative ones
+///
+/// Returns true when the summary was changed.
+pub fn migrate_summary_record(summary: &mut serde_json::Value) -> bool {
+    let mut changed = false;
+
+    if let Some(workspace) = summary
+        .get_mut("analysis_summary")
+        .and_then(|s| s.get_mut("workspace_info"))
+        .and_then(|w| w.as_object_mut())
+    {
+        if let Some(path) = workspace
+            .get("adapters_path")
+            .and_then(|p| p.as_str())
+            .map(str::to_string)
+        {
+            if Path::new(&path).is_absolute() {
+                let relative = to_repo_relative(&path);
+                workspace.insert(
+                    "adapters_path".to_string(),
+                    serde_json::Value::String(relative),
+                );
+                workspace.insert(
+                    "path_base".to_string(),
+                    serde_json::Value::String(PATH_BASE.to_string()),
+                );
+                changed = true;
+            }
+        }
+    }
+
+    if let Some(results) = summary
+        .get_mut("file_results")
+        .and_then(|r| r.as_array_mut())
+    {
+        for result in results {
+            if let Some(path) = result
+                .get("file_path")
+                .and_then(|p| p.as_str())
+                .map(str::to_string)
+            {
+                if Path::new(&path).is_absolute() {
+                    result["file_path"] = serde_json::Value::String(to_repo_relative(&path));
+                    changed = true;
+                }
+            }
+            if let Some(bug_file) = result
+                .get("bug_file")
+                .and_then(|p| p.as_str())
+                .map(str::to_string)
+            {
+                if Path::new(&bug_file).is_absolute() {
+                    let name = Path::new(&bug_file)
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string();
+                    result["bug_file"] = serde_json::Value::String(name);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    changed
+}
+
+/// One-time migration of every record in the bugs directory to repository-relative paths
+///
+/// Returns the number of files rewritten.
+pub async fn migrate_bugs_directory(bugs_dir: &Path) -> Result<usize> {
+    let mut migrated = 0;
+    let mut entries = tokio::fs::read_dir(bugs_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+
+        let content = tokio::fs::read_to_string(&path).await?;
+        let mut record: serde_json::Value = match serde_json::from_str(&content) {
+            Ok(record) => record,
+            Err(e) => {
+                warn!("Skipping unparseable record {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        let original = record.clone();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let changed = if name.starts_with("analysis_summary_") {
+            migrate_summary_record(&mut record)
+        } else {
+            migrate_bug_record(&mut record)
+        };
+        if !changed || record == original {
+            continue;
+        }
+
+        // Keys stay in file order and a trailing newline is kept, so a diff shows only the paths
+        let mut migrated_content = serde_json::to_string_pretty(&record)?;
+        if content.ends_with('\n') {
+            migrated_content.push('\n');
+        }
+        tokio::fs::write(&path, migrated_content).await?;
+        debug!("Migrated paths in {}", path.display());
+        migrated += 1;
+    }
+
+    info!(
+        "Migrated {} records in {} to repository-relative paths",
+        migrated,
+        bugs_dir.display()
+    );
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_to_detected_root() {
+        let root = Path::new("/work/nautilus_trader");
+        assert_eq!(
+            relative_to(
+                Some(root),
+                "/work/nautilus_trader/nautilus-trader-rig/../crates/adapters/okx/src/lib.rs"
+            ),
+            "crates/adapters/okx/src/lib.rs"
+        );
+    }
+
+    #[test]
+    fn test_relative_to_foreign_machine_path() {
+        let root = Path::new("/home/ci/nautilus_trader");
+        assert_eq!(
+            relative_to(
+                Some(root),
+                "/Users/vadimnicolai/Public/trading/nautilus_trader/nautilus-trader-rig/../crates/adapters/tardis/src/enums.rs"
+            ),
+            "crates/adapters/tardis/src/enums.rs"
+        );
+        assert_eq!(
+            relative_to(None, "../crates/adapters/okx/src/lib.rs"),
+            "crates/adapters/okx/src/lib.rs"
+        );
+    }
+
+    #[test]
+    fn test_migrate_bug_record() {
+        let mut bug = serde_json::json!({
+            "bug_id": "B1",
+            "file_location": {
+                "details": {
+                    "absolute_path": "/Users/x/nautilus_trader/nautilus-trader-rig/../crates/adapters/okx/src/lib.rs",
+                    "directory": "/Users/x/nautilus_trader/nautilus-trader-rig/../crates/adapters/okx/src",
+                    "relative_path": "../crates/adapters/okx/src/lib.rs"
+                }
+            }
+        });
+        assert!(migrate_bug_record(&mut bug));
+        let details = &bug["file_location"]["details"];
+        assert!(details.get("absolute_path").is_none());
+        assert_eq!(details["relative_path"], "crates/adapters/okx/src/lib.rs");
+        assert_eq!(details["directory"], "crates/adapters/okx/src");
+        assert!(!migrate_bug_record(&mut bug));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_bugs_directory_keeps_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let migrated_path = dir.path().join("AUTO_BUG_B1.json");
+        let untouched_path = dir.path().join("AUTO_BUG_B2.json");
+        // Keys out of alphabetical order and a trailing newline, as other tools write them
+        let absolute = concat!(
+            "{\n",
+            "  \"severity\": \"HIGH\",\n",
+            "  \"bug_id\": \"B1\",\n",
+            "  \"file_location\": {\n",
+            "    \"details\": {\n",
+            "      \"absolute_path\": \"/Users/x/nautilus_trader/crates/adapters/okx/src/lib.rs\",\n",
+            "      \"relative_path\": \"crates/adapters/okx/src/lib.rs\",\n",
+            "      \"filename\": \"lib.rs\",\n",
+            "      \"directory\": \"/Users/x/nautilus_trader/crates/adapters/okx/src\"\n",
+            "    }\n",
+            "  }\n",
+            "}\n"
+        );
+        tokio::fs::write(&migrated_path, absolute).await.unwrap();
+        let relative = "{\n  \"bug_id\": \"B2\", \"file_location\": {\"details\": {\"relative_path\": \"crates/adapters/okx/src/lib.rs\"}}}";
+        tokio::fs::write(&untouched_path, relative).await.unwrap();
+
+        assert_eq!(migrate_bugs_directory(dir.path()).await.unwrap(), 1);
+        assert_eq!(
+            tokio::fs::read_to_string(&untouched_path).await.unwrap(),
+            relative
+        );
+
+        let expected = concat!(
+            "{\n",
+            "  \"severity\": \"HIGH\",\n",
+            "  \"bug_id\": \"B1\",\n",
+            "  \"file_location\": {\n",
+            "    \"details\": {\n",
+            "      \"relative_path\": \"crates/adapters/okx/src/lib.rs\",\n",
+            "      \"filename\": \"lib.rs\",\n",
+            "      \"directory\": \"crates/adapters/okx/src\",\n",
+            "      \"path_base\": \"repo_root\"\n",
+            "    }\n",
+            "  }\n",
+            "}\n"
+        );
+        assert_eq!(
+            tokio::fs::read_to_string(&migrated_path).await.unwrap(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,440 @@
+//! Retrieval quality evaluation
+//!
+//! `eval/queries.json` holds labelled queries: each query lists the pattern or
+//! bug IDs a good search returns. The harness runs every query against the
+//! pattern catalog and the stored bugs for each embedding model and search mode,
+//! and reports recall@k, MRR and nDCG@k, so model swaps and search changes are
+//! judged on numbers rather than on single spot checks.
+//!
+//! ```text
+//! recall@k = |relevant ∩ top k| / |relevant|
+//! MRR      = mean of 1 / rank of the first relevant hit in the top k (0 when none)
+//! nDCG@k   = Σ_{relevant hits} 1 / log2(rank + 1), divided by the same sum for a perfect ranking
+//! ```
+
+use anyhow::{anyhow, bail, Context, Result};
+use fastembed::EmbeddingModel as FastembedModel;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::bug_index::BugIndex;
+use crate::bug_store::BugFilter;
+use crate::config::Config;
+use crate::hybrid::{self, SearchMode};
+use crate::models;
+use crate::vector_store::{PatternFilter, VectorStoreManager};
+
+/// Labelled query set checked into the repository
+pub const QUERIES_FILE: &str = "eval/queries.json";
+
+/// Cut-off used for recall, MRR and nDCG unless `--k` is given
+pub const DEFAULT_K: usize = 5;
+
+/// A query and the IDs a good search returns for it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LabeledQuery {
+    pub query: String,
+    pub relevant: Vec<String>,
+}
+
+/// Labelled queries against the pattern catalog and the stored bugs
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuerySet {
+    #[serde(default)]
+    pub patterns: Vec<LabeledQuery>,
+    #[serde(default)]
+    pub bugs: Vec<LabeledQuery>,
+}
+
+impl QuerySet {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read query set {}", path.display()))?;
+        let set: QuerySet = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid query set {}", path.display()))?;
+        let problems: Vec<String> = set
+            .patterns
+            .iter()
+            .chain(&set.bugs)
+            .filter(|q| q.query.trim().is_empty() || q.relevant.is_empty())
+            .map(|q| format!("'{}' needs a query and at least one relevant ID", q.query))
+            .collect();
+        if !problems.is_empty() {
+            bail!(
+                "Invalid query set {}:\n  - {}",
+                path.display(),
+                problems.join("\n  - ")
+            );
+        }
+        Ok(set)
+    }
+}
+
+/// A search mode, optionally followed by the configured cross-encoder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalMode {
+    pub search: SearchMode,
+    pub rerank: bool,
+}
+
+impl EvalMode {
+    pub fn name(&self) -> String {
+        if self.rerank {
+            format!("{}+rerank", self.search.name())
+        } else {
+            self.search.name().to_string()
+        }
+    }
+
+    /// `vector`, `lexical`, `hybrid`, or any of them followed by `+rerank`
+    pub fn parse(name: &str) -> Result<Self> {
+        let (search, rerank) = match name.trim().strip_suffix("+rerank") {
+            Some(search) => (search, true),
+            None => (name, false),
+        };
+        Ok(Self {
+            search: SearchMode::parse(search)?,
+            rerank,
+        })
+    }
+
+    /// Every plain mode, plus hybrid with re-ranking when `embedding.reranker` is set
+    pub fn defaults() -> Vec<Self> {
+        let mut modes: Vec<Self> = SearchMode::ALL
+            .into_iter()
+            .map(|search| Self {
+                search,
+                rerank: false,
+            })
+            .collect();
+        if hybrid::reranker_enabled(&Config::settings().embedding.reranker) {
+            modes.push(Self {
+                search: SearchMode::Hybrid,
+                rerank: true,
+            });
+        }
+        modes
+    }
+}
+
+/// Embedding model to evaluate and the bundle it is loaded from
+#[derive(Debug, Clone)]
+pub struct ModelSpec {
+    pub model: FastembedModel,
+    pub dir: Option<PathBuf>,
+}
+
+impl ModelSpec {
+    /// `embedding.model` loaded from `embedding.model_dir`
+    pub fn configured() -> Result<Self> {
+        Ok(Self {
+            model: models::configured_model()?,
+            dir: Some(Config::settings().embedding.model_dir.clone()),
+        })
+    }
+
+    /// `<name>` or `<name>=<bundle-dir>`; the configured model defaults to `embedding.model_dir`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, dir) = match spec.split_once('=') {
+            Some((name, dir)) => (name, Some(PathBuf::from(dir))),
+            None => (spec, None),
+        };
+        let model = models::resolve_model(name)?;
+        let dir = dir.or_else(|| {
+            let configured = models::configured_model().ok()?;
+            (models::model_name(&configured) == models::model_name(&model))
+                .then(|| Config::settings().embedding.model_dir.clone())
+        });
+        Ok(Self { model, dir })
+    }
+}
+
+/// Metrics of one model and mode over one corpus
+#[derive(Debug, Clone, Serialize)]
+pub struct ModeMetrics {
+    pub model: String,
+    pub corpus: String,
+    pub mode: String,
+    pub queries: usize,
+    pub recall: f64,
+    pub mrr: f64,
+    pub ndcg: f64,
+}
+
+/// Metrics of every model and mode evaluated
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub k: usize,
+    pub results: Vec<ModeMetrics>,
+    /// Labelled IDs missing from the corpus, which no search can find
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unknown_ids: Vec<String>,
+}
+
+impl EvalReport {
+    pub fn to_table(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{:<24} {:<9} {:<15} {:>7} {:>9} {:>7} {:>9}",
+            "MODEL",
+            "CORPUS",
+            "MODE",
+            "QUERIES",
+            format!("RECALL@{}", self.k),
+            "MRR",
+            format!("NDCG@{}", self.k)
+        );
+        for result in &self.results {
+            let _ = writeln!(
+                out,
+                "{:<24} {:<9} {:<15} {:>7} {:>9.3} {:>7.3} {:>9.3}",
+                result.model,
+                result.corpus,
+                result.mode,
+                result.queries,
+                result.recall,
+                result.mrr,
+                result.ndcg
+            );
+        }
+        if !self.unknown_ids.is_empty() {
+            let _ = writeln!(
+                out,
+                "\n⚠️ Labelled IDs not in the corpus: {}",
+                self.unknown_ids.join(", ")
+            );
+        }
+        out
+    }
+}
+
+/// Share of `relevant` found in the first `k` of `ranked`
+pub fn recall_at_k(ranked: &[String], relevant: &[String], k: usize) -> f64 {
+    if relevant.is_empty() {
+        return 0.0;
+    }
+    let found = ranked
+        .iter()
+        .take(k)
+        .filter(|id| relevant.contains(id))
+        .count();
+    found as f64 / relevant.len() as f64
+}
+
+/// 1 / rank of the first relevant ID in the first `k` of `ranked`, or 0
+pub fn reciprocal_rank(ranked: &[String], relevant: &[String], k: usize) -> f64 {
+    ranked
+        .iter()
+        .take(k)
+        .position(|id| relevant.contains(id))
+        .map_or(0.0, |i| 1.0 / (i + 1) as f64)
+}
+
+/// Normalized discounted cumulative gain of the first `k` of `ranked`, with binary relevance
+pub fn ndcg_at_k(ranked: &[String], relevant: &[String], k: usize) -> f64 {
+    let gain = |i: usize| 1.0 / ((i + 2) as f64).log2();
+    let dcg: f64 = ranked
+        .iter()
+        .take(k)
+        .enumerate()
+        .filter(|(_, id)| relevant.contains(id))
+        .map(|(i, _)| gain(i))
+        .sum();
+    let ideal: f64 = (0..relevant.len().min(k)).map(gain).sum();
+    if ideal == 0.0 {
+        0.0
+    } else {
+        dcg / ideal
+    }
+}
+
+/// Average the metrics over `(ranking, query)` pairs
+fn summarize(
+    model: &str,
+    corpus: &str,
+    mode: EvalMode,
+    runs: &[(Vec<String>, &LabeledQuery)],
+    k: usize,
+) -> ModeMetrics {
+    let mean = |metric: fn(&[String], &[String], usize) -> f64| {
+        if runs.is_empty() {
+            return 0.0;
+        }
+        runs.iter()
+            .map(|(ranked, q)| metric(ranked, &q.relevant, k))
+            .sum::<f64>()
+            / runs.len() as f64
+    };
+    ModeMetrics {
+        model: model.to_string(),
+        corpus: corpus.to_string(),
+        mode: mode.name(),
+        queries: runs.len(),
+        recall: mean(recall_at_k),
+        mrr: mean(reciprocal_rank),
+        ndcg: mean(ndcg_at_k),
+    }
+}
+
+/// Labelled IDs of `queries` not in `known`
+fn unknown_ids(queries: &[LabeledQuery], known: &HashSet<String>) -> Vec<String> {
+    let mut unknown: Vec<String> = queries
+        .iter()
+        .flat_map(|q| &q.relevant)
+        .filter(|id| !known.contains(*id))
+        .cloned()
+        .collect();
+    unknown.sort();
+    unknown.dedup();
+    unknown
+}
+
+/// Run every query of `set` for each model and mode and score the top `k` results
+///
+/// Patterns come from `paths.patterns_dir` and bugs from `paths.bugs_dir`.
+pub async fn evaluate(
+    set: &QuerySet,
+    specs: &[ModelSpec],
+    modes: &[EvalMode],
+    k: usize,
+) -> Result<EvalReport> {
+    if k == 0 {
+        return Err(anyhow!("k must be at least 1"));
+    }
+    let mut report = EvalReport {
+        k,
+        results: Vec::new(),
+        unknown_ids: Vec::new(),
+    };
+
+    for (n, spec) in specs.iter().enumerate() {
+        let name = models::model_name(&spec.model);
+        info!(
+            "📏 Evaluating {} on {} pattern and {} bug queries",
+            name,
+            set.patterns.len(),
+            set.bugs.len()
+        );
+        let (model, dir) = (spec.model.clone(), spec.dir.clone());
+        let embedding_model =
+            tokio::task::spawn_blocking(move || models::load_model(&model, dir.as_deref()))
+                .await??;
+
+        if !set.patterns.is_empty() {
+            let store = VectorStoreManager::with_model(embedding_model.clone()).await?;
+            if n == 0 {
+                let known = store.documents.iter().map(|p| p.id.clone()).collect();
+                report
+                    .unknown_ids
+                    .extend(unknown_ids(&set.patterns, &known));
+            }
+            for &mode in modes {
+                let mut runs = Vec::with_capacity(set.patterns.len());
+                for q in &set.patterns {
+                    let ranked = store
+                        .ranked_search(
+                            &q.query,
+                            &PatternFilter::default(),
+                            k,
+                            mode.search,
+                            mode.rerank,
+                        )
+                        .await?;
+                    runs.push((ranked.into_iter().map(|(id, _)| id).collect(), q));
+                }
+                report
+                    .results
+                    .push(summarize(&name, "patterns", mode, &runs, k));
+            }
+        }
+
+        if !set.bugs.is_empty() {
+            let mut index = BugIndex::new(embedding_model)?;
+            index.sync(&Config::bugs_directory_path()).await?;
+            if n == 0 {
+                let known = index
+                    .vectors(&BugFilter::default())
+                    .into_iter()
+                    .map(|(bug, _)| bug.bug_id)
+                    .collect();
+                report.unknown_ids.extend(unknown_ids(&set.bugs, &known));
+            }
+            for &mode in modes {
+                let mut runs = Vec::with_capacity(set.bugs.len());
+                for q in &set.bugs {
+                    let ranked = index
+                        .ranked_search(&q.query, &BugFilter::default(), k, mode.search, mode.rerank)
+                        .await?;
+                    runs.push((ranked.into_iter().map(|(id, _)| id).collect(), q));
+                }
+                report
+                    .results
+                    .push(summarize(&name, "bugs", mode, &runs, k));
+            }
+        }
+    }
+
+    if !report.unknown_ids.is_empty() {
+        warn!(
+            "Query set labels IDs missing from the corpus: {}",
+            report.unknown_ids.join(", ")
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bug_store, pattern_catalog};
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_metrics() {
+        let ranked = ids(&["x", "a", "y", "b"]);
+        let relevant = ids(&["a", "b"]);
+        assert_eq!(recall_at_k(&ranked, &relevant, 2), 0.5);
+        assert_eq!(recall_at_k(&ranked, &relevant, 4), 1.0);
+        assert_eq!(reciprocal_rank(&ranked, &relevant, 4), 0.5);
+        assert_eq!(reciprocal_rank(&ranked, &relevant, 1), 0.0);
+
+        let expected = (1.0 / 3f64.log2() + 1.0 / 5f64.log2()) / (1.0 + 1.0 / 3f64.log2());
+        assert!((ndcg_at_k(&ranked, &relevant, 4) - expected).abs() < 1e-12);
+        assert_eq!(ndcg_at_k(&ids(&["a", "b"]), &relevant, 2), 1.0);
+        assert_eq!(ndcg_at_k(&[], &relevant, 5), 0.0);
+    }
+
+    #[test]
+    fn test_mode_names_round_trip() {
+        for name in ["vector", "lexical", "hybrid", "hybrid+rerank"] {
+            assert_eq!(EvalMode::parse(name).unwrap().name(), name);
+        }
+        assert!(EvalMode::parse("rerank").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shipped_query_set_labels_exist() {
+        let set = QuerySet::load(&Config::manifest_dir().join(QUERIES_FILE)).unwrap();
+        assert!(!set.patterns.is_empty() && !set.bugs.is_empty());
+
+        let patterns =
+            pattern_catalog::load_catalog(&Config::manifest_dir().join("patterns")).unwrap();
+        let known = patterns.into_iter().map(|p| p.id).collect();
+        assert_eq!(unknown_ids(&set.patterns, &known), Vec::<String>::new());
+
+        let bugs = bug_store::load_bugs(&Config::manifest_dir().join("bugs"))
+            .await
+            .unwrap();
+        let known = bugs.into_iter().map(|(_, bug)| bug.bug_id).collect();
+        assert_eq!(unknown_ids(&set.bugs, &known), Vec::<String>::new());
+    }
+}
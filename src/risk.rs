@@ -0,0 +1,449 @@
+//! Per-adapter risk scoring
+//!
+//! Ranks adapters by how urgently they need attention. The score combines the
+//! active bugs of an adapter (weighted by severity, confirmation and age), how
+//! much its code has churned recently in git, and the share of its files whose
+//! analysis failed in the run. Scores are persisted per run as
+//! `bugs/adapter_risk_{run_id}.json` so trends can be followed between runs.
+//!
+//! ```text
+//! bug_points = Σ severity_weight × confirmation_factor × (1 + min(age_days, 90) / 90)
+//! score      = bug_points × (1 + 0.5 × churn / max_churn) + 10 × failed_share
+//! ```
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use crate::bug_store::{self, StoredBug};
+use crate::html_report::report_status;
+use crate::repo_paths;
+use crate::run_diff;
+
+/// Multiplier for bugs confirmed by a reviewer or confirmation pass
+const CONFIRMED_MULTIPLIER: f64 = 1.5;
+
+/// Age in days at which a bug's age factor stops growing
+const AGE_CAP_DAYS: f64 = 90.0;
+
+/// Weight of relative git churn on top of the bug points
+const CHURN_WEIGHT: f64 = 0.5;
+
+/// Points added for an adapter whose files all failed analysis
+const FAILURE_WEIGHT: f64 = 10.0;
+
+/// Window of git history counted as churn
+pub const CHURN_WINDOW_DAYS: u32 = 90;
+
+/// Weight of a single bug by severity
+pub fn severity_weight(severity: &str) -> f64 {
+    match bug_store::severity_rank(severity) {
+        4 => 10.0,
+        3 => 5.0,
+        2 => 2.0,
+        _ => 1.0,
+    }
+}
+
+/// Risk score and its inputs for one adapter
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdapterRisk {
+    pub adapter: String,
+    pub score: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_score: Option<f64>,
+    pub active_bugs: usize,
+    pub confirmed_bugs: usize,
+    pub bug_points: f64,
+    pub oldest_bug_days: u64,
+    pub churn: usize,
+    pub files_analyzed: usize,
+    pub failed_files: usize,
+    pub failed_share: f64,
+}
+
+impl AdapterRisk {
+    /// Change in score since the previous persisted report
+    pub fn trend(&self) -> Option<f64> {
+        self.previous_score
+            .map(|previous| round2(self.score - previous))
+    }
+}
+
+/// Risk scores of every adapter for one run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskReport {
+    pub run_id: String,
+    pub generated_at: String,
+    pub churn_window_days: u32,
+    pub adapters: Vec<AdapterRisk>,
+}
+
+impl RiskReport {
+    /// Render the report as a plain-text table, highest risk first
+    pub fn to_table(&self) -> String {
+        let mut out = format!("Adapter risk for run {}\n\n", self.run_id);
+        let _ = writeln!(
+            out,
+            "{:<16} {:>8} {:>8} {:>7} {:>10} {:>9} {:>7} {:>12}",
+            "ADAPTER", "SCORE", "TREND", "ACTIVE", "CONFIRMED", "AGE (d)", "CHURN", "FAILED"
+        );
+        for risk in &self.adapters {
+            let trend = match risk.trend() {
+                Some(delta) => format!("{:+.2}", delta),
+                None => "-".to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "{:<16} {:>8.2} {:>8} {:>7} {:>10} {:>9} {:>7} {:>12}",
+                risk.adapter,
+                risk.score,
+                trend,
+                risk.active_bugs,
+                risk.confirmed_bugs,
+                risk.oldest_bug_days,
+                risk.churn,
+                format!("{}/{}", risk.failed_files, risk.files_analyzed),
+            );
+        }
+        if self.adapters.is_empty() {
+            out.push_str("No adapters with bugs or analyzed files.\n");
+        }
+        out
+    }
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Score adapters from stored bugs, a run summary and git churn counts
+pub fn score_adapters(
+    bugs: &[StoredBug],
+    summary: Option<&Value>,
+    churn: &HashMap<String, usize>,
+    now: DateTime<Utc>,
+) -> Vec<AdapterRisk> {
+    let mut adapters: BTreeMap<String, AdapterRisk> = BTreeMap::new();
+
+    for bug in bugs.iter().filter(|bug| bug.status.is_active()) {
+        let risk = adapters.entry(bug.adapter()).or_default();
+        let age_days = bug
+            .recorded_at()
+            .map(|recorded| (now - recorded).num_days().max(0) as u64)
+            .unwrap_or(0);
+        let confirmation = if bug.is_confirmed() {
+            CONFIRMED_MULTIPLIER
+        } else {
+            1.0
+        };
+        let age_factor = 1.0 + (age_days as f64).min(AGE_CAP_DAYS) / AGE_CAP_DAYS;
+
+        risk.active_bugs += 1;
+        risk.confirmed_bugs += bug.is_confirmed() as usize;
+        risk.oldest_bug_days = risk.oldest_bug_days.max(age_days);
+        risk.bug_points += severity_weight(&bug.severity) * confirmation * age_factor;
+    }
+
+    let results = summary
+        .and_then(|summary| summary["file_results"].as_array())
+        .map(|r| r.as_slice())
+        .unwrap_or(&[]);
+    for result in results {
+        let path = repo_paths::to_repo_relative(result["file_path"].as_str().unwrap_or(""));
+        let Some(adapter) = bug_store::adapter_from_path(&path) else {
+            continue;
+        };
+        let risk = adapters.entry(adapter).or_default();
+        risk.files_analyzed += 1;
+        if report_status(result["status"].as_str().unwrap_or("")) == "analysis_failed" {
+            risk.failed_files += 1;
+        }
+    }
+
+    let max_churn = churn.values().copied().max().unwrap_or(0);
+    let mut scored: Vec<AdapterRisk> = adapters
+        .into_iter()
+        .map(|(adapter, mut risk)| {
+            risk.churn = churn.get(&adapter).copied().unwrap_or(0);
+            risk.failed_share = if risk.files_analyzed > 0 {
+                risk.failed_files as f64 / risk.files_analyzed as f64
+            } else {
+                0.0
+            };
+            let churn_factor = if max_churn > 0 {
+                1.0 + CHURN_WEIGHT * risk.churn as f64 / max_churn as f64
+            } else {
+                1.0
+            };
+            risk.score =
+                round2(risk.bug_points * churn_factor + FAILURE_WEIGHT * risk.failed_share);
+            risk.bug_points = round2(risk.bug_points);
+            risk.failed_share = round2(risk.failed_share);
+            risk.adapter = adapter;
+            risk
+        })
+        .collect();
+
+    scored.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.adapter.cmp(&b.adapter))
+    });
+    scored
+}
+
+/// Count file changes per adapter in the git history of the repository
+///
+/// Returns an empty map when the repository root is unknown or git is unavailable.
+pub async fn git_churn(window_days: u32) -> HashMap<String, usize> {
+    let mut churn = HashMap::new();
+    let Some(root) = repo_paths::repo_root() else {
+        debug!("Repository root unknown, skipping git churn");
+        return churn;
+    };
+
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args([
+            "log",
+            &format!("--since={}.days", window_days),
+            "--name-only",
+            "--pretty=format:",
+            "--",
+            "crates/adapters",
+        ])
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => {
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                if let Some(adapter) = bug_store::adapter_from_path(line.trim()) {
+                    *churn.entry(adapter).or_insert(0) += 1;
+                }
+            }
+        }
+        Ok(output) => warn!(
+            "git log failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => warn!("Failed to run git for churn: {}", e),
+    }
+    churn
+}
+
+/// Path of the persisted risk report for a run
+pub fn report_path(bugs_dir: &Path, run_id: &str) -> PathBuf {
+    bugs_dir.join(format!("adapter_risk_{}.json", run_id))
+}
+
+/// List persisted risk reports, oldest first
+pub fn list_reports(bugs_dir: &Path) -> Vec<PathBuf> {
+    let mut reports: Vec<PathBuf> = std::fs::read_dir(bugs_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    name.starts_with("adapter_risk_") && name.ends_with(".json")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    reports.sort();
+    reports
+}
+
+/// Load the most recent persisted report of a run other than `run_id`
+async fn previous_report(bugs_dir: &Path, run_id: &str) -> Option<RiskReport> {
+    let current = report_path(bugs_dir, run_id);
+    let path = list_reports(bugs_dir)
+        .into_iter()
+        .rev()
+        .find(|path| *path < current)?;
+    let content = tokio::fs::read_to_string(&path).await.ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Run ID and summary for `run`, which accepts anything `run_diff::resolve_summary` does
+///
+/// When no summary exists yet, `latest` resolves to run ID `adhoc` without a summary.
+async fn resolve_run(bugs_dir: &Path, run: &str) -> Result<(String, Option<Value>)> {
+    match run_diff::resolve_summary(bugs_dir, run) {
+        Ok(path) => {
+            let summary: Value = serde_json::from_str(&tokio::fs::read_to_string(&path).await?)?;
+            let run_id = summary["analysis_summary"]["run_id"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| run_diff::run_id_from_path(&path));
+            Ok((run_id, Some(summary)))
+        }
+        Err(e) if run == "latest" => {
+            debug!("No run summary available, scoring stored bugs only: {}", e);
+            Ok(("adhoc".to_string(), None))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Score the adapters from today's bugs and churn, comparing against the previous report
+async fn compute(bugs_dir: &Path, run_id: String, summary: Option<&Value>) -> Result<RiskReport> {
+    let bugs: Vec<StoredBug> = bug_store::load_bugs(bugs_dir)
+        .await?
+        .into_iter()
+        .map(|(_, bug)| bug)
+        .collect();
+    let churn = git_churn(CHURN_WINDOW_DAYS).await;
+    let mut adapters = score_adapters(&bugs, summary, &churn, Utc::now());
+
+    if let Some(previous) = previous_report(bugs_dir, &run_id).await {
+        for risk in &mut adapters {
+            risk.previous_score = previous
+                .adapters
+                .iter()
+                .find(|p| p.adapter == risk.adapter)
+                .map(|p| p.score);
+        }
+    }
+
+    Ok(RiskReport {
+        run_id,
+        generated_at: Utc::now().to_rfc3339(),
+        churn_window_days: CHURN_WINDOW_DAYS,
+        adapters,
+    })
+}
+
+/// Compute and persist the risk report at the end of a run
+///
+/// Only the run that just finished is scored this way: the scores depend on the
+/// bugs and git history of the moment, so a past run's report is never rewritten.
+pub async fn generate(bugs_dir: &Path, run: &str) -> Result<RiskReport> {
+    let (run_id, summary) = resolve_run(bugs_dir, run).await?;
+    let report = compute(bugs_dir, run_id, summary.as_ref()).await?;
+
+    if report.run_id != "adhoc" {
+        let path = report_path(bugs_dir, &report.run_id);
+        tokio::fs::write(&path, serde_json::to_string_pretty(&report)?).await?;
+        info!("Adapter risk report saved: {}", path.display());
+    }
+    Ok(report)
+}
+
+/// Risk report of a run as persisted when it finished
+///
+/// Runs without a persisted report, such as those from before risk scoring, are
+/// scored from the current bugs without writing anything.
+pub async fn load_or_compute(bugs_dir: &Path, run: &str) -> Result<RiskReport> {
+    let (run_id, summary) = resolve_run(bugs_dir, run).await?;
+    let path = report_path(bugs_dir, &run_id);
+    if run_id != "adhoc" {
+        if let Ok(content) = tokio::fs::read_to_string(&path).await {
+            return Ok(serde_json::from_str(&content)?);
+        }
+    }
+    debug!("No persisted risk report for run {}, computing it", run_id);
+    compute(bugs_dir, run_id, summary.as_ref()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bug_store::test_bug;
+    use serde_json::json;
+
+    #[test]
+    fn test_score_adapters() {
+        let now = chrono::NaiveDateTime::parse_from_str("20250901_000000", "%Y%m%d_%H%M%S")
+            .unwrap()
+            .and_utc();
+        let bugs = vec![
+            test_bug(json!({
+                "bug_id": "B1", "severity": "HIGH", "status": "open", "confirmed": true,
+                "timestamp": "20250601_000000",
+                "file_location": { "details": { "relative_path": "crates/adapters/okx/src/parse.rs" } }
+            })),
+            test_bug(json!({
+                "bug_id": "B2", "severity": "CRITICAL", "status": "fixed",
+                "timestamp": "20250601_000000",
+                "file_location": { "details": { "relative_path": "crates/adapters/okx/src/lib.rs" } }
+            })),
+            test_bug(json!({
+                "bug_id": "B3", "severity": "LOW", "status": "open",
+                "timestamp": "20250901_000000",
+                "file_location": { "details": { "relative_path": "crates/adapters/bitmex/src/lib.rs" } }
+            })),
+        ];
+        let summary = json!({
+            "file_results": [
+                { "file_path": "crates/adapters/bitmex/src/lib.rs", "status": "bug_found" },
+                { "file_path": "crates/adapters/bitmex/src/http.rs", "status": "analysis_failed" }
+            ]
+        });
+        let churn = HashMap::from([("okx".to_string(), 4), ("bitmex".to_string(), 2)]);
+
+        let scored = score_adapters(&bugs, Some(&summary), &churn, now);
+        assert_eq!(scored[0].adapter, "okx");
+        // HIGH (5) × confirmed (1.5) × capped age (2.0) × full churn (1.5)
+        assert_eq!(scored[0].score, 22.5);
+        assert_eq!(scored[0].active_bugs, 1);
+        assert_eq!(scored[0].confirmed_bugs, 1);
+
+        let bitmex = &scored[1];
+        // LOW (1) × new (1.0) × half churn (1.25) + 10 × 0.5 failed
+        assert_eq!(bitmex.score, 6.25);
+        assert_eq!(bitmex.failed_share, 0.5);
+    }
+
+    #[test]
+    fn test_trend() {
+        let risk = AdapterRisk {
+            score: 7.5,
+            previous_score: Some(10.0),
+            ..Default::default()
+        };
+        assert_eq!(risk.trend(), Some(-2.5));
+    }
+
+    #[tokio::test]
+    async fn test_past_run_reads_persisted_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let summary =
+            json!({ "analysis_summary": { "run_id": "20250101_000000" }, "file_results": [] });
+        tokio::fs::write(
+            dir.path().join("analysis_summary_20250101_000000.json"),
+            summary.to_string(),
+        )
+        .await
+        .unwrap();
+        let persisted = serde_json::to_string_pretty(&RiskReport {
+            run_id: "20250101_000000".to_string(),
+            generated_at: "2025-01-01T00:00:00+00:00".to_string(),
+            churn_window_days: CHURN_WINDOW_DAYS,
+            adapters: vec![AdapterRisk {
+                adapter: "okx".to_string(),
+                score: 3.0,
+                ..Default::default()
+            }],
+        })
+        .unwrap();
+        let path = report_path(dir.path(), "20250101_000000");
+        tokio::fs::write(&path, &persisted).await.unwrap();
+
+        let report = load_or_compute(dir.path(), "20250101_000000")
+            .await
+            .unwrap();
+        assert_eq!(report.generated_at, "2025-01-01T00:00:00+00:00");
+        assert_eq!(report.adapters[0].score, 3.0);
+        assert_eq!(tokio::fs::read_to_string(&path).await.unwrap(), persisted);
+    }
+}
@@ -0,0 +1,480 @@
+//! Run-to-run diff of analysis summary snapshots
+//!
+//! Compares two `analysis_summary_*.json` files and reports newly flagged files,
+//! files that went clean, severity changes, new analysis failures and files the
+//! head run no longer analyzed. Results are keyed by their normalized relative
+//! path, plus the line range for partial analyses, so snapshots taken on
+//! different machines can be compared.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::repo_paths::to_repo_relative;
+
+/// File statuses that indicate the analysis itself did not complete
+const FAILURE_STATUSES: &[&str] = &[
+    "error",
+    "analysis_failed",
+    "skipped_no_client",
+    "bug_found_but_storage_failed",
+];
+
+/// Outcome of a single file within a run
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FileOutcome {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bug_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FileOutcome {
+    fn is_flagged(&self) -> bool {
+        self.status == "bug_found"
+    }
+
+    /// Whether the file was not actually analyzed
+    pub fn is_failure(&self) -> bool {
+        FAILURE_STATUSES.contains(&self.status.as_str())
+    }
+}
+
+/// Parsed analysis summary snapshot
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSnapshot {
+    pub run_id: String,
+    pub analysis_timestamp: Option<String>,
+    /// Outcomes keyed like analysis jobs: `path` or `path#L<start>-L<end>`
+    pub files: BTreeMap<String, FileOutcome>,
+}
+
+/// Key of a file result, matching the key of the analysis job that produced it
+fn result_key(file_path: &str, lines: Option<&str>) -> String {
+    let path = to_repo_relative(file_path);
+    match lines.and_then(|lines| lines.split_once('-')) {
+        Some((start, end)) => format!("{}#L{}-L{}", path, start, end),
+        None => path,
+    }
+}
+
+impl RunSnapshot {
+    /// Parse a snapshot from summary JSON
+    pub fn from_json(run_id: &str, value: &serde_json::Value) -> Self {
+        let analysis_timestamp = value["analysis_summary"]["analysis_timestamp"]
+            .as_str()
+            .map(|s| s.to_string());
+
+        let mut files = BTreeMap::new();
+        if let Some(results) = value["file_results"].as_array() {
+            for result in results {
+                let Some(file_path) = result["file_path"].as_str() else {
+                    continue;
+                };
+                let text = |key: &str| result[key].as_str().map(|s| s.to_string());
+                files.insert(
+                    result_key(file_path, result["lines"].as_str()),
+                    FileOutcome {
+                        status: text("status").unwrap_or_else(|| "unknown".to_string()),
+                        severity: text("severity").map(|s| s.to_uppercase()),
+                        bug_id: text("bug_id"),
+                        error: text("error").or_else(|| text("storage_error")),
+                    },
+                );
+            }
+        }
+
+        Self {
+            run_id: run_id.to_string(),
+            analysis_timestamp,
+            files,
+        }
+    }
+
+    /// Load a snapshot from a summary file
+    pub async fn load(path: &Path) -> Result<Self> {
+        let content = tokio::fs::read_to_string(path).await?;
+        let value: serde_json::Value = serde_json::from_str(&content)?;
+        let run_id = value["analysis_summary"]["run_id"]
+            .as_str()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| run_id_from_path(path));
+        Ok(Self::from_json(&run_id, &value))
+    }
+}
+
+/// Derive a run ID from a summary file name
+pub fn run_id_from_path(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .trim_start_matches("analysis_summary_")
+        .to_string()
+}
+
+/// List summary files in the bugs directory, oldest first
+pub fn list_summary_files(bugs_dir: &Path) -> Vec<PathBuf> {
+    let mut summaries: Vec<PathBuf> = std::fs::read_dir(bugs_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    name.starts_with("analysis_summary_") && name.ends_with(".json")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    summaries.sort();
+    summaries
+}
+
+/// Resolve a run reference to a summary file
+///
+/// Accepts a path to a summary file, a run ID such as `20250825_183509`, or the
+/// aliases `latest` and `previous`.
+pub fn resolve_summary(bugs_dir: &Path, reference: &str) -> Result<PathBuf> {
+    let as_path = Path::new(reference);
+    if as_path.is_file() {
+        return Ok(as_path.to_path_buf());
+    }
+
+    let summaries = list_summary_files(bugs_dir);
+    let resolved = match reference {
+        "latest" => summaries.last().cloned(),
+        "previous" => summaries.iter().rev().nth(1).cloned(),
+        run_id => summaries
+            .into_iter()
+            .find(|path| run_id_from_path(path) == run_id),
+    };
+
+    resolved.ok_or_else(|| {
+        anyhow!(
+            "No analysis summary found for '{}' in {}",
+            reference,
+            bugs_dir.display()
+        )
+    })
+}
+
+/// File whose status changed between runs
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FileChange {
+    /// Relative path, with the line range for partial analyses
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<FileOutcome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<FileOutcome>,
+}
+
+/// Differences between two analysis runs
+#[derive(Debug, Clone, Serialize)]
+pub struct RunDiff {
+    pub base_run: String,
+    pub head_run: String,
+    pub newly_flagged: Vec<FileChange>,
+    pub went_clean: Vec<FileChange>,
+    pub severity_changes: Vec<FileChange>,
+    pub new_failures: Vec<FileChange>,
+    /// Files analyzed in the base run but not in the head run
+    pub removed: Vec<FileChange>,
+}
+
+impl RunDiff {
+    /// Compare two snapshots
+    pub fn compute(base: &RunSnapshot, head: &RunSnapshot) -> Self {
+        let mut diff = Self {
+            base_run: base.run_id.clone(),
+            head_run: head.run_id.clone(),
+            newly_flagged: Vec::new(),
+            went_clean: Vec::new(),
+            severity_changes: Vec::new(),
+            new_failures: Vec::new(),
+            removed: Vec::new(),
+        };
+
+        for (path, after) in &head.files {
+            let before = base.files.get(path);
+            let change = FileChange {
+                path: path.clone(),
+                before: before.cloned(),
+                after: Some(after.clone()),
+            };
+
+            if after.is_flagged() {
+                match before {
+                    Some(before) if before.is_flagged() => {
+                        if before.severity != after.severity {
+                            diff.severity_changes.push(change);
+                        }
+                    }
+                    _ => diff.newly_flagged.push(change),
+                }
+            } else if after.status == "clean" {
+                if before.is_some_and(|b| b.is_flagged()) {
+                    diff.went_clean.push(change);
+                }
+            } else if after.is_failure() && !before.is_some_and(|b| b.is_failure()) {
+                diff.new_failures.push(change);
+            }
+        }
+
+        for (path, before) in &base.files {
+            if !head.files.contains_key(path) {
+                diff.removed.push(FileChange {
+                    path: path.clone(),
+                    before: Some(before.clone()),
+                    after: None,
+                });
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newly_flagged.is_empty()
+            && self.went_clean.is_empty()
+            && self.severity_changes.is_empty()
+            && self.new_failures.is_empty()
+            && self.removed.is_empty()
+    }
+
+    fn sections(&self) -> [(&'static str, &Vec<FileChange>); 5] {
+        [
+            ("Newly flagged files", &self.newly_flagged),
+            ("Files that went clean", &self.went_clean),
+            ("Severity changes", &self.severity_changes),
+            ("New failures", &self.new_failures),
+            ("Files no longer analyzed", &self.removed),
+        ]
+    }
+
+    /// Render the diff as JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Render the diff as a markdown report
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# Analysis run diff\n\n`{}` → `{}`\n\n| Change | Files |\n|---|---|\n",
+            self.base_run, self.head_run
+        );
+        for (title, changes) in self.sections() {
+            out.push_str(&format!("| {} | {} |\n", title, changes.len()));
+        }
+
+        for (title, changes) in self.sections() {
+            if changes.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n## {}\n\n", title));
+            for change in changes {
+                out.push_str(&format!(
+                    "- `{}`: {}\n",
+                    change.path,
+                    describe_change(change)
+                ));
+            }
+        }
+        out
+    }
+
+    /// Render the diff for the terminal
+    pub fn to_text(&self) -> String {
+        let mut out = format!(
+            "📊 Analysis run diff: {} → {}\n",
+            self.base_run, self.head_run
+        );
+        if self.is_empty() {
+            out.push_str("   No differences between runs\n");
+            return out;
+        }
+        for (title, changes) in self.sections() {
+            out.push_str(&format!("\n{} ({}):\n", title, changes.len()));
+            for change in changes {
+                out.push_str(&format!(
+                    "   • {} — {}\n",
+                    change.path,
+                    describe_change(change)
+                ));
+            }
+        }
+        out
+    }
+}
+
+fn describe_outcome(outcome: Option<&FileOutcome>) -> String {
+    match outcome {
+        None => "not analyzed".to_string(),
+        Some(outcome) => match &outcome.severity {
+            Some(severity) => format!("{} ({})", outcome.status, severity),
+            None => outcome.status.clone(),
+        },
+    }
+}
+
+fn describe_change(change: &FileChange) -> String {
+    format!(
+        "{} → {}",
+        describe_outcome(change.before.as_ref()),
+        describe_outcome(change.after.as_ref())
+    )
+}
+
+/// Diff two runs given as run IDs, aliases or summary file paths
+pub async fn diff_runs(bugs_dir: &Path, base: &str, head: &str) -> Result<RunDiff> {
+    let base = RunSnapshot::load(&resolve_summary(bugs_dir, base)?).await?;
+    let head = RunSnapshot::load(&resolve_summary(bugs_dir, head)?).await?;
+    Ok(RunDiff::compute(&base, &head))
+}
+
+/// Render a diff in the requested format: `json`, `markdown` or `text`
+pub fn render(diff: &RunDiff, format: &str) -> Result<String> {
+    match format {
+        "json" => diff.to_json(),
+        "markdown" | "md" => Ok(diff.to_markdown()),
+        "text" => Ok(diff.to_text()),
+        other => Err(anyhow!(
+            "Unsupported diff format '{}' (expected json, markdown or text)",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(run_id: &str, root: &str, results: serde_json::Value) -> RunSnapshot {
+        let results = results
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                let mut r = r.clone();
+                let path = format!("{}/{}", root, r["file_path"].as_str().unwrap());
+                r["file_path"] = serde_json::Value::String(path);
+                r
+            })
+            .collect::<Vec<_>>();
+        RunSnapshot::from_json(run_id, &serde_json::json!({ "file_results": results }))
+    }
+
+    #[test]
+    fn test_diff_across_machines() {
+        let base = snapshot(
+            "a",
+            "/Users/a/nautilus_trader/nautilus-trader-rig/..",
+            serde_json::json!([
+                {"file_path": "crates/adapters/okx/src/lib.rs", "status": "bug_found", "severity": "HIGH"},
+                {"file_path": "crates/adapters/okx/src/config.rs", "status": "clean"},
+                {"file_path": "crates/adapters/bitmex/src/lib.rs", "status": "bug_found", "severity": "LOW"},
+                {"file_path": "crates/adapters/bitmex/src/parse.rs", "status": "clean"},
+            ]),
+        );
+        let head = snapshot(
+            "b",
+            "/home/ci/work/nautilus_trader",
+            serde_json::json!([
+                {"file_path": "crates/adapters/okx/src/lib.rs", "status": "clean"},
+                {"file_path": "crates/adapters/okx/src/config.rs", "status": "bug_found", "severity": "MEDIUM"},
+                {"file_path": "crates/adapters/bitmex/src/lib.rs", "status": "bug_found", "severity": "CRITICAL"},
+                {"file_path": "crates/adapters/bitmex/src/parse.rs", "status": "analysis_failed", "error": "timeout"},
+            ]),
+        );
+
+        let diff = RunDiff::compute(&base, &head);
+        assert_eq!(diff.newly_flagged.len(), 1);
+        assert_eq!(
+            diff.newly_flagged[0].path,
+            "crates/adapters/okx/src/config.rs"
+        );
+        assert_eq!(diff.went_clean[0].path, "crates/adapters/okx/src/lib.rs");
+        assert_eq!(
+            diff.severity_changes[0].path,
+            "crates/adapters/bitmex/src/lib.rs"
+        );
+        assert_eq!(
+            diff.new_failures[0].path,
+            "crates/adapters/bitmex/src/parse.rs"
+        );
+    }
+
+    #[test]
+    fn test_line_ranges_of_one_file_are_kept_apart() {
+        let base = snapshot(
+            "a",
+            "/x",
+            serde_json::json!([
+                {"file_path": "crates/adapters/okx/src/parse.rs", "lines": "1-200", "status": "clean"},
+                {"file_path": "crates/adapters/okx/src/parse.rs", "lines": "201-400", "status": "clean"},
+            ]),
+        );
+        let head = snapshot(
+            "b",
+            "/y",
+            serde_json::json!([
+                {"file_path": "crates/adapters/okx/src/parse.rs", "lines": "1-200", "status": "bug_found", "severity": "HIGH"},
+                {"file_path": "crates/adapters/okx/src/parse.rs", "lines": "201-400", "status": "clean"},
+            ]),
+        );
+        assert_eq!(base.files.len(), 2);
+
+        let diff = RunDiff::compute(&base, &head);
+        assert_eq!(diff.newly_flagged.len(), 1);
+        assert_eq!(
+            diff.newly_flagged[0].path,
+            "crates/adapters/okx/src/parse.rs#L1-L200"
+        );
+        assert!(diff.removed.is_empty());
+    }
+
+    #[test]
+    fn test_files_missing_from_head_are_reported_removed() {
+        let base = snapshot(
+            "a",
+            "/x",
+            serde_json::json!([
+                {"file_path": "crates/adapters/okx/src/lib.rs", "status": "clean"},
+                {"file_path": "crates/adapters/okx/src/legacy.rs", "status": "bug_found", "severity": "HIGH"},
+            ]),
+        );
+        let head = snapshot(
+            "b",
+            "/y",
+            serde_json::json!([
+                {"file_path": "crates/adapters/okx/src/lib.rs", "status": "clean"},
+            ]),
+        );
+
+        let diff = RunDiff::compute(&base, &head);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].path, "crates/adapters/okx/src/legacy.rs");
+        assert!(diff.removed[0].after.is_none());
+        assert!(diff.went_clean.is_empty());
+        assert!(diff
+            .to_markdown()
+            .contains("| Files no longer analyzed | 1 |"));
+    }
+
+    #[test]
+    fn test_identical_runs_have_empty_diff() {
+        let results = serde_json::json!([
+            {"file_path": "crates/adapters/okx/src/lib.rs", "status": "bug_found", "severity": "HIGH"},
+        ]);
+        let base = snapshot("a", "/x", results.clone());
+        let head = snapshot("b", "/y", results);
+        let diff = RunDiff::compute(&base, &head);
+        assert!(diff.is_empty());
+        assert!(diff.to_text().contains("No differences"));
+    }
+}
@@ -0,0 +1,280 @@
+//! SARIF 2.1.0 export of stored bugs
+//!
+//! Converts the bug records in the bugs directory into a SARIF log so findings
+//! can be viewed in standard code-scanning viewers and IDE SARIF plugins.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::bug_store::{self, StoredBug, CATEGORY_KEYWORDS, DEFAULT_CATEGORY};
+use crate::repo_paths;
+
+/// SARIF schema location
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// SARIF version emitted by the exporter
+pub const SARIF_VERSION: &str = "2.1.0";
+
+/// Base URI identifier for repository-relative artifact locations
+const REPO_ROOT_BASE_ID: &str = "REPO_ROOT";
+
+/// Key under which bug fingerprints are reported in `partialFingerprints`
+const FINGERPRINT_KEY: &str = "nautilusTraderRigFingerprint/v1";
+
+/// Map a bug severity to a SARIF result level
+pub fn severity_to_level(severity: &str) -> &'static str {
+    match bug_store::severity_rank(severity) {
+        4 | 3 => "error",
+        2 => "warning",
+        1 => "note",
+        _ => "none",
+    }
+}
+
+/// Rule ID for a bug: an explicit `rule_id` field, or one derived from its category
+pub fn rule_id(bug: &StoredBug) -> String {
+    bug.extra
+        .get("rule_id")
+        .and_then(|r| r.as_str())
+        .map(|r| r.to_string())
+        .unwrap_or_else(|| format!("NTR-{}", bug.category()))
+}
+
+fn category_description(category: &str) -> String {
+    CATEGORY_KEYWORDS
+        .iter()
+        .find(|(known, _)| *known == category)
+        .map(|(_, keywords)| {
+            format!(
+                "Potential {} issue ({})",
+                category.to_lowercase().replace('_', " "),
+                keywords.join(", ")
+            )
+        })
+        .unwrap_or_else(|| {
+            format!(
+                "Potential {} issue",
+                category.to_lowercase().replace('_', " ")
+            )
+        })
+}
+
+fn rule(id: &str, bugs: &[&StoredBug]) -> Value {
+    let category = bugs
+        .first()
+        .map(|bug| bug.category())
+        .unwrap_or(DEFAULT_CATEGORY);
+    let level = bugs
+        .iter()
+        .max_by_key(|bug| bug.severity_rank())
+        .map(|bug| severity_to_level(&bug.severity))
+        .unwrap_or("warning");
+
+    json!({
+        "id": id,
+        "name": category
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_string() + &chars.as_str().to_lowercase(),
+                    None => String::new(),
+                }
+            })
+            .collect::<String>(),
+        "shortDescription": { "text": category_description(category) },
+        "defaultConfiguration": { "level": level },
+        "properties": { "tags": ["nautilus-trader", category.to_lowercase()] }
+    })
+}
+
+fn result(bug: &StoredBug, rule_index: usize) -> Value {
+    let mut result = json!({
+        "ruleId": rule_id(bug),
+        "ruleIndex": rule_index,
+        "level": severity_to_level(&bug.severity),
+        "message": { "text": bug.description },
+        "partialFingerprints": { FINGERPRINT_KEY: bug.effective_fingerprint() },
+        "properties": {
+            "bugId": bug.bug_id,
+            "severity": bug.severity,
+            "adapter": bug.adapter(),
+            "status": bug.status,
+            "category": bug.category()
+        }
+    });
+
+    if let Some(path) = bug.source_path() {
+        let uri = repo_paths::to_repo_relative(path);
+        let mut physical_location = json!({
+            "artifactLocation": { "uri": uri, "uriBaseId": REPO_ROOT_BASE_ID }
+        });
+        // A region needs a start; without a known line the location is the whole file
+        if let Some(line) = bug.line_number() {
+            let mut region = json!({ "startLine": line });
+            if let Some(sample) = bug.code_sample.as_deref().filter(|s| !s.trim().is_empty()) {
+                region["snippet"] = json!({ "text": sample });
+            }
+            physical_location["region"] = region;
+        }
+        result["locations"] = json!([{ "physicalLocation": physical_location }]);
+
+        if let Some(fix) = bug
+            .fix_suggestion
+            .as_deref()
+            .filter(|s| !s.trim().is_empty())
+        {
+            // Fix suggestions are prose, so the change itself is an empty insertion
+            // that anchors the description at the bug location.
+            let deleted_region = match bug.line_number() {
+                Some(line) => json!({ "startLine": line, "startColumn": 1, "endColumn": 1 }),
+                None => json!({ "charOffset": 0, "charLength": 0 }),
+            };
+            result["fixes"] = json!([{
+                "description": { "text": fix },
+                "artifactChanges": [{
+                    "artifactLocation": { "uri": uri, "uriBaseId": REPO_ROOT_BASE_ID },
+                    "replacements": [{ "deletedRegion": deleted_region }]
+                }]
+            }]);
+        }
+    }
+
+    result
+}
+
+/// Build a SARIF log from stored bugs
+pub fn to_sarif(bugs: &[StoredBug]) -> Value {
+    let mut grouped: BTreeMap<String, Vec<&StoredBug>> = BTreeMap::new();
+    for bug in bugs {
+        grouped.entry(rule_id(bug)).or_default().push(bug);
+    }
+
+    let rule_ids: Vec<&String> = grouped.keys().collect();
+    let rules: Vec<Value> = grouped.iter().map(|(id, bugs)| rule(id, bugs)).collect();
+    let results: Vec<Value> = bugs
+        .iter()
+        .map(|bug| {
+            let id = rule_id(bug);
+            let index = rule_ids.iter().position(|r| **r == id).unwrap_or(0);
+            result(bug, index)
+        })
+        .collect();
+
+    let mut run = json!({
+        "tool": {
+            "driver": {
+                "name": "nautilus-trader-rig",
+                "version": env!("CARGO_PKG_VERSION"),
+                "rules": rules
+            }
+        },
+        "results": results
+    });
+    if let Some(root) = repo_paths::repo_root() {
+        let mut uri = format!("file://{}", root.display());
+        if !uri.ends_with('/') {
+            uri.push('/');
+        }
+        run["originalUriBaseIds"] = json!({ REPO_ROOT_BASE_ID: { "uri": uri } });
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [run]
+    })
+}
+
+/// Export bugs from the bugs directory as a SARIF log
+///
+/// Only open and regressed bugs are exported unless `include_fixed` is set.
+pub async fn export_bugs(bugs_dir: &Path, include_fixed: bool) -> Result<Value> {
+    let bugs: Vec<StoredBug> = bug_store::load_bugs(bugs_dir)
+        .await?
+        .into_iter()
+        .map(|(_, bug)| bug)
+        .filter(|bug| include_fixed || bug.status.is_active())
+        .collect();
+    Ok(to_sarif(&bugs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bug_store::test_bug;
+
+    #[test]
+    fn test_severity_to_level() {
+        assert_eq!(severity_to_level("CRITICAL"), "error");
+        assert_eq!(severity_to_level("high"), "error");
+        assert_eq!(severity_to_level("MEDIUM"), "warning");
+        assert_eq!(severity_to_level("LOW"), "note");
+    }
+
+    #[test]
+    fn test_sarif_log_structure() {
+        let bugs = vec![
+            test_bug(json!({
+                "bug_id": "B1",
+                "severity": "CRITICAL",
+                "description": "Precision loss converting prices to f64",
+                "fix_suggestion": "Use fixed-point Price types",
+                "code_sample": "let px = raw as f64;",
+                "file_location": {
+                    "details": { "relative_path": "crates/adapters/okx/src/parse.rs" },
+                    "source_location": { "approximate_line_number": 42 }
+                }
+            })),
+            test_bug(json!({
+                "bug_id": "B2",
+                "severity": "LOW",
+                "description": "Something else entirely"
+            })),
+            test_bug(json!({
+                "bug_id": "B3",
+                "severity": "MEDIUM",
+                "description": "Something else again",
+                "code_sample": "let qty = raw.qty;",
+                "file_location": { "details": { "relative_path": "crates/adapters/okx/src/parse.rs" } }
+            })),
+        ];
+
+        let log = to_sarif(&bugs);
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+
+        let first = &run["results"][0];
+        assert_eq!(first["ruleId"], "NTR-PRECISION");
+        assert_eq!(first["level"], "error");
+        let location = &first["locations"][0]["physicalLocation"];
+        assert_eq!(
+            location["artifactLocation"]["uri"],
+            "crates/adapters/okx/src/parse.rs"
+        );
+        assert_eq!(location["region"]["startLine"], 42);
+        assert_eq!(
+            first["fixes"][0]["description"]["text"],
+            "Use fixed-point Price types"
+        );
+        assert!(first["partialFingerprints"][FINGERPRINT_KEY].is_string());
+
+        let rule_index = first["ruleIndex"].as_u64().unwrap() as usize;
+        assert_eq!(
+            run["tool"]["driver"]["rules"][rule_index]["id"],
+            "NTR-PRECISION"
+        );
+        assert!(run["results"][1].get("locations").is_none());
+
+        // Without a line the file is still located, but no region lacking a start is emitted
+        let unplaced = &run["results"][2]["locations"][0]["physicalLocation"];
+        assert_eq!(
+            unplaced["artifactLocation"]["uri"],
+            "crates/adapters/okx/src/parse.rs"
+        );
+        assert!(unplaced.get("region").is_none());
+    }
+}
@@ -0,0 +1,514 @@
+//! Scheduled analysis jobs for Nautilus Trader Rig
+//!
+//! Full sweeps, diff sweeps of changed files and re-verification of open bugs run
+//! on cron schedules through apalis-cron. Only one analysis runs at a time: a tick
+//! that fires while another job is still running is recorded as skipped. Job state
+//! and history persist in `jobs/scheduler_state.json`, and runs a crashed process
+//! left `running` are marked `interrupted` at the next start.
+
+use anyhow::{anyhow, Result};
+use apalis::prelude::*;
+use apalis::utils::TokioExecutor;
+use apalis_cron::{CronStream, Schedule};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+use crate::bug_store;
+use crate::config::Config;
+use crate::job_queue::JobQueue;
+use crate::repo_paths;
+
+/// Number of job runs kept in the persisted history
+const HISTORY_LIMIT: usize = 200;
+
+/// Kind of scheduled analysis job
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Analyze every adapter file
+    FullSweep,
+    /// Analyze files modified since the last successful sweep
+    DiffSweep,
+    /// Re-analyze the source files of open and regressed bugs
+    Reverify,
+}
+
+impl JobKind {
+    pub const ALL: [JobKind; 3] = [JobKind::FullSweep, JobKind::DiffSweep, JobKind::Reverify];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::FullSweep => "full_sweep",
+            JobKind::DiffSweep => "diff_sweep",
+            JobKind::Reverify => "reverify",
+        }
+    }
+
+    #[cfg(test)]
+    fn default_schedule(&self) -> &'static str {
+        match self {
+            JobKind::FullSweep => Config::DEFAULT_FULL_SWEEP_SCHEDULE,
+            JobKind::DiffSweep => Config::DEFAULT_DIFF_SWEEP_SCHEDULE,
+            JobKind::Reverify => Config::DEFAULT_REVERIFY_SCHEDULE,
+        }
+    }
+
+    /// Configured cron expression, or `None` when the job is disabled
+    pub fn schedule(&self) -> Option<String> {
+        let expression = Config::settings().schedule_of(*self).trim();
+        if expression.is_empty() || expression.eq_ignore_ascii_case("off") {
+            None
+        } else {
+            Some(expression.to_string())
+        }
+    }
+}
+
+impl std::fmt::Display for JobKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse a cron expression, naming the job in the error
+pub fn parse_schedule(kind: JobKind, expression: &str) -> Result<Schedule> {
+    Schedule::from_str(expression)
+        .map_err(|e| anyhow!("Invalid cron schedule '{}' for {}: {}", expression, kind, e))
+}
+
+/// Outcome of a job run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    SkippedOverlap,
+    Interrupted,
+}
+
+/// A single run of a scheduled job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRun {
+    pub id: String,
+    pub kind: JobKind,
+    /// What started the run: `cron` or `startup`
+    pub trigger: String,
+    pub started_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    pub status: RunStatus,
+    /// Number of files selected, absent for full sweeps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files_selected: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Persisted scheduler state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchedulerState {
+    /// Start time of the last successful run per job kind
+    #[serde(default)]
+    pub last_success: BTreeMap<String, String>,
+    /// Job runs, oldest first
+    #[serde(default)]
+    pub history: Vec<JobRun>,
+}
+
+impl SchedulerState {
+    /// Load the state file, starting empty when it is missing or unreadable
+    pub async fn load(path: &Path) -> Self {
+        match tokio::fs::read_to_string(path).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!(
+                    "Ignoring unreadable scheduler state {}: {}",
+                    path.display(),
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// Mark runs left `running` by a previous process as interrupted
+    pub fn mark_interrupted(&mut self) -> usize {
+        let mut interrupted = 0;
+        for run in self
+            .history
+            .iter_mut()
+            .filter(|run| run.status == RunStatus::Running)
+        {
+            run.status = RunStatus::Interrupted;
+            interrupted += 1;
+        }
+        interrupted
+    }
+
+    fn push(&mut self, run: JobRun) {
+        self.history.push(run);
+        if self.history.len() > HISTORY_LIMIT {
+            let excess = self.history.len() - HISTORY_LIMIT;
+            self.history.drain(..excess);
+        }
+    }
+
+    fn finish(
+        &mut self,
+        id: &str,
+        status: RunStatus,
+        files_selected: Option<usize>,
+        error: Option<String>,
+    ) {
+        let Some(run) = self.history.iter_mut().rev().find(|run| run.id == id) else {
+            return;
+        };
+        run.finished_at = Some(Utc::now().to_rfc3339());
+        run.files_selected = files_selected;
+        run.error = error;
+        if status == RunStatus::Succeeded {
+            self.last_success
+                .insert(run.kind.as_str().to_string(), run.started_at.clone());
+        }
+        run.status = status;
+    }
+
+    /// Start time of the last successful run of a job kind
+    pub fn last_success_of(&self, kind: JobKind) -> Option<DateTime<Utc>> {
+        self.last_success
+            .get(kind.as_str())
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    /// Scheduler overview for MCP: schedules, last successes and recent runs, newest first
+    pub fn overview(&self, kind: Option<JobKind>, limit: usize) -> serde_json::Value {
+        let schedules: BTreeMap<&str, String> = JobKind::ALL
+            .iter()
+            .map(|k| {
+                (
+                    k.as_str(),
+                    k.schedule().unwrap_or_else(|| "off".to_string()),
+                )
+            })
+            .collect();
+        let runs: Vec<&JobRun> = self
+            .history
+            .iter()
+            .rev()
+            .filter(|run| !kind.is_some_and(|k| k != run.kind))
+            .take(limit)
+            .collect();
+        serde_json::json!({
+            "schedules": schedules,
+            "last_success": self.last_success,
+            "runs": runs,
+        })
+    }
+}
+
+/// Path of the persisted scheduler state
+pub fn state_path() -> PathBuf {
+    Config::jobs_directory_path().join("scheduler_state.json")
+}
+
+/// Runs analysis jobs one at a time and records their outcome
+pub struct Scheduler {
+    queue: &'static JobQueue,
+    state: Mutex<SchedulerState>,
+    run_lock: Mutex<()>,
+    state_path: PathBuf,
+}
+
+impl Scheduler {
+    pub async fn new(queue: &'static JobQueue) -> Result<Arc<Self>> {
+        let state_path = state_path();
+        let mut state = SchedulerState::load(&state_path).await;
+        let interrupted = state.mark_interrupted();
+        if interrupted > 0 {
+            warn!(
+                "⚠️ Marked {} job runs from a previous process as interrupted",
+                interrupted
+            );
+            state.save(&state_path).await?;
+        }
+
+        Ok(Arc::new(Self {
+            queue,
+            state: Mutex::new(state),
+            run_lock: Mutex::new(()),
+            state_path,
+        }))
+    }
+
+    async fn update<F: FnOnce(&mut SchedulerState)>(&self, f: F) {
+        let mut state = self.state.lock().await;
+        f(&mut state);
+        if let Err(e) = state.save(&self.state_path).await {
+            error!("Failed to save scheduler state: {}", e);
+        }
+    }
+
+    /// Run a job unless another one is already running
+    pub async fn run_job(&self, kind: JobKind, trigger: &str) -> Result<RunStatus> {
+        let run = JobRun {
+            id: format!("{}_{}", kind, Utc::now().format("%Y%m%d_%H%M%S")),
+            kind,
+            trigger: trigger.to_string(),
+            started_at: Utc::now().to_rfc3339(),
+            finished_at: None,
+            status: RunStatus::Running,
+            files_selected: None,
+            error: None,
+        };
+
+        let Ok(_guard) = self.run_lock.try_lock() else {
+            info!(
+                "⏭️ Skipping {} ({}): another analysis job is running",
+                kind, trigger
+            );
+            self.update(|state| {
+                state.push(JobRun {
+                    finished_at: Some(run.started_at.clone()),
+                    status: RunStatus::SkippedOverlap,
+                    ..run
+                })
+            })
+            .await;
+            return Ok(RunStatus::SkippedOverlap);
+        };
+
+        info!("⏰ Starting scheduled {} ({})", kind, trigger);
+        let id = run.id.clone();
+        self.update(|state| state.push(run)).await;
+
+        let selection = self.select_files(kind).await;
+        let files_selected = selection
+            .as_ref()
+            .ok()
+            .and_then(|s| s.as_ref().map(HashSet::len));
+        let outcome = match selection {
+            Ok(Some(files)) if files.is_empty() => {
+                info!("No files selected for {}, nothing to analyze", kind);
+                Ok(())
+            }
+            Ok(selection) => crate::analyze_adapter_files_for_bugs(self.queue, selection.as_ref())
+                .await
+                .map(|_| ()),
+            Err(e) => Err(e),
+        };
+
+        let status = match &outcome {
+            Ok(()) => RunStatus::Succeeded,
+            Err(_) => RunStatus::Failed,
+        };
+        let error = outcome.as_ref().err().map(|e| e.to_string());
+        match &error {
+            Some(e) => error!("❌ Scheduled {} failed: {}", kind, e),
+            None => info!("✅ Scheduled {} completed", kind),
+        }
+        self.update(|state| state.finish(&id, status.clone(), files_selected, error))
+            .await;
+        Ok(status)
+    }
+
+    /// Files a job should analyze as repository-relative paths, or `None` for all files
+    async fn select_files(&self, kind: JobKind) -> Result<Option<HashSet<String>>> {
+        match kind {
+            JobKind::FullSweep => Ok(None),
+            JobKind::DiffSweep => {
+                let since = {
+                    let state = self.state.lock().await;
+                    [JobKind::FullSweep, JobKind::DiffSweep]
+                        .iter()
+                        .filter_map(|k| state.last_success_of(*k))
+                        .max()
+                };
+                let Some(since) = since else {
+                    info!("No previous successful sweep, diff sweep analyzes every file");
+                    return Ok(None);
+                };
+
+                let mut changed = HashSet::new();
+                for file in crate::discover_rust_files(Config::adapter_roots()).await? {
+                    let modified = tokio::fs::metadata(&file)
+                        .await
+                        .ok()
+                        .and_then(|m| m.modified().ok())
+                        .map(DateTime::<Utc>::from);
+                    if modified.is_some_and(|m| m > since) {
+                        changed.insert(repo_paths::to_repo_relative(&file));
+                    }
+                }
+                debug!(
+                    "Diff sweep selected {} files changed since {}",
+                    changed.len(),
+                    since
+                );
+                Ok(Some(changed))
+            }
+            JobKind::Reverify => {
+                let files: HashSet<String> = bug_store::load_bugs(&Config::bugs_directory_path())
+                    .await?
+                    .into_iter()
+                    .filter(|(_, bug)| bug.status.is_active())
+                    .filter_map(|(_, bug)| bug.source_path().map(repo_paths::to_repo_relative))
+                    .collect();
+                debug!(
+                    "Re-verification selected {} files with open bugs",
+                    files.len()
+                );
+                Ok(Some(files))
+            }
+        }
+    }
+}
+
+/// Cron tick delivered to the scheduled job workers
+#[derive(Debug, Clone, Default)]
+pub struct Tick(DateTime<Utc>);
+
+impl From<DateTime<Utc>> for Tick {
+    fn from(t: DateTime<Utc>) -> Self {
+        Tick(t)
+    }
+}
+
+impl Job for Tick {
+    const NAME: &'static str = "nautilus_trader_rig::scheduled_analysis";
+}
+
+#[derive(Clone)]
+struct TickContext {
+    scheduler: Arc<Scheduler>,
+    kind: JobKind,
+}
+
+async fn on_tick(tick: Tick, ctx: Data<TickContext>) {
+    debug!("Cron tick for {} at {}", ctx.kind, tick.0);
+    if let Err(e) = ctx.scheduler.run_job(ctx.kind, "cron").await {
+        error!("❌ Scheduled {} could not run: {}", ctx.kind, e);
+    }
+}
+
+/// Run the scheduler until the process is stopped
+///
+/// Jobs enqueue their files on the analysis queue and wait for the run to finish.
+/// A full sweep runs at startup when none has ever succeeded.
+pub async fn run(queue: &'static JobQueue) -> Result<()> {
+    let scheduler = Scheduler::new(queue).await?;
+
+    let mut monitor = Monitor::<TokioExecutor>::new();
+    let mut registered = 0;
+    for kind in JobKind::ALL {
+        let Some(expression) = kind.schedule() else {
+            info!("Scheduled {} is disabled", kind);
+            continue;
+        };
+        let schedule = parse_schedule(kind, &expression)?;
+        info!("📅 Scheduled {} with cron '{}'", kind, expression);
+        println!("   📅 {}: {}", kind, expression);
+
+        let worker = WorkerBuilder::new(format!("rig-{}", kind))
+            .data(TickContext {
+                scheduler: scheduler.clone(),
+                kind,
+            })
+            .stream(CronStream::new(schedule).into_stream())
+            .build_fn(on_tick);
+        monitor = monitor.register(worker);
+        registered += 1;
+    }
+
+    let needs_initial_sweep = scheduler
+        .state
+        .lock()
+        .await
+        .last_success_of(JobKind::FullSweep)
+        .is_none();
+    if needs_initial_sweep {
+        let scheduler = scheduler.clone();
+        tokio::spawn(async move {
+            if let Err(e) = scheduler.run_job(JobKind::FullSweep, "startup").await {
+                error!("❌ Initial full sweep could not run: {}", e);
+            }
+        });
+    }
+
+    if registered == 0 {
+        warn!("⚠️ All scheduled jobs are disabled");
+        tokio::signal::ctrl_c().await?;
+        return Ok(());
+    }
+    monitor.run().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(id: &str, status: RunStatus) -> JobRun {
+        JobRun {
+            id: id.to_string(),
+            kind: JobKind::DiffSweep,
+            trigger: "cron".to_string(),
+            started_at: "2025-08-25T18:35:09+00:00".to_string(),
+            finished_at: None,
+            status,
+            files_selected: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_default_schedules_parse() {
+        for kind in JobKind::ALL {
+            assert!(
+                parse_schedule(kind, kind.default_schedule()).is_ok(),
+                "{}",
+                kind
+            );
+        }
+        assert!(parse_schedule(JobKind::FullSweep, "every day").is_err());
+    }
+
+    #[test]
+    fn test_interrupted_runs_and_success() {
+        let mut state = SchedulerState::default();
+        state.push(run("a", RunStatus::Running));
+        state.push(run("b", RunStatus::Running));
+        assert_eq!(state.mark_interrupted(), 2);
+
+        state.push(run("c", RunStatus::Running));
+        state.finish("c", RunStatus::Succeeded, Some(3), None);
+        assert_eq!(state.history[2].status, RunStatus::Succeeded);
+        assert!(state.last_success_of(JobKind::DiffSweep).is_some());
+        assert!(state.last_success_of(JobKind::FullSweep).is_none());
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut state = SchedulerState::default();
+        for i in 0..HISTORY_LIMIT + 5 {
+            state.push(run(&i.to_string(), RunStatus::Succeeded));
+        }
+        assert_eq!(state.history.len(), HISTORY_LIMIT);
+        assert_eq!(state.history[0].id, "5");
+    }
+}
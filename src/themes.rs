@@ -0,0 +1,314 @@
+//! Theme discovery across stored bugs
+//!
+//! Individual bugs often share a root cause across adapters, such as every decoder
+//! parsing prices through `f64`. Active bugs are clustered by embedding similarity
+//! and each cluster becomes a theme: an LLM-written label and summary, the bugs it
+//! contains and the adapters it affects. Fixing a theme fixes a class of bugs at once.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use tracing::warn;
+
+use crate::bug_index;
+use crate::bug_store::{BugFilter, StoredBug};
+use crate::clustering;
+use crate::config::Config;
+use crate::deepseek::{extract_json_object, DeepSeekClient};
+
+/// Average cosine similarity at which bugs count as one theme; looser than pattern learning
+pub const THEME_THRESHOLD: f64 = 0.7;
+
+/// Smallest cluster reported as a theme
+pub const MIN_THEME_SIZE: usize = 2;
+
+/// System prompt of the theme labeller
+const LABELLER_PREAMBLE: &str =
+    "You are a lead engineer reviewing bugs across trading system adapters. Given a group of \
+     related bugs, you name the shared root cause in terms an engineer can act on across every \
+     adapter at once.";
+
+/// Characters of each bug description included in the labelling prompt
+const PROMPT_DESCRIPTION_CHARS: usize = 400;
+
+/// Which bugs to cluster and how tightly
+#[derive(Debug, Clone)]
+pub struct ThemeOptions {
+    pub filter: BugFilter,
+    pub threshold: f64,
+    pub min_size: usize,
+}
+
+impl Default for ThemeOptions {
+    fn default() -> Self {
+        Self {
+            filter: BugFilter {
+                status: Some("active".to_string()),
+                ..Default::default()
+            },
+            threshold: THEME_THRESHOLD,
+            min_size: MIN_THEME_SIZE,
+        }
+    }
+}
+
+/// Label fields the LLM is asked for
+#[derive(Debug, Deserialize)]
+struct ThemeLabel {
+    label: String,
+    #[serde(default)]
+    summary: String,
+}
+
+/// One cluster of related bugs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Theme {
+    pub label: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub summary: String,
+    pub adapters: Vec<String>,
+    pub categories: Vec<String>,
+    /// Highest severity among the bugs
+    pub severity: String,
+    pub bug_ids: Vec<String>,
+}
+
+/// Themes found among the bugs matching a filter, largest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemeReport {
+    pub generated_at: DateTime<Utc>,
+    pub bugs: usize,
+    pub threshold: f64,
+    pub themes: Vec<Theme>,
+    /// Bugs that share no theme with another bug
+    pub unclustered: usize,
+}
+
+impl ThemeReport {
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# Bug themes\n\n{} themes across {} bugs ({} unclustered, similarity ≥ {:.2}), generated {}\n",
+            self.themes.len(),
+            self.bugs,
+            self.unclustered,
+            self.threshold,
+            self.generated_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        for (i, theme) in self.themes.iter().enumerate() {
+            let _ = write!(
+                out,
+                "\n## {}. {}\n\n- Bugs: {}\n- Adapters: {}\n- Categories: {}\n- Highest severity: {}\n",
+                i + 1,
+                theme.label,
+                theme.bug_ids.len(),
+                theme.adapters.join(", "),
+                theme.categories.join(", "),
+                theme.severity
+            );
+            if !theme.summary.is_empty() {
+                let _ = writeln!(out, "\n{}", theme.summary);
+            }
+            let _ = writeln!(
+                out,
+                "\n{}",
+                theme
+                    .bug_ids
+                    .iter()
+                    .map(|id| format!("`{}`", id))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+        if self.themes.is_empty() {
+            out.push_str("\nNo bugs are similar enough to form a theme.\n");
+        }
+        out
+    }
+}
+
+/// Prompt asking the LLM to name the shared root cause of `bugs`
+pub fn labelling_prompt(bugs: &[StoredBug]) -> String {
+    let mut prompt = format!(
+        "These {} bugs were found in Nautilus Trader exchange adapters and were grouped because they look \
+         alike. Name the systemic problem they share so it can be fixed across adapters at once.\n",
+        bugs.len()
+    );
+    for bug in bugs {
+        let description: String = bug
+            .description
+            .trim()
+            .chars()
+            .take(PROMPT_DESCRIPTION_CHARS)
+            .collect();
+        let _ = write!(
+            prompt,
+            "\n- [{}, {}] {}",
+            bug.adapter(),
+            bug.severity,
+            description
+        );
+    }
+    prompt.push_str(
+        "\n\nReply with a JSON object with these fields:\n\
+         - label: the shared problem in at most 12 words, e.g. \"Decoders parse prices through f64\"\n\
+         - summary: two or three sentences on the root cause and how to fix every instance\n",
+    );
+    prompt
+}
+
+fn parse_label(reply: &str) -> Result<ThemeLabel> {
+    let label: ThemeLabel = serde_json::from_str(extract_json_object(reply)?)?;
+    if label.label.trim().is_empty() {
+        bail!("reply has an empty label");
+    }
+    Ok(label)
+}
+
+/// Theme for `bugs` with a label made from their categories, used when the LLM is unavailable
+pub fn build_theme(bugs: &[StoredBug]) -> Theme {
+    let adapters: BTreeSet<String> = bugs.iter().map(StoredBug::adapter).collect();
+    let categories: BTreeSet<&str> = bugs.iter().map(StoredBug::category).collect();
+    let severity = bugs
+        .iter()
+        .max_by_key(|bug| bug.severity_rank())
+        .map(|bug| bug.severity.to_uppercase())
+        .unwrap_or_default();
+    let categories: Vec<String> = categories.into_iter().map(str::to_string).collect();
+    Theme {
+        label: format!(
+            "{} bugs in {} adapters",
+            categories.join("/"),
+            adapters.len()
+        ),
+        summary: String::new(),
+        adapters: adapters.into_iter().collect(),
+        categories,
+        severity,
+        bug_ids: bugs.iter().map(|bug| bug.bug_id.clone()).collect(),
+    }
+}
+
+/// Group `bugs` into clusters of at least `min_size`, largest first
+pub fn cluster_bugs(
+    bugs: &[(StoredBug, Vec<f64>)],
+    threshold: f64,
+    min_size: usize,
+) -> Vec<Vec<StoredBug>> {
+    let vectors: Vec<Vec<f64>> = bugs.iter().map(|(_, vector)| vector.clone()).collect();
+    clustering::agglomerative(&vectors, threshold)
+        .into_iter()
+        .filter(|cluster| cluster.len() >= min_size.max(1))
+        .map(|cluster| {
+            let mut members: Vec<StoredBug> = cluster.iter().map(|&i| bugs[i].0.clone()).collect();
+            members.sort_by(|a, b| a.bug_id.cmp(&b.bug_id));
+            members
+        })
+        .collect()
+}
+
+/// Cluster the indexed bugs matching `options` and label each theme
+///
+/// Without a client, or when the LLM reply cannot be used, themes keep the label
+/// built from their categories.
+pub async fn discover_themes(
+    client: Option<&DeepSeekClient>,
+    options: &ThemeOptions,
+) -> Result<ThemeReport> {
+    let index = bug_index::shared().await?;
+    index
+        .write()
+        .await
+        .sync(&Config::bugs_directory_path())
+        .await?;
+    let mut bugs = index.read().await.vectors(&options.filter);
+    bugs.sort_by(|a, b| a.0.bug_id.cmp(&b.0.bug_id));
+
+    let clusters = cluster_bugs(&bugs, options.threshold, options.min_size);
+    let mut themes = Vec::with_capacity(clusters.len());
+    for members in clusters {
+        let mut theme = build_theme(&members);
+        if let Some(client) = client {
+            let label = async {
+                parse_label(
+                    &client
+                        .complete_json(LABELLER_PREAMBLE, &labelling_prompt(&members))
+                        .await?,
+                )
+            }
+            .await;
+            match label {
+                Ok(label) => {
+                    theme.label = label.label.trim().to_string();
+                    theme.summary = label.summary.trim().to_string();
+                }
+                Err(e) => warn!(
+                    "Failed to label theme of {}: {:#}",
+                    theme.bug_ids.join(", "),
+                    e
+                ),
+            }
+        }
+        themes.push(theme);
+    }
+
+    let clustered: usize = themes.iter().map(|theme| theme.bug_ids.len()).sum();
+    Ok(ThemeReport {
+        generated_at: Utc::now(),
+        bugs: bugs.len(),
+        threshold: options.threshold,
+        unclustered: bugs.len() - clustered,
+        themes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bug_store::test_bug;
+    use serde_json::json;
+
+    #[test]
+    fn test_themes_span_adapters() {
+        let bugs = vec![
+            (
+                test_bug(
+                    json!({"bug_id": "b1", "adapter_name": "okx", "severity": "HIGH",
+                                "description": "Price decoded through f64 loses precision"}),
+                ),
+                vec![1.0, 0.1],
+            ),
+            (
+                test_bug(
+                    json!({"bug_id": "b2", "adapter_name": "bybit", "severity": "CRITICAL",
+                                "description": "Quantity parsed as f64 before conversion"}),
+                ),
+                vec![1.0, 0.1],
+            ),
+            (
+                test_bug(
+                    json!({"bug_id": "b3", "adapter_name": "okx", "severity": "LOW",
+                                "description": "Reconnect loop never backs off"}),
+                ),
+                vec![0.0, 1.0],
+            ),
+        ];
+        let clusters = cluster_bugs(&bugs, THEME_THRESHOLD, MIN_THEME_SIZE);
+        assert_eq!(clusters.len(), 1);
+
+        let theme = build_theme(&clusters[0]);
+        assert_eq!(theme.bug_ids, vec!["b1", "b2"]);
+        assert_eq!(theme.adapters, vec!["bybit", "okx"]);
+        assert_eq!(theme.severity, "CRITICAL");
+        assert!(theme.label.ends_with("in 2 adapters"), "{}", theme.label);
+    }
+
+    #[test]
+    fn test_parse_label() {
+        let label = parse_label("Sure:\n```json\n{\"label\": \"Prices parsed via f64\", \"summary\": \"Use Price::from_str.\"}\n```").unwrap();
+        assert_eq!(label.label, "Prices parsed via f64");
+        assert!(parse_label("{\"label\": \" \"}").is_err());
+        assert!(parse_label("no json").is_err());
+    }
+}
@@ -1,26 +1,29 @@
 //! Vector Store Manager for bug pattern similarity search using FastEmbed
-//! 
+//!
 //! This module uses FastEmbed for local embeddings (no API key required) with in-memory or SQLite storage.
-//! Embeddings are generated locally using the AllMiniLML6V2 model.
+//! Embeddings are generated locally using the model configured in `embedding.model`.
+//! Searches fuse the vector ranking with a keyword ranking of the same patterns; see `hybrid`.
 
 use anyhow::Result;
-use std::path::Path;
-use tracing::{debug, info, trace};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::{OnceCell, RwLock};
+use tracing::{debug, info, trace, warn};
 
-use fastembed::{
-    EmbeddingModel as FastembedModel, Pooling, TextEmbedding as FastembedTextEmbedding,
-    TokenizerFiles, UserDefinedEmbeddingModel, read_file_to_bytes,
-};
 use rig::{
-    Embed,
-    embeddings::EmbeddingsBuilder,
+    embeddings::Embedding,
     vector_store::{
-        VectorStoreIndex, in_memory_store::InMemoryVectorStore, request::VectorSearchRequest,
+        in_memory_store::InMemoryVectorStore, request::VectorSearchRequest, VectorStoreIndex,
     },
+    Embed, OneOrMany,
 };
 use rig_fastembed::EmbeddingModel;
 
+use crate::batch_embed;
+use crate::config::Config;
+use crate::hybrid::{self, LexicalIndex, ScoreBreakdown, SearchMode};
+use crate::index_snapshot;
+
 // Bug pattern document that will be embedded and searched
 #[derive(Embed, Clone, Deserialize, Debug, Serialize, Eq, PartialEq, Default)]
 pub struct BugPatternDocument {
@@ -30,12 +33,148 @@ pub struct BugPatternDocument {
     // The content field will be used to generate embeddings
     #[embed]
     pub content: String,
+    /// Adapter the pattern was seen in; built-in patterns apply to every adapter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adapter: Option<String>,
+    /// Lifecycle status, e.g. active or retired
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Query-time filters of a pattern search; unset fields match every pattern
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PatternFilter {
+    /// Category (case-insensitive), e.g. WEBSOCKET
+    pub category: Option<String>,
+    pub min_severity: Option<u8>,
+    /// Adapter name (case-insensitive substring); only patterns tied to an adapter match
+    pub adapter: Option<String>,
+    /// Status (case-insensitive); only patterns with a status match
+    pub status: Option<String>,
+    /// First and last creation day, inclusive; only dated patterns match
+    pub since: Option<chrono::NaiveDate>,
+    pub until: Option<chrono::NaiveDate>,
+    /// Minimum cosine similarity of every returned hit, keyword matches included
+    pub min_score: Option<f64>,
+}
+
+impl PatternFilter {
+    /// Whether a pattern passes every metadata filter that is set
+    pub fn matches(&self, pattern: &BugPatternDocument) -> bool {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_lowercase)
+        };
+
+        if let Some(category) = non_empty(&self.category) {
+            if pattern.category.to_lowercase() != category {
+                return false;
+            }
+        }
+        if self.min_severity.is_some_and(|min| pattern.severity < min) {
+            return false;
+        }
+        if let Some(adapter) = non_empty(&self.adapter) {
+            if !pattern
+                .adapter
+                .as_deref()
+                .is_some_and(|a| a.to_lowercase().contains(&adapter))
+            {
+                return false;
+            }
+        }
+        if let Some(status) = non_empty(&self.status) {
+            if !pattern
+                .status
+                .as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case(&status))
+            {
+                return false;
+            }
+        }
+        if self.since.is_some() || self.until.is_some() {
+            let Some(day) = pattern.created_at.map(|t| t.date_naive()) else {
+                return false;
+            };
+            if self.since.is_some_and(|since| day < since)
+                || self.until.is_some_and(|until| day > until)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Parse a `YYYY-MM-DD` day, or the day of an RFC 3339 timestamp, for the date filters
+pub fn parse_filter_date(value: &str) -> Result<chrono::NaiveDate> {
+    let value = value.trim();
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| {
+            chrono::DateTime::parse_from_rfc3339(value)
+                .map(|t| t.with_timezone(&chrono::Utc).date_naive())
+        })
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "invalid date '{}'; use YYYY-MM-DD or an RFC 3339 timestamp",
+                value
+            )
+        })
+}
+
+/// Patterns of the catalog in `paths.patterns_dir`, as indexed documents
+pub fn catalog_patterns() -> Result<Vec<BugPatternDocument>> {
+    let catalog = crate::pattern_catalog::load_catalog(&Config::patterns_directory_path())?;
+    Ok(catalog.iter().map(|entry| entry.to_document()).collect())
+}
+
+/// Pattern index shared by the analysis pipeline, CLI commands and every MCP session
+static SHARED: OnceCell<RwLock<VectorStoreManager>> = OnceCell::const_new();
+
+/// Shared pattern index, built on first use; a failed build is retried by the next caller
+pub async fn shared() -> Result<&'static RwLock<VectorStoreManager>> {
+    SHARED
+        .get_or_try_init(|| async { VectorStoreManager::new().await.map(RwLock::new) })
+        .await
+}
+
+/// Shared pattern index if it has already been built
+pub fn loaded() -> Option<&'static RwLock<VectorStoreManager>> {
+    SHARED.get()
+}
+
+/// Re-index a catalog pattern after it was added or changed; a no-op until the index is built
+pub async fn index_pattern(pattern: BugPatternDocument) -> Result<()> {
+    if let Some(store) = loaded() {
+        store.write().await.add_pattern(pattern).await?;
+    }
+    Ok(())
+}
+
+/// Drop a deleted catalog pattern from the shared index, if it is built
+pub async fn unindex_pattern(pattern_id: &str) -> Result<()> {
+    if let Some(store) = loaded() {
+        store.write().await.remove_pattern(pattern_id)?;
+    }
+    Ok(())
 }
 
 pub struct VectorStoreManager {
     pub vector_store: InMemoryVectorStore<BugPatternDocument>,
     pub embedding_model: EmbeddingModel,
     pub documents: Vec<BugPatternDocument>,
+    /// Keyword index over pattern IDs, categories and content
+    pub lexical: LexicalIndex,
+}
+
+/// Text of a pattern in the keyword index
+fn lexical_text(pattern: &BugPatternDocument) -> String {
+    format!("{} {} {}", pattern.id, pattern.category, pattern.content)
 }
 
 impl VectorStoreManager {
@@ -49,225 +188,275 @@ impl VectorStoreManager {
         let embedding_model = Self::create_embedding_model().await?;
         info!("✅ FastEmbed embedding model created successfully");
 
+        let manager = Self::with_model(embedding_model).await?;
+        info!("✅ Vector store initialized successfully with FastEmbed");
+        Ok(manager)
+    }
+
+    /// Vector store over the pattern catalog embedded with `embedding_model`
+    pub async fn with_model(embedding_model: EmbeddingModel) -> Result<Self> {
         debug!("Initializing in-memory vector store");
         let mut manager = Self {
             vector_store: InMemoryVectorStore::default(),
             embedding_model,
             documents: Vec::new(),
+            lexical: LexicalIndex::new()?,
         };
 
-        // Initialize with default patterns
-        debug!("Loading default bug patterns into vector store");
-        manager.initialize_default_patterns().await?;
-
-        info!("✅ Vector store initialized successfully with FastEmbed");
-        debug!("Vector store ready with {} patterns loaded", manager.documents.len());
+        debug!("Loading the pattern catalog into vector store");
+        manager.load_catalog().await?;
+        debug!(
+            "Vector store ready with {} patterns loaded",
+            manager.documents.len()
+        );
         Ok(manager)
     }
 
-    /// Create embedding model - try local files first, then auto-download
+    /// Process-wide embedding model, loaded from the configured bundle on first use
     async fn create_embedding_model() -> Result<EmbeddingModel> {
-        let model_dir = Path::new("./models/Qdrant--all-MiniLM-L6-v2-onnx/snapshots");
-        
-        if model_dir.exists() {
-            info!("Loading FastEmbed model from local directory: {model_dir:?}");
-            Self::load_local_model(model_dir).await
-        } else {
-            info!("Local model not found, using automatic download");
-            Self::load_auto_model().await
-        }
+        crate::models::shared_embedding_model().await
     }
 
-    /// Load model from local files
-    async fn load_local_model(model_dir: &Path) -> Result<EmbeddingModel> {
-        // Get model info
-        let test_model_info = FastembedTextEmbedding::get_model_info(&FastembedModel::AllMiniLML6V2)?;
+    /// Embed every pattern of the catalog
+    async fn load_catalog(&mut self) -> Result<()> {
+        let patterns = catalog_patterns()?;
+        if patterns.is_empty() {
+            warn!(
+                "⚠️ Pattern catalog {} has no patterns; similarity search will find nothing",
+                Config::patterns_directory_path().display()
+            );
+            return Ok(());
+        }
 
-        // Load model files
-        let onnx_file = read_file_to_bytes(&model_dir.join("model.onnx"))?;
+        // Reuse imported embeddings of unchanged patterns made by this model and embed the rest
+        let cache = index_snapshot::load_cache();
+        let mut embeddings = Vec::with_capacity(patterns.len());
+        let mut missing = Vec::new();
+        for pattern in &patterns {
+            match cache.pattern_embedding(&self.embedding_model.model, pattern) {
+                Some(embedding) => embeddings.push((pattern.clone(), embedding)),
+                None => missing.push(pattern.clone()),
+            }
+        }
+        if !embeddings.is_empty() {
+            debug!("Reusing {} cached pattern embeddings", embeddings.len());
+        }
+        if !missing.is_empty() {
+            embeddings.extend(batch_embed::embed_configured(&self.embedding_model, missing).await?);
+        }
 
-        let tokenizer_files = TokenizerFiles {
-            tokenizer_file: read_file_to_bytes(&model_dir.join("tokenizer.json"))?,
-            config_file: read_file_to_bytes(&model_dir.join("config.json"))?,
-            special_tokens_map_file: read_file_to_bytes(&model_dir.join("special_tokens_map.json"))?,
-            tokenizer_config_file: read_file_to_bytes(&model_dir.join("tokenizer_config.json"))?,
-        };
+        // Create vector store from documents
+        self.vector_store =
+            InMemoryVectorStore::from_documents_with_id_f(embeddings, |doc| doc.id.clone());
 
-        // Create embedding model
-        let user_defined_model = UserDefinedEmbeddingModel::new(onnx_file, tokenizer_files)
-            .with_pooling(Pooling::Mean);
+        for pattern in &patterns {
+            self.lexical.upsert(&pattern.id, &lexical_text(pattern))?;
+        }
+        self.documents = patterns;
 
-        let embedding_model = EmbeddingModel::new_from_user_defined(
-            user_defined_model, 
-            384, // Dimension for AllMiniLML6V2
-            test_model_info
+        info!(
+            "Added {} catalog bug patterns to vector store with FastEmbed",
+            self.documents.len()
         );
-
-        Ok(embedding_model)
-    }
-
-    /// Load model with automatic download
-    async fn load_auto_model() -> Result<EmbeddingModel> {
-        // Create init options for the FastEmbed model
-        let init_options = fastembed::InitOptions::new(FastembedModel::AllMiniLML6V2);
-        
-        // First create the FastEmbed model instance  
-        let _fastembed_model = FastembedTextEmbedding::try_new(init_options)?;
-        
-        // Get the model info 
-        let model_info = FastembedTextEmbedding::get_model_info(&FastembedModel::AllMiniLML6V2)?;
-        
-        // Create the rig embedding model - use reference to the model enum, not the instance
-        let embedding_model = EmbeddingModel::new(&FastembedModel::AllMiniLML6V2, model_info.dim);
-        Ok(embedding_model)
+        Ok(())
     }
 
-    /// Initialize with default bug patterns
-    async fn initialize_default_patterns(&mut self) -> Result<()> {
-        let default_patterns = vec![
-            BugPatternDocument {
-                id: "auth_bypass_001".to_string(),
-                category: "AUTHENTICATION".to_string(),
-                severity: 9,
-                content: "Authentication bypass vulnerability in trading adapters: API key validation missing, weak token verification, credential exposure in logs, unauthorized access to trading functions".to_string(),
-            },
-            BugPatternDocument {
-                id: "rate_limit_bypass_001".to_string(),
-                category: "RATE_LIMITING".to_string(),
-                severity: 8,
-                content: "Rate limiting bypass in exchange adapters: Multiple connection exploitation, request queue overflow, throttling mechanism failure, DoS attack vectors".to_string(),
-            },
-            BugPatternDocument {
-                id: "websocket_security_001".to_string(),
-                category: "WEBSOCKET".to_string(),
-                severity: 7,
-                content: "WebSocket security vulnerabilities: Unvalidated message injection, connection hijacking, authentication after connect, message replay attacks".to_string(),
-            },
-            BugPatternDocument {
-                id: "order_execution_race_001".to_string(),
-                category: "EXECUTION".to_string(),
-                severity: 9,
-                content: "Order execution race conditions: Concurrent order processing, double execution vulnerability, state synchronization issues, atomic operation failures".to_string(),
-            },
-            BugPatternDocument {
-                id: "data_validation_001".to_string(),
-                category: "VALIDATION".to_string(),
-                severity: 6,
-                content: "Market data validation failures: Unvalidated price feeds, malformed data processing, type conversion vulnerabilities, data integrity checks missing".to_string(),
-            },
-            BugPatternDocument {
-                id: "connection_pool_001".to_string(),
-                category: "CONNECTION".to_string(),
-                severity: 5,
-                content: "Connection pool issues: Pool exhaustion, resource leaks, timeout handling, connection state management, cleanup failures".to_string(),
-            },
-            BugPatternDocument {
-                id: "memory_leak_001".to_string(),
-                category: "MEMORY".to_string(),
-                severity: 7,
-                content: "Memory management issues: Memory leaks in adapters, resource cleanup failures, garbage collection problems, buffer overflows".to_string(),
-            },
-            BugPatternDocument {
-                id: "performance_degradation_001".to_string(),
-                category: "PERFORMANCE".to_string(),
-                severity: 6,
-                content: "Performance issues: Slow response times, high latency, CPU bottlenecks, inefficient algorithms, blocking operations".to_string(),
-            },
-            BugPatternDocument {
-                id: "error_handling_001".to_string(),
-                category: "ERROR_HANDLING".to_string(),
-                severity: 8,
-                content: "Error handling problems: Unhandled exceptions, silent failures, improper error propagation, missing error logging".to_string(),
-            },
-            BugPatternDocument {
-                id: "configuration_001".to_string(),
-                category: "CONFIGURATION".to_string(),
-                severity: 7,
-                content: "Configuration vulnerabilities: Hardcoded secrets, missing environment variables, insecure defaults, configuration injection".to_string(),
-            },
-        ];
-
-        // Create embeddings using EmbeddingsBuilder
-        let embeddings = EmbeddingsBuilder::new(self.embedding_model.clone())
-            .documents(default_patterns.clone())?
-            .build()
-            .await?;
-
-        // Create vector store from documents
-        self.vector_store = InMemoryVectorStore::from_documents_with_id_f(
-            embeddings, 
-            |doc| doc.id.clone()
+    /// Patterns passing `filter` that best match `query` by fused vector and keyword rank
+    ///
+    /// `score` is the cosine similarity to the query and `rank_score` the fused or re-rank
+    /// score the order is based on; `scores` breaks it down by component.
+    pub async fn similarity_search(
+        &self,
+        query: &str,
+        filter: &PatternFilter,
+        limit: usize,
+    ) -> Result<Vec<serde_json::Value>> {
+        debug!(
+            "Performing similarity search for: '{}' with {:?}",
+            query, filter
         );
+        let ranked = self
+            .ranked_search(query, filter, limit, SearchMode::Hybrid, true)
+            .await?;
+        let pattern = |id: &str| self.documents.iter().find(|p| p.id == id);
+        let results = ranked
+            .into_iter()
+            .filter_map(|(id, scores)| {
+                let doc = pattern(&id)?;
+                Some(serde_json::json!({
+                    "id": doc.id,
+                    "content": doc.content,
+                    "category": doc.category,
+                    "severity": doc.severity,
+                    "adapter": doc.adapter,
+                    "status": doc.status,
+                    "created_at": doc.created_at,
+                    "score": scores.vector_score.unwrap_or_default(),
+                    "rank_score": scores.score(),
+                    "scores": scores
+                }))
+            })
+            .collect::<Vec<_>>();
 
-        self.documents = default_patterns;
-        
-        info!("Added {} default bug patterns to vector store with FastEmbed", self.documents.len());
-        Ok(())
+        debug!("Found {} similar patterns", results.len());
+        Ok(results)
     }
 
-    /// Perform similarity search using FastEmbed
-    pub async fn similarity_search(&self, query: &str, limit: usize) -> Result<Vec<serde_json::Value>> {
-        debug!("Performing similarity search for: '{}'", query);
+    /// IDs of the patterns passing `filter` that best match `query` under `mode`, best first
+    ///
+    /// `rerank` applies the configured cross-encoder, if any, to the candidates.
+    pub async fn ranked_search(
+        &self,
+        query: &str,
+        filter: &PatternFilter,
+        limit: usize,
+        mode: SearchMode,
+        rerank: bool,
+    ) -> Result<Vec<(String, ScoreBreakdown)>> {
+        let candidates: Vec<(BugPatternDocument, OneOrMany<Embedding>)> = self
+            .vector_store
+            .iter()
+            .filter(|(_, (doc, _))| filter.matches(doc))
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        if candidates.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let pool = Config::settings().embedding.search_candidates.max(limit);
+        let index_size = candidates.len();
 
-        // Create index for searching
-        let index = self.vector_store.clone().index(self.embedding_model.clone());
+        // Create an index over the patterns passing the filter
+        let index = InMemoryVectorStore::from_documents_with_id_f(candidates, |doc| doc.id.clone())
+            .index(self.embedding_model.clone());
 
+        // Score every candidate so keyword-only hits report their similarity too
         let req = VectorSearchRequest::builder()
             .query(query)
-            .samples(limit as u64)
+            .samples(index_size as u64)
             .build()?;
 
-        // Query the index
-        let results = index
+        // Query both indexes and fuse the rankings
+        let pattern = |id: &str| self.documents.iter().find(|p| p.id == id);
+        let mut vector: Vec<(String, f64)> = index
             .top_n::<BugPatternDocument>(req)
             .await?
             .into_iter()
-            .map(|(score, _id, doc)| {
-                serde_json::json!({
-                    "id": doc.id,
-                    "content": doc.content,
-                    "category": doc.category,
-                    "severity": doc.severity,
-                    "score": score
-                })
-            })
-            .collect::<Vec<_>>();
-
-        debug!("Found {} similar patterns", results.len());
-        Ok(results)
+            .map(|(score, _id, doc)| (doc.id, score))
+            .collect();
+        let similarity: HashMap<String, f64> = vector.iter().cloned().collect();
+        vector.truncate(pool);
+        let lexical: Vec<(String, f64)> = self
+            .lexical
+            .search(query, self.documents.len())?
+            .into_iter()
+            .filter(|(id, _)| pattern(id).is_some_and(|p| filter.matches(p)))
+            .take(pool)
+            .collect();
+        let mut fused = mode.fuse(&vector, &lexical);
+        hybrid::fill_similarity(&mut fused, &similarity);
+        if let Some(min_score) = filter.min_score {
+            fused.retain(|(_, scores)| scores.vector_score.is_some_and(|v| v >= min_score));
+        }
+        fused.truncate(pool);
+
+        if rerank {
+            let texts = fused
+                .iter()
+                .map(|(id, _)| pattern(id).map(|p| p.content.clone()).unwrap_or_default())
+                .collect();
+            hybrid::rerank(query, &mut fused, texts).await?;
+        }
+        fused.truncate(limit);
+        Ok(fused)
     }
 
-    /// Add a new bug pattern to the vector store using FastEmbed
+    /// Add a bug pattern to the vector store using FastEmbed, replacing one with the same ID
     pub async fn add_pattern(&mut self, pattern: BugPatternDocument) -> Result<()> {
         // Create embeddings for the new pattern
-        let embeddings = EmbeddingsBuilder::new(self.embedding_model.clone())
-            .documents(vec![pattern.clone()])?
-            .build()
-            .await?;
+        let embeddings =
+            batch_embed::embed_configured(&self.embedding_model, vec![pattern.clone()]).await?;
 
         // Add to vector store
-        self.vector_store.add_documents_with_id_f(embeddings, |doc| doc.id.clone());
+        self.vector_store
+            .add_documents_with_id_f(embeddings, |doc| doc.id.clone());
+        self.lexical.upsert(&pattern.id, &lexical_text(&pattern))?;
+        self.documents.retain(|p| p.id != pattern.id);
+        self.documents.push(pattern);
+
+        Ok(())
+    }
+
+    /// Add a pattern with an embedding computed elsewhere, replacing one with the same ID
+    pub fn add_embedded(&mut self, pattern: BugPatternDocument, vec: Vec<f64>) -> Result<()> {
+        let embedding = OneOrMany::one(Embedding {
+            document: pattern.content.clone(),
+            vec,
+        });
+        self.vector_store
+            .add_documents_with_id_f(vec![(pattern.clone(), embedding)], |doc| doc.id.clone());
+        self.lexical.upsert(&pattern.id, &lexical_text(&pattern))?;
+        self.documents.retain(|p| p.id != pattern.id);
         self.documents.push(pattern);
-        
         Ok(())
     }
 
+    /// Every indexed pattern with its embedding vector, in ID order
+    pub fn embedded(&self) -> Vec<(BugPatternDocument, Vec<f64>)> {
+        let mut embedded: Vec<(BugPatternDocument, Vec<f64>)> = self
+            .vector_store
+            .iter()
+            .map(|(_, (doc, embedding))| (doc.clone(), embedding.first().vec))
+            .collect();
+        embedded.sort_by(|a, b| a.0.id.cmp(&b.0.id));
+        embedded
+    }
+
+    /// Remove a bug pattern from the vector store; returns whether it was indexed
+    pub fn remove_pattern(&mut self, pattern_id: &str) -> Result<bool> {
+        let before = self.documents.len();
+        self.documents.retain(|p| p.id != pattern_id);
+        if self.documents.len() == before {
+            return Ok(false);
+        }
+
+        let remaining: Vec<(BugPatternDocument, OneOrMany<Embedding>)> = self
+            .vector_store
+            .iter()
+            .filter(|(id, _)| id.as_str() != pattern_id)
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        self.vector_store =
+            InMemoryVectorStore::from_documents_with_id_f(remaining, |doc| doc.id.clone());
+        self.lexical.remove(pattern_id)?;
+        Ok(true)
+    }
+
     /// Get pattern by ID
     pub async fn get_pattern_by_id(&self, pattern_id: &str) -> Result<Option<BugPatternDocument>> {
         Ok(self.documents.iter().find(|p| p.id == pattern_id).cloned())
     }
 
     /// Get all patterns in a specific category
-    pub async fn get_patterns_by_category(&self, category: &str) -> Result<Vec<BugPatternDocument>> {
-        Ok(self.documents.iter()
+    pub async fn get_patterns_by_category(
+        &self,
+        category: &str,
+    ) -> Result<Vec<BugPatternDocument>> {
+        Ok(self
+            .documents
+            .iter()
             .filter(|p| p.category == category)
             .cloned()
             .collect())
     }
 
     /// Get patterns by severity level
-    pub async fn get_patterns_by_severity(&self, min_severity: u8) -> Result<Vec<BugPatternDocument>> {
-        Ok(self.documents.iter()
+    pub async fn get_patterns_by_severity(
+        &self,
+        min_severity: u8,
+    ) -> Result<Vec<BugPatternDocument>> {
+        Ok(self
+            .documents
+            .iter()
             .filter(|p| p.severity >= min_severity)
             .cloned()
             .collect())
@@ -282,43 +471,127 @@ mod tests {
     async fn test_vector_store_creation() {
         // FastEmbed doesn't require API keys - runs locally
         let manager = VectorStoreManager::new().await.unwrap();
-        let results = manager.similarity_search("authentication", 3).await.unwrap();
+        let results = manager
+            .similarity_search("authentication", &PatternFilter::default(), 3)
+            .await
+            .unwrap();
         assert!(!results.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_keyword_matches_are_fused_into_results() {
+        let mut manager = VectorStoreManager::new().await.unwrap();
+        manager
+            .add_pattern(BugPatternDocument {
+                id: "databento_imbalance_001".to_string(),
+                category: "FFI".to_string(),
+                severity: 6,
+                content: "DatabentoImbalance side decoded from c_char without validating the byte"
+                    .to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let results = manager
+            .similarity_search("DatabentoImbalance c_char", &PatternFilter::default(), 3)
+            .await
+            .unwrap();
+        assert_eq!(results[0]["id"], "databento_imbalance_001");
+        assert_eq!(results[0]["scores"]["lexical_rank"], 1);
+        assert!(results[0]["scores"]["vector_score"].is_number());
+    }
+
+    #[tokio::test]
+    async fn test_shared_store_is_built_once() {
+        let first = shared().await.unwrap();
+        let second = shared().await.unwrap();
+        assert!(std::ptr::eq(first, second));
+        assert!(std::ptr::eq(loaded().unwrap(), first));
+    }
+
     #[tokio::test]
     async fn test_add_pattern() {
         // FastEmbed doesn't require API keys - runs locally
         let mut manager = VectorStoreManager::new().await.unwrap();
-        
+
         let new_pattern = BugPatternDocument {
             id: "test_pattern_001".to_string(),
             category: "TEST".to_string(),
             severity: 5,
-            content: "Test pattern for unit testing: This is a test vulnerability pattern".to_string(),
+            content: "Test pattern for unit testing: This is a test vulnerability pattern"
+                .to_string(),
+            ..Default::default()
         };
 
-        manager.add_pattern(new_pattern).await.unwrap();
+        manager.add_pattern(new_pattern.clone()).await.unwrap();
+        let count = manager.documents.len();
+
+        // Re-adding an ID replaces the pattern; removing drops it from both indexes
+        manager
+            .add_pattern(BugPatternDocument {
+                severity: 7,
+                ..new_pattern
+            })
+            .await
+            .unwrap();
+        assert_eq!(manager.documents.len(), count);
+        assert_eq!(
+            manager
+                .get_pattern_by_id("test_pattern_001")
+                .await
+                .unwrap()
+                .unwrap()
+                .severity,
+            7
+        );
+        assert!(manager.remove_pattern("test_pattern_001").unwrap());
+        assert!(!manager.remove_pattern("test_pattern_001").unwrap());
+        assert_eq!(manager.vector_store.len(), count - 1);
+        assert!(manager
+            .lexical
+            .search("test_pattern_001", 5)
+            .unwrap()
+            .is_empty());
     }
 
     #[tokio::test]
     async fn test_similarity_search() {
         let manager = VectorStoreManager::new().await.unwrap();
-        
+
         // Test searching for authentication-related issues
-        let results = manager.similarity_search("API key security authentication", 3).await.unwrap();
+        let results = manager
+            .similarity_search(
+                "API key security authentication",
+                &PatternFilter::default(),
+                3,
+            )
+            .await
+            .unwrap();
         assert!(!results.is_empty());
-        
+
         // The first result should be related to authentication
         let first_result = &results[0];
-        assert!(first_result["category"].as_str().unwrap_or("").contains("AUTHENTICATION") || 
-                first_result["content"].as_str().unwrap_or("").to_lowercase().contains("authentication"));
+        assert!(
+            first_result["category"]
+                .as_str()
+                .unwrap_or("")
+                .contains("AUTHENTICATION")
+                || first_result["content"]
+                    .as_str()
+                    .unwrap_or("")
+                    .to_lowercase()
+                    .contains("authentication")
+        );
     }
 
     #[tokio::test]
     async fn test_category_filtering() {
         let manager = VectorStoreManager::new().await.unwrap();
-        let auth_patterns = manager.get_patterns_by_category("AUTHENTICATION").await.unwrap();
+        let auth_patterns = manager
+            .get_patterns_by_category("AUTHENTICATION")
+            .await
+            .unwrap();
         assert!(!auth_patterns.is_empty());
     }
 
@@ -328,4 +601,108 @@ mod tests {
         let critical_patterns = manager.get_patterns_by_severity(8).await.unwrap();
         assert!(!critical_patterns.is_empty());
     }
+
+    #[test]
+    fn test_pattern_filter_matches_metadata() {
+        let pattern = BugPatternDocument {
+            id: "okx_precision_001".to_string(),
+            category: "VALIDATION".to_string(),
+            severity: 7,
+            adapter: Some("okx".to_string()),
+            status: Some("active".to_string()),
+            created_at: Some("2025-03-10T12:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        let day = |value: &str| Some(parse_filter_date(value).unwrap());
+
+        assert!(PatternFilter::default().matches(&pattern));
+        let filter = PatternFilter {
+            category: Some("validation".to_string()),
+            min_severity: Some(7),
+            adapter: Some("OK".to_string()),
+            status: Some("Active".to_string()),
+            since: day("2025-03-10"),
+            until: day("2025-03-10T23:00:00+00:00"),
+            min_score: None,
+        };
+        assert!(filter.matches(&pattern));
+        assert!(!PatternFilter {
+            min_severity: Some(8),
+            ..filter.clone()
+        }
+        .matches(&pattern));
+        assert!(!PatternFilter {
+            since: day("2025-03-11"),
+            ..filter.clone()
+        }
+        .matches(&pattern));
+        assert!(!PatternFilter {
+            adapter: Some("bybit".to_string()),
+            ..filter.clone()
+        }
+        .matches(&pattern));
+
+        // Patterns without an adapter, status or date only pass filters that ignore them
+        let built_in = &BugPatternDocument {
+            id: "generic_001".to_string(),
+            ..Default::default()
+        };
+        assert!(!PatternFilter {
+            adapter: Some("okx".to_string()),
+            ..Default::default()
+        }
+        .matches(built_in));
+        assert!(!PatternFilter {
+            until: day("2030-01-01"),
+            ..Default::default()
+        }
+        .matches(built_in));
+        assert!(parse_filter_date("10/03/2025").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_similarity_search_applies_filters() {
+        let manager = VectorStoreManager::new().await.unwrap();
+        let websocket = PatternFilter {
+            category: Some("WEBSOCKET".to_string()),
+            ..Default::default()
+        };
+        let results = manager
+            .similarity_search("authentication", &websocket, 5)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["category"], "WEBSOCKET");
+
+        let severe = PatternFilter {
+            min_severity: Some(9),
+            ..Default::default()
+        };
+        let results = manager
+            .similarity_search("memory leak", &severe, 10)
+            .await
+            .unwrap();
+        assert!(results.iter().all(|r| r["severity"].as_u64().unwrap() >= 9));
+
+        // Keyword matches must clear the similarity threshold too
+        let keyword_hits = manager
+            .similarity_search("latency", &PatternFilter::default(), 10)
+            .await
+            .unwrap();
+        assert!(keyword_hits
+            .iter()
+            .any(|r| r["scores"]["lexical_rank"].is_number()));
+        assert!(keyword_hits
+            .iter()
+            .all(|r| r["score"] == r["scores"]["vector_score"] && r["rank_score"].is_number()));
+        let strict = PatternFilter {
+            min_score: Some(1.01),
+            ..Default::default()
+        };
+        assert!(manager
+            .similarity_search("latency", &strict, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
 }